@@ -0,0 +1,59 @@
+use crate::grid::{Grid, Point};
+
+// One step of a simulation drawn as text, with a caption saying what
+// happened.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Frame {
+    pub caption: String,
+    pub lines: Vec<String>,
+}
+
+// The frames of a simulation. They are produced lazily, so the simulation only
+// runs as far as someone watches.
+pub type Frames<'a> = Box<dyn Iterator<Item = Frame> + 'a>;
+
+impl Frame {
+    pub fn new(caption: impl Into<String>, lines: Vec<String>) -> Self {
+        Frame {
+            caption: caption.into(),
+            lines,
+        }
+    }
+
+    // Draws a grid with one character per cell.
+    pub fn grid<T>(
+        caption: impl Into<String>,
+        grid: &Grid<T>,
+        draw: impl Fn(Point, &T) -> char,
+    ) -> Self {
+        let lines = grid
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(x, c)| draw(Point::new(x, y), c))
+                    .collect()
+            })
+            .collect();
+        Frame::new(caption, lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_test() {
+        let grid = Grid::new(vec![vec![1, 0, 1], vec![0, 1, 0]]);
+        let frame = Frame::grid("step 1", &grid, |p, &c| match (p, c) {
+            (Point { x: 0, y: 0 }, _) => '@',
+            (_, 1) => '#',
+            _ => '.',
+        });
+        assert_eq!(frame.caption, "step 1");
+        assert_eq!(frame.lines, ["@.#", ".#."]);
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+// The answer to a problem. Answers compare numerically, so `Unsigned(5)`,
+// `Signed(5)` and `Text("5")` are all equal.
+#[derive(Clone, Debug)]
+pub enum Answer {
+    Unsigned(u64),
+    Signed(i64),
+    BigInt(i128),
+    Text(String),
+    // The part has no answer, like the second part of day 25
+    None,
+}
+
+impl Answer {
+    // Returns the answer as an integer if it is one or is text holding one.
+    pub fn as_int(&self) -> Option<i128> {
+        match self {
+            Self::Unsigned(x) => Some(*x as i128),
+            Self::Signed(x) => Some(*x as i128),
+            Self::BigInt(x) => Some(*x),
+            Self::Text(s) => s.trim().parse().ok(),
+            Self::None => None,
+        }
+    }
+}
+
+impl PartialEq for Answer {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.as_int(), other.as_int()) {
+            (Some(a), Some(b)) => a == b,
+            (Some(_), None) | (None, Some(_)) => false,
+            (None, None) => match (self, other) {
+                (Self::Text(a), Self::Text(b)) => a == b,
+                (Self::None, Self::None) => true,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Eq for Answer {}
+
+impl PartialEq<&str> for Answer {
+    fn eq(&self, other: &&str) -> bool {
+        *self == Answer::Text(other.to_string())
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsigned(x) => write!(f, "{}", x),
+            Self::Signed(x) => write!(f, "{}", x),
+            Self::BigInt(x) => write!(f, "{}", x),
+            Self::Text(s) => f.write_str(s),
+            Self::None => f.write_str("(none)"),
+        }
+    }
+}
+
+macro_rules! from_int {
+    ($variant:ident: $($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(x: $t) -> Self {
+                    Answer::$variant(x as _)
+                }
+            }
+        )*
+    };
+}
+
+from_int!(Unsigned: u8, u16, u32, u64, usize);
+from_int!(Signed: i8, i16, i32, i64, isize);
+from_int!(BigInt: i128);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_owned())
+    }
+}
+
+// Integers that fit in 64 bits are written as JSON numbers. Big integers are
+// written as strings since most JSON readers cannot represent them.
+impl Serialize for Answer {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Unsigned(x) => s.serialize_u64(*x),
+            Self::Signed(x) => s.serialize_i64(*x),
+            Self::BigInt(x) => s.collect_str(x),
+            Self::Text(x) => s.serialize_str(x),
+            Self::None => s.serialize_unit(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Answer {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Answer;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an integer, a string or null")
+            }
+
+            fn visit_u64<E>(self, x: u64) -> Result<Answer, E> {
+                Ok(Answer::Unsigned(x))
+            }
+
+            fn visit_i64<E>(self, x: i64) -> Result<Answer, E> {
+                Ok(Answer::Signed(x))
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Answer, E> {
+                Ok(match s.parse() {
+                    Ok(x) if x > i64::MAX as i128 || x < i64::MIN as i128 => Answer::BigInt(x),
+                    _ => Answer::Text(s.to_owned()),
+                })
+            }
+
+            fn visit_unit<E>(self) -> Result<Answer, E> {
+                Ok(Answer::None)
+            }
+        }
+
+        d.deserialize_any(Visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eq_test() {
+        assert_eq!(Answer::from(5usize), Answer::from(5i64));
+        assert_eq!(Answer::from(5usize), "5");
+        assert_eq!(Answer::from(-5i32), " -5");
+        assert_ne!(Answer::from(5usize), "6");
+        assert_ne!(Answer::from(5usize), Answer::None);
+        assert_eq!(Answer::from("Push the button"), "Push the button");
+        assert_eq!(Answer::None, Answer::None);
+    }
+
+    #[test]
+    fn serde_test() {
+        let answers = vec![
+            Answer::from(5usize),
+            Answer::from(-5i64),
+            Answer::BigInt(1 << 100),
+            Answer::from("x"),
+        ];
+        let json = serde_json::to_string(&answers).unwrap();
+        assert_eq!(json, r#"[5,-5,"1267650600228229401496703205376","x"]"#);
+
+        let decoded: Vec<Answer> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(decoded[2], Answer::BigInt(_)));
+        assert_eq!(decoded, answers);
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use rand::prelude::*;
+use sha2::{Digest, Sha256};
+
+use adventofcode2023::answer::Answer;
+
+// A known answer. Hashed answers are stored as `sha256:<salt>:<hex digest>`
+// where the digest is computed over the salt followed by the answer. Plain
+// answers are compared numerically while hashed answers can only be compared
+// as text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expected {
+    Plain(String),
+    Hashed { salt: String, digest: String },
+}
+
+impl Expected {
+    pub fn hashed(answer: &Answer) -> Self {
+        let salt: String = thread_rng()
+            .sample_iter(rand::distributions::Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        let digest = salted_digest(&salt, &answer.to_string());
+        Expected::Hashed { salt, digest }
+    }
+
+    pub fn matches(&self, answer: &Answer) -> bool {
+        match self {
+            Self::Plain(s) => *answer == s.as_str(),
+            Self::Hashed { salt, digest } => &salted_digest(salt, &answer.to_string()) == digest,
+        }
+    }
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Plain(s) => f.write_str(s),
+            Self::Hashed { salt, digest } => write!(f, "sha256:{}:{}", salt, digest),
+        }
+    }
+}
+
+fn salted_digest(salt: &str, answer: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(answer.as_bytes());
+    hasher.finalize().iter().fold(String::new(), |mut acc, b| {
+        let _ = write!(acc, "{:02x}", b);
+        acc
+    })
+}
+
+// Known answers keyed by (day, part). Each line of an answers file is
+// `<day> <part> <answer>`. Blank lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Answers {
+    pub entries: BTreeMap<(usize, usize), Expected>,
+}
+
+impl Answers {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (day, part, expected) = parser::parse_line(line)
+                .map_err(|e| anyhow!("line {}: {}", i + 1, e))?
+                .1;
+            if entries.insert((day, part), expected).is_some() {
+                return Err(anyhow!(
+                    "line {}: duplicate entry for {}-{}",
+                    i + 1,
+                    day,
+                    part
+                ));
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read answers file `{}`", path.display()))?;
+        Self::parse(&data)
+            .with_context(|| format!("failed to parse answers file `{}`", path.display()))
+    }
+
+    pub fn get(&self, day: usize, part: usize) -> Option<&Expected> {
+        self.entries.get(&(day, part))
+    }
+}
+
+impl Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# <day> <part> <answer>")?;
+        for ((day, part), expected) in &self.entries {
+            writeln!(f, "{} {} {}", day, part, expected)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    Fail {
+        expected: Expected,
+        got: Result<Answer, String>,
+    },
+    Missing {
+        got: Result<Answer, String>,
+    },
+}
+
+impl Outcome {
+    pub fn new(expected: Option<&Expected>, got: Result<Answer, String>) -> Self {
+        match (expected, got) {
+            (Some(e), Ok(ans)) if e.matches(&ans) => Outcome::Pass,
+            // There is nothing to check for parts without an answer
+            (None, Ok(Answer::None)) => Outcome::Pass,
+            (Some(e), got) => Outcome::Fail {
+                expected: e.clone(),
+                got,
+            },
+            (None, got) => Outcome::Missing { got },
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn got(f: &mut fmt::Formatter<'_>, got: &Result<Answer, String>) -> fmt::Result {
+            match got {
+                Ok(ans) => write!(f, "got {}", ans),
+                Err(e) => write!(f, "error: {}", e),
+            }
+        }
+
+        match self {
+            Self::Pass => f.write_str("PASS"),
+            Self::Fail { expected, got: g } => {
+                write!(f, "FAIL     expected {}, ", expected)?;
+                got(f, g)
+            }
+            Self::Missing { got: g } => {
+                f.write_str("MISSING  ")?;
+                got(f, g)
+            }
+        }
+    }
+}
+
+mod parser {
+    use super::*;
+    use adventofcode2023::parser::prelude::*;
+    use nom::character::complete::not_line_ending;
+
+    pub fn parse_line(input: &str) -> IResult<&str, (usize, usize, Expected)> {
+        let hashed = tuple((
+            tag("sha256:"),
+            alphanumeric1,
+            char(':'),
+            is_a("0123456789abcdef"),
+        ))
+        .map(
+            |(_, salt, _, digest): (_, &str, _, &str)| Expected::Hashed {
+                salt: salt.to_owned(),
+                digest: digest.to_owned(),
+            },
+        );
+        let plain = not_line_ending.map(|x: &str| Expected::Plain(x.trim_end().to_owned()));
+        let expected = alt((hashed, plain));
+        let line = tuple((uint, space1, uint, space1, expected)).map(|(d, _, p, _, e)| (d, p, e));
+        ws_all_consuming(line)(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+
+    use super::*;
+    use crate::inputs::Inputs;
+    use adventofcode2023::solutions::SOLUTIONS;
+
+    const EXAMPLE_ANSWERS: &str = "
+        # comment
+        1 1 142
+        25 2 Push the button
+        3 2 sha256:abc:a3f5e0c2d0c2ab1b9dc1f7b7f2a4b4b14bea0c5e8b3c2a07a3f6ae0f8e52e0ba
+    ";
+
+    #[test]
+    fn parse_test() {
+        let answers = Answers::parse(EXAMPLE_ANSWERS).unwrap();
+        assert_eq!(answers.entries.len(), 3);
+        assert_eq!(answers.get(1, 1), Some(&Expected::Plain("142".to_owned())));
+        assert_eq!(
+            answers.get(25, 2),
+            Some(&Expected::Plain("Push the button".to_owned()))
+        );
+        assert!(matches!(answers.get(3, 2), Some(Expected::Hashed { .. })));
+        assert_eq!(Answers::parse(&answers.to_string()).unwrap(), answers);
+    }
+
+    #[test]
+    fn outcome_test() {
+        let expected = Expected::Plain("054390".to_owned());
+        assert_eq!(
+            Outcome::new(Some(&expected), Ok(Answer::from(54390usize))),
+            Outcome::Pass
+        );
+        assert!(matches!(
+            Outcome::new(Some(&expected), Ok(Answer::from(54391usize))),
+            Outcome::Fail { .. }
+        ));
+        assert_eq!(Outcome::new(None, Ok(Answer::None)), Outcome::Pass);
+        assert!(matches!(
+            Outcome::new(Some(&expected), Ok(Answer::None)),
+            Outcome::Fail { .. }
+        ));
+    }
+
+    #[test]
+    fn parse_duplicate_test() {
+        assert!(Answers::parse("1 1 2\n1 1 3").is_err());
+    }
+
+    #[test]
+    fn hashed_test() {
+        let e = Expected::hashed(&Answer::from(4361usize));
+        assert!(e.matches(&Answer::from(4361i64)));
+        assert!(!e.matches(&Answer::from(4362usize)));
+        assert_ne!(e, Expected::hashed(&Answer::from(4361usize)));
+    }
+
+    #[test]
+    fn real_inputs_test() {
+        let answers = Answers::parse(include_str!("../puzzle-inputs/answers.txt")).unwrap();
+        let inputs = Inputs::new(
+            Some(Path::new(env!("CARGO_MANIFEST_DIR")).join("puzzle-inputs")),
+            None,
+        );
+
+        let failures: Vec<String> = answers
+            .entries
+            .par_iter()
+            .filter_map(|(&(day, part), expected)| {
+                let solution = SOLUTIONS.get(&day)?;
+                let got = inputs
+                    .load(day)
+                    .and_then(|input| solution.solver.parse(&input))
+                    .and_then(|parsed| solution.solver.solve(&parsed, part))
+                    .map_err(|e| format!("{:#}", e));
+                let outcome = Outcome::new(Some(expected), got);
+                matches!(outcome, Outcome::Fail { .. })
+                    .then(|| format!("{:2}-{}: {}", day, part, outcome))
+            })
+            .collect();
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
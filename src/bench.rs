@@ -0,0 +1,143 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+// Limits how long a single problem is benchmarked for.
+#[derive(Clone, Copy, Debug)]
+pub struct Budget {
+    pub warmup: Duration,
+    pub measure: Duration,
+    pub min_iters: usize,
+    pub max_iters: usize,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            warmup: Duration::from_millis(200),
+            measure: Duration::from_secs(2),
+            min_iters: 5,
+            max_iters: 100_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub samples: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub p95: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    // Returns None if there are no samples.
+    pub fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        samples.sort_unstable();
+        let n = samples.len();
+
+        let nanos: Vec<f64> = samples.iter().map(|d| d.as_nanos() as f64).collect();
+        let mean = nanos.iter().sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            nanos.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+
+        let median = if n.is_multiple_of(2) {
+            (samples[n / 2 - 1] + samples[n / 2]) / 2
+        } else {
+            samples[n / 2]
+        };
+
+        // nearest-rank percentile
+        let p95_rank = (n as f64 * 0.95).ceil() as usize;
+
+        Some(Self {
+            samples: n,
+            min: samples[0],
+            median,
+            mean: Duration::from_nanos(mean.round() as u64),
+            p95: samples[p95_rank.saturating_sub(1)],
+            stddev: Duration::from_nanos(variance.sqrt().round() as u64),
+        })
+    }
+}
+
+// Repeatedly calls `f` until the budget runs out. The first calls are used to
+// warm up caches and are not recorded.
+pub fn bench<T>(budget: &Budget, mut f: impl FnMut() -> Result<T>) -> Result<Stats> {
+    let warmup_start = Instant::now();
+    loop {
+        std::hint::black_box(f()?);
+        if warmup_start.elapsed() >= budget.warmup {
+            break;
+        }
+    }
+
+    let mut samples = Vec::new();
+    let measure_start = Instant::now();
+    while samples.len() < budget.max_iters
+        && (samples.len() < budget.min_iters || measure_start.elapsed() < budget.measure)
+    {
+        let start = Instant::now();
+        let ret = f()?;
+        let end = Instant::now();
+        std::hint::black_box(ret);
+        samples.push(end.duration_since(start));
+    }
+
+    Ok(Stats::from_samples(samples).expect("max_iters must be at least one"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(x: u64) -> Duration {
+        Duration::from_millis(x)
+    }
+
+    #[test]
+    fn stats_test() {
+        let samples = (1..=20).rev().map(ms).collect();
+        let stats = Stats::from_samples(samples).unwrap();
+        assert_eq!(stats.samples, 20);
+        assert_eq!(stats.min, ms(1));
+        assert_eq!(stats.median, Duration::from_micros(10_500));
+        assert_eq!(stats.mean, Duration::from_micros(10_500));
+        assert_eq!(stats.p95, ms(19));
+        assert_eq!(stats.stddev, Duration::from_nanos(5_916_080));
+    }
+
+    #[test]
+    fn stats_empty_test() {
+        assert_eq!(Stats::from_samples(Vec::new()), None);
+    }
+
+    #[test]
+    fn bench_budget_test() {
+        let budget = Budget {
+            warmup: Duration::ZERO,
+            measure: Duration::ZERO,
+            min_iters: 3,
+            max_iters: 10,
+        };
+        let mut calls = 0;
+        let stats = bench(&budget, || {
+            calls += 1;
+            Ok(())
+        })
+        .unwrap();
+
+        // one warmup call and min_iters measured calls
+        assert_eq!(stats.samples, 3);
+        assert_eq!(calls, 4);
+    }
+}
@@ -0,0 +1,396 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Args;
+
+pub const SESSION_ENV_VAR: &str = "AOC_SESSION";
+pub const SESSION_FILE_ENV_VAR: &str = "AOC_SESSION_FILE";
+pub const BASE_URL_ENV_VAR: &str = "AOC_BASE_URL";
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+
+const YEAR: u32 = 2023;
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Args, Clone, Debug)]
+pub struct Options {
+    /// Server to use instead of adventofcode.com
+    #[arg(long, env = BASE_URL_ENV_VAR, default_value = DEFAULT_BASE_URL)]
+    pub base_url: String,
+    /// File holding the session cookie, used unless $AOC_SESSION is set.
+    /// Defaults to ~/.adventofcode.session.
+    #[arg(long, env = SESSION_FILE_ENV_VAR)]
+    pub session_file: Option<PathBuf>,
+    /// Minimum time between two requests to the server, in seconds
+    #[arg(long, default_value_t = 5.0)]
+    pub min_interval: f64,
+}
+
+impl Options {
+    // Reads the session cookie from the environment or the session file.
+    pub fn session(&self) -> Result<String> {
+        if let Ok(s) = std::env::var(SESSION_ENV_VAR) {
+            return Ok(s.trim().to_owned());
+        }
+
+        let path = match &self.session_file {
+            Some(p) => p.clone(),
+            None => std::env::var_os("HOME")
+                .map(|home| Path::new(&home).join(".adventofcode.session"))
+                .ok_or_else(|| anyhow!("no session token: set {}", SESSION_ENV_VAR))?,
+        };
+        let session = std::fs::read_to_string(&path).with_context(|| {
+            format!(
+                "no session token: set {} or save the session cookie in `{}`",
+                SESSION_ENV_VAR,
+                path.display()
+            )
+        })?;
+        Ok(session.trim().to_owned())
+    }
+}
+
+// The result of submitting an answer, as told by the response page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    // The hint says whether the answer was too high or too low
+    Wrong { hint: Option<String> },
+    TooSoon { wait: Option<Duration> },
+    // The part was solved before, so the answer was not checked
+    AlreadySolved,
+    Unknown(String),
+}
+
+impl Verdict {
+    pub fn parse(page: &str) -> Self {
+        let text = article_text(page);
+        if text.contains("That's the right answer") {
+            Self::Correct
+        } else if text.contains("That's not the right answer") {
+            let hint = ["too high", "too low"]
+                .into_iter()
+                .find(|h| text.contains(&format!("your answer is {}", h)))
+                .map(|h| h.to_owned());
+            Self::Wrong { hint }
+        } else if text.contains("You gave an answer too recently") {
+            Self::TooSoon {
+                wait: parse_wait(&text),
+            }
+        } else if text.contains("You don't seem to be solving the right level") {
+            Self::AlreadySolved
+        } else {
+            Self::Unknown(text)
+        }
+    }
+}
+
+// Returns the text of the page's `<article>`, or of the whole page if it has
+// none, without tags and with whitespace collapsed.
+fn article_text(page: &str) -> String {
+    // The article starts inside its opening tag
+    let (html, mut in_tag) = match page
+        .split_once("<article")
+        .and_then(|(_, rest)| rest.split_once("</article>"))
+    {
+        Some((article, _)) => (article, true),
+        None => (page, false),
+    };
+
+    let mut text = String::new();
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Parses the time left from "You have 1m 5s left to wait".
+fn parse_wait(text: &str) -> Option<Duration> {
+    let (_, rest) = text.split_once("You have ")?;
+    let (wait, _) = rest.split_once(" left to wait")?;
+
+    let mut secs = 0;
+    for part in wait.split_whitespace() {
+        let unit = part.find(|c: char| !c.is_ascii_digit())?;
+        let n: u64 = part[..unit].parse().ok()?;
+        secs += match &part[unit..] {
+            "h" => n * 3600,
+            "m" => n * 60,
+            "s" => n,
+            _ => return None,
+        };
+    }
+    Some(Duration::from_secs(secs))
+}
+
+// Talks to the Advent of Code server. Requests are spaced at least
+// `min_interval` apart, including across runs, by remembering the time of the
+// last request in `state_path`.
+pub struct Client {
+    base_url: String,
+    session: String,
+    min_interval: Duration,
+    state_path: PathBuf,
+    agent: ureq::Agent,
+}
+
+impl Client {
+    pub fn new(options: &Options, state_path: PathBuf) -> Result<Self> {
+        let min_interval = Duration::try_from_secs_f64(options.min_interval)
+            .context("invalid minimum interval")?;
+        Ok(Self {
+            base_url: options.base_url.trim_end_matches('/').to_owned(),
+            session: options.session()?,
+            min_interval,
+            state_path,
+            agent: ureq::AgentBuilder::new()
+                .user_agent(USER_AGENT)
+                .timeout(TIMEOUT)
+                .build(),
+        })
+    }
+
+    pub fn fetch_input(&self, day: usize) -> Result<String> {
+        let url = format!("{}/{}/day/{}/input", self.base_url, YEAR, day);
+        self.wait_turn()?;
+        let res = self.agent.get(&url).set("Cookie", &self.cookie()).call();
+        read_body(res).with_context(|| format!("failed to fetch the input of day {}", day))
+    }
+
+    pub fn submit(&self, day: usize, part: usize, answer: &str) -> Result<Verdict> {
+        let url = format!("{}/{}/day/{}/answer", self.base_url, YEAR, day);
+        self.wait_turn()?;
+        let res = self
+            .agent
+            .post(&url)
+            .set("Cookie", &self.cookie())
+            .send_form(&[("level", &part.to_string()), ("answer", answer)]);
+        let page = read_body(res)
+            .with_context(|| format!("failed to submit the answer of {}-{}", day, part))?;
+        Ok(Verdict::parse(&page))
+    }
+
+    fn cookie(&self) -> String {
+        format!("session={}", self.session)
+    }
+
+    // Sleeps until `min_interval` has passed since the last request and
+    // records the time of this one. Returns how long it slept.
+    fn wait_turn(&self) -> Result<Duration> {
+        let last = std::fs::read_to_string(&self.state_path)
+            .ok()
+            .and_then(|s| s.trim().parse::<u128>().ok());
+        let wait = last.map_or(Duration::ZERO, |last| self.wait_time(last, now_nanos()));
+        if !wait.is_zero() {
+            eprintln!("waiting {:.1?} between requests", wait);
+            std::thread::sleep(wait);
+        }
+
+        std::fs::write(&self.state_path, now_nanos().to_string()).with_context(|| {
+            format!(
+                "failed to write request time to `{}`",
+                self.state_path.display()
+            )
+        })?;
+        Ok(wait)
+    }
+
+    // How long a request at `now` has to wait after the one at `last`, both
+    // in nanoseconds since the epoch. Whole nanoseconds are kept so the wait
+    // never comes out shorter than `min_interval`.
+    fn wait_time(&self, last: u128, now: u128) -> Duration {
+        let since =
+            u64::try_from(now.saturating_sub(last)).map_or(Duration::MAX, Duration::from_nanos);
+        self.min_interval.saturating_sub(since)
+    }
+}
+
+fn now_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+// Error pages are short, so their first line says what went wrong.
+fn read_body(res: Result<ureq::Response, ureq::Error>) -> Result<String> {
+    match res {
+        Ok(r) => Ok(r.into_string()?),
+        Err(ureq::Error::Status(code, r)) => {
+            let body = r.into_string().unwrap_or_default();
+            match body.lines().map(str::trim).find(|l| !l.is_empty()) {
+                Some(line) => bail!("server responded with {}: {}", code, line),
+                None => bail!("server responded with {}", code),
+            }
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    use super::*;
+
+    // Answers one request per response with the given status and body and
+    // returns the requests it received.
+    fn serve(responses: Vec<(u16, &'static str)>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut request = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body_buf = vec![0; content_length];
+                reader.read_exact(&mut body_buf).unwrap();
+                request.push_str(&String::from_utf8(body_buf).unwrap());
+                requests.push(request);
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        (url, handle)
+    }
+
+    fn client(base_url: String, min_interval: Duration, name: &str) -> Client {
+        let state_path =
+            std::env::temp_dir().join(format!("aoc2023-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_file(&state_path);
+        Client {
+            base_url,
+            session: "abc".to_owned(),
+            min_interval,
+            state_path,
+            agent: ureq::agent(),
+        }
+    }
+
+    #[test]
+    fn fetch_input_test() {
+        let (url, server) = serve(vec![(200, "Time: 7\n"), (404, "\nNot Found\n")]);
+        let client = client(url, Duration::ZERO, "fetch");
+
+        assert_eq!(client.fetch_input(6).unwrap(), "Time: 7\n");
+        let err = client.fetch_input(26).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "failed to fetch the input of day 26: server responded with 404: Not Found"
+        );
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /2023/day/6/input HTTP/1.1\r\n"));
+        assert!(requests[0].contains("Cookie: session=abc\r\n"));
+        std::fs::remove_file(client.state_path).unwrap();
+    }
+
+    #[test]
+    fn submit_test() {
+        let page = "<html><main><article><p>That's the right answer! \
+                    You are <em>one gold star</em> closer.</p></article></main></html>";
+        let (url, server) = serve(vec![(200, page)]);
+        let client = client(url, Duration::ZERO, "submit");
+
+        assert_eq!(client.submit(6, 2, "71503").unwrap(), Verdict::Correct);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /2023/day/6/answer HTTP/1.1\r\n"));
+        assert!(requests[0].ends_with("\r\n\r\nlevel=2&answer=71503"));
+        std::fs::remove_file(client.state_path).unwrap();
+    }
+
+    #[test]
+    fn rate_limit_test() {
+        let (url, server) = serve(vec![(200, "a"), (200, "b")]);
+        let client = client(url, Duration::from_millis(200), "rate-limit");
+        let last_request = || -> u128 {
+            let state = std::fs::read_to_string(&client.state_path).unwrap();
+            state.parse().unwrap()
+        };
+
+        client.fetch_input(1).unwrap();
+        let first = last_request();
+        client.fetch_input(2).unwrap();
+        assert!(last_request() - first >= 200_000_000);
+
+        let ms = Duration::from_millis;
+        assert_eq!(client.wait_time(first, first + 50_000_000), ms(150));
+        assert_eq!(
+            client.wait_time(first, first + 1),
+            ms(200) - Duration::from_nanos(1)
+        );
+        assert_eq!(client.wait_time(first, first + 300_000_000), Duration::ZERO);
+        // A clock that went back waits the whole interval
+        assert_eq!(client.wait_time(first, first - 1), ms(200));
+
+        server.join().unwrap();
+        std::fs::remove_file(client.state_path).unwrap();
+    }
+
+    #[test]
+    fn verdict_test() {
+        let wrong = "<article><p>That's not the right answer; your answer is too \
+                     high. Please wait one minute before trying again.</p></article>";
+        assert_eq!(
+            Verdict::parse(wrong),
+            Verdict::Wrong {
+                hint: Some("too high".to_owned())
+            }
+        );
+
+        let too_soon = "<article><p>You gave an answer too recently; you have to wait \
+                        after submitting an answer before trying again.  You have \
+                        <span>1m 5s</span> left to wait.</p></article>";
+        assert_eq!(
+            Verdict::parse(too_soon),
+            Verdict::TooSoon {
+                wait: Some(Duration::from_secs(65))
+            }
+        );
+
+        let solved = "<article><p>You don't seem to be solving the right level.  \
+                      Did you already complete it?</p></article>";
+        assert_eq!(Verdict::parse(solved), Verdict::AlreadySolved);
+
+        assert_eq!(
+            Verdict::parse("<p>Something\n  else</p>"),
+            Verdict::Unknown("Something else".to_owned())
+        );
+    }
+}
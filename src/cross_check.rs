@@ -0,0 +1,345 @@
+use std::mem;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::answer::Answer;
+use crate::generate::InputGenerator;
+use crate::solutions::DynSolution;
+
+// An input on which the fast and the reference solution of a part disagree.
+#[derive(Debug)]
+pub struct Mismatch {
+    pub seed: u64,
+    pub part: usize,
+    pub input: String,
+    pub fast: String,
+    pub reference: String,
+}
+
+// What a cross-check found.
+#[derive(Debug, Default)]
+pub struct Report {
+    // The parts whose reference solved at least one input
+    pub checked: Vec<usize>,
+    // The parts whose reference could not run, with why, like day 14 when
+    // there are too many cycles for it
+    pub skipped: Vec<(usize, String)>,
+    pub mismatch: Option<Mismatch>,
+}
+
+// What the fast solution of a part made of an input.
+pub enum Outcome {
+    Answer(Answer),
+    Error(String),
+    Panic(String),
+    Timeout(String),
+}
+
+impl Outcome {
+    fn text(&self) -> String {
+        match self {
+            Outcome::Answer(ans) => ans.to_string(),
+            Outcome::Error(e) => format!("error: {}", e),
+            Outcome::Panic(msg) => format!("panicked: {}", msg),
+            Outcome::Timeout(msg) => format!("timed out: {}", msg),
+        }
+    }
+}
+
+// Solves inputs generated from each seed both ways, and shrinks the first one
+// the two disagree on. A part whose reference fails is skipped rather than
+// failing the whole check. The fast solution runs in this process, so a panic
+// only counts as a disagreement when panics unwind.
+pub fn check(solver: &dyn DynSolution, seeds: Range<u64>, size: usize) -> Result<Report> {
+    check_with(solver, seeds, size, |input, part| {
+        solve(solver, input, part)
+    })
+}
+
+// Like `check`, but `fast` runs the fast solution of a part on an input, for
+// callers that run it somewhere a panic or hang cannot take them down.
+pub fn check_with(
+    solver: &dyn DynSolution,
+    seeds: Range<u64>,
+    size: usize,
+    fast: impl Fn(&str, usize) -> Outcome,
+) -> Result<Report> {
+    let mut report = Report::default();
+    for seed in seeds {
+        let input = solver
+            .generate(&mut InputGenerator::new(seed, size))
+            .ok_or_else(|| anyhow!("no input generator"))?;
+        let parsed = solver
+            .parse(&input)
+            .with_context(|| format!("generated input of seed {} does not parse", seed))?;
+        for part in 1..=2 {
+            if report.skipped.iter().any(|&(p, _)| p == part) {
+                continue;
+            }
+            match solver.reference(&parsed, part) {
+                Ok(Some(_)) => {}
+                Ok(None) => continue,
+                Err(e) => {
+                    report.skipped.push((part, format!("{:#}", e)));
+                    continue;
+                }
+            }
+            if !report.checked.contains(&part) {
+                report.checked.push(part);
+            }
+
+            // Shrinking keeps to inputs the fast solution gets wrong the same
+            // way, so a wrong answer does not turn into some other panic
+            if let Some((outcome, _)) = compare(solver, &fast, &input, part) {
+                let kind = mem::discriminant(&outcome);
+                let input = shrink(&input, |x| {
+                    compare(solver, &fast, x, part)
+                        .is_some_and(|(outcome, _)| mem::discriminant(&outcome) == kind)
+                });
+                let (outcome, reference) = compare(solver, &fast, &input, part)
+                    .ok_or_else(|| anyhow!("shrunk input of seed {} no longer fails", seed))?;
+                report.mismatch = Some(Mismatch {
+                    seed,
+                    part,
+                    input,
+                    fast: outcome.text(),
+                    reference,
+                });
+                return Ok(report);
+            }
+        }
+        if report.checked.is_empty() && report.skipped.is_empty() {
+            bail!("no reference solution");
+        }
+    }
+    Ok(report)
+}
+
+// The outcome of the fast solution of a part and the reference answer if they
+// differ. Inputs that do not parse or that the reference cannot solve are not
+// counterexamples, a fast solution that fails, panics or hangs is.
+fn compare(
+    solver: &dyn DynSolution,
+    fast: &impl Fn(&str, usize) -> Outcome,
+    input: &str,
+    part: usize,
+) -> Option<(Outcome, String)> {
+    let parsed = solver.parse(input).ok()?;
+    let reference = solver.reference(&parsed, part).ok()??;
+    match fast(input, part) {
+        Outcome::Answer(ans) if ans == reference => None,
+        outcome => Some((outcome, reference.to_string())),
+    }
+}
+
+fn solve(solver: &dyn DynSolution, input: &str, part: usize) -> Outcome {
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        let parsed = solver.parse(input)?;
+        solver.solve(&parsed, part)
+    }));
+    match res {
+        Ok(Ok(ans)) => Outcome::Answer(ans),
+        Ok(Err(e)) => Outcome::Error(format!("{:#}", e)),
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(|x| x.as_str()))
+                .unwrap_or("unknown panic");
+            Outcome::Panic(msg.to_owned())
+        }
+    }
+}
+
+// Repeatedly takes the first smaller variant of the input that still fails
+// until none does.
+pub fn shrink(input: &str, fails: impl Fn(&str) -> bool) -> String {
+    let mut input = input.to_owned();
+    while let Some(smaller) = smaller_variants(&input).into_iter().find(|x| fails(x)) {
+        input = smaller;
+    }
+    input
+}
+
+// Variants of the input with a line or a column removed, a number made
+// smaller or a symbol replaced by '.', roughly the biggest cuts first.
+fn smaller_variants(input: &str) -> Vec<String> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mut variants = Vec::new();
+
+    for i in 0..lines.len() {
+        let mut rest = lines.clone();
+        rest.remove(i);
+        variants.push(rest.join("\n"));
+    }
+
+    let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+    for col in 0..width {
+        let rest: Vec<String> = lines
+            .iter()
+            .map(|l| {
+                l.char_indices()
+                    .filter(|&(i, _)| i != col)
+                    .map(|(_, c)| c)
+                    .collect()
+            })
+            .collect();
+        variants.push(rest.join("\n"));
+    }
+
+    let bytes = input.as_bytes();
+    let mut start = 0;
+    while start < bytes.len() {
+        let end = start
+            + bytes[start..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count();
+        if end == start {
+            start += 1;
+            continue;
+        }
+        if let Ok(n) = input[start..end].parse::<u128>() {
+            let mut smaller = vec![0, n / 2, n.saturating_sub(1)];
+            smaller.dedup();
+            for m in smaller.into_iter().filter(|&m| m < n) {
+                variants.push(format!("{}{}{}", &input[..start], m, &input[end..]));
+            }
+        }
+        start = end;
+    }
+
+    for (i, c) in input.char_indices() {
+        if !c.is_whitespace() && !c.is_ascii_digit() && c != '.' {
+            let end = i + c.len_utf8();
+            variants.push(format!("{}.{}", &input[..i], &input[end..]));
+        }
+    }
+
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params;
+    use crate::solutions::{Solution, SOLUTIONS};
+
+    // Sums the numbers of the input, except that the fast way gets numbers
+    // above 7 wrong.
+    struct Buggy;
+
+    impl Solution for Buggy {
+        type Parsed = Vec<u64>;
+
+        fn parse(input: &str) -> Result<Self::Parsed> {
+            input.split_whitespace().map(|x| Ok(x.parse()?)).collect()
+        }
+
+        fn part1(nums: &Self::Parsed) -> Result<Answer> {
+            Ok(nums.iter().map(|&x| x.min(7)).sum::<u64>().into())
+        }
+
+        fn part2(nums: &Self::Parsed) -> Result<Answer> {
+            Ok(nums.iter().sum::<u64>().into())
+        }
+
+        fn generate(gen: &mut InputGenerator) -> Option<String> {
+            use rand::Rng;
+            let nums: Vec<String> = (0..gen.size())
+                .map(|_| gen.gen_range(0..100).to_string())
+                .collect();
+            Some(nums.join("\n"))
+        }
+
+        fn reference(nums: &Self::Parsed, _part: usize) -> Result<Option<Answer>> {
+            Ok(Some(nums.iter().sum::<u64>().into()))
+        }
+    }
+
+    #[test]
+    fn shrink_test() {
+        // Fails while a line of x has a number of at least 10
+        let fails = |x: &str| {
+            x.lines().any(|l| {
+                let mut words = l.split(' ');
+                words.next() == Some("x") && words.any(|w| w.parse().is_ok_and(|n: u32| n >= 10))
+            })
+        };
+        assert_eq!(shrink("a 1\nx 57 3\nb 12", fails), "x 10");
+        assert_eq!(shrink("xyz", |_| false), "xyz");
+    }
+
+    #[test]
+    fn mismatch_test() {
+        let mismatch = check(&Buggy, 0..10, 5).unwrap().mismatch.unwrap();
+        assert_eq!(mismatch.seed, 0);
+        assert_eq!(mismatch.part, 1);
+        assert_eq!(mismatch.input, "8");
+        assert_eq!(
+            (mismatch.fast.as_str(), mismatch.reference.as_str()),
+            ("7", "8")
+        );
+    }
+
+    #[test]
+    fn check_with_test() {
+        // Hangs on numbers above 90 and is right otherwise
+        let fast = |input: &str, _part| {
+            let nums = <Buggy as Solution>::parse(input).unwrap();
+            match nums.iter().any(|&n| n > 90) {
+                true => Outcome::Timeout("did not finish".to_owned()),
+                false => Outcome::Answer(nums.iter().sum::<u64>().into()),
+            }
+        };
+        let mismatch = check_with(&Buggy, 0..10, 5, fast)
+            .unwrap()
+            .mismatch
+            .unwrap();
+        assert_eq!(mismatch.input, "91");
+        assert_eq!(mismatch.fast, "timed out: did not finish");
+    }
+
+    #[test]
+    fn references_agree_test() {
+        // Day 14's reference runs every spin cycle, so it gets far fewer
+        // than the puzzle's billion
+        let overrides = [(14, "cycles=1000")];
+
+        let mut checked = 0;
+        for (day, solution) in SOLUTIONS.iter() {
+            let args: Vec<String> = overrides
+                .iter()
+                .filter(|(d, _)| d == day)
+                .map(|(_, arg)| arg.to_string())
+                .collect();
+            let overrides = params::Overrides::parse(solution.solver.params(), &args).unwrap();
+            match params::with(&overrides, || check(solution.solver, 0..20, 6)) {
+                Ok(report) => {
+                    assert!(report.mismatch.is_none(), "day {}: {:?}", day, report);
+                    assert!(report.skipped.is_empty(), "day {}: {:?}", day, report);
+                    checked += 1;
+                }
+                Err(e) => assert_eq!(e.to_string(), "no reference solution", "day {}", day),
+            }
+        }
+        assert_eq!(checked, 5);
+    }
+
+    #[test]
+    fn skipped_test() {
+        // The default billion cycles are too many for day 14's reference
+        let report = check(SOLUTIONS[&14].solver, 0..3, 6).unwrap();
+        assert_eq!(report.checked, [1]);
+        assert_eq!(
+            report.skipped,
+            [(
+                2,
+                "the reference runs every cycle, set cycles to at most 100000".to_owned()
+            )]
+        );
+        assert!(report.mismatch.is_none());
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+// Longest stretch of unparsed input quoted in a parse error.
+const PARSE_SNIPPET_LEN: usize = 20;
+
+// The ways a solution can fail. Solutions still return `anyhow::Error`, so
+// these are raised with `bail!` and recovered with `kind`, which looks past
+// any context added on the way up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    // The input does not follow the puzzle's format. Lines and columns start
+    // at 1.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    // The input parsed, but breaks a rule of the puzzle or an assumption the
+    // solution relies on, like a number small enough to fit.
+    InvalidInput(String),
+    // The input is valid but has no answer.
+    NoSolution(String),
+    NotImplemented,
+    // A bug in the solution.
+    Internal(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Parse,
+    InvalidInput,
+    NoSolution,
+    NotImplemented,
+    Internal,
+}
+
+impl Error {
+    // Points at where a parser gave up on `input`, given the unparsed rest
+    // of it.
+    pub fn parse(input: &str, rest: &str) -> Self {
+        let offset = input.len().saturating_sub(rest.len());
+        let before = input.get(..offset).unwrap_or(input);
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+
+        let found = rest.lines().next().unwrap_or("");
+        let message = if rest.is_empty() {
+            "unexpected end of input".to_owned()
+        } else if found.is_empty() {
+            "unexpected end of line".to_owned()
+        } else if found.chars().count() > PARSE_SNIPPET_LEN {
+            let snippet: String = found.chars().take(PARSE_SNIPPET_LEN).collect();
+            format!("unexpected `{}...`", snippet)
+        } else {
+            format!("unexpected `{}`", found)
+        };
+
+        Error::Parse {
+            line,
+            column,
+            message,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Parse { .. } => ErrorKind::Parse,
+            Error::InvalidInput(_) => ErrorKind::InvalidInput,
+            Error::NoSolution(_) => ErrorKind::NoSolution,
+            Error::NotImplemented => ErrorKind::NotImplemented,
+            Error::Internal(_) => ErrorKind::Internal,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "failed to parse input at line {}, column {}: {}",
+                line, column, message
+            ),
+            Error::InvalidInput(msg) | Error::NoSolution(msg) | Error::Internal(msg) => {
+                write!(f, "{}", msg)
+            }
+            Error::NotImplemented => write!(f, "not yet implemented"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::Parse => "parse error",
+            ErrorKind::InvalidInput => "invalid input",
+            ErrorKind::NoSolution => "no solution",
+            ErrorKind::NotImplemented => "not implemented",
+            ErrorKind::Internal => "internal error",
+        })
+    }
+}
+
+// The kind of the first typed error in the chain. Errors that were never
+// given a kind are treated as bugs.
+pub fn kind(e: &anyhow::Error) -> ErrorKind {
+    e.chain()
+        .find_map(|e| e.downcast_ref::<Error>())
+        .map_or(ErrorKind::Internal, Error::kind)
+}
+
+// anyhow errors cannot be cloned, so this rebuilds one with the same
+// messages and kind, for when one error fails several problems.
+pub fn duplicate(e: &anyhow::Error) -> anyhow::Error {
+    let mut context = Vec::new();
+    for layer in e.chain() {
+        if let Some(typed) = layer.downcast_ref::<Error>() {
+            return context
+                .into_iter()
+                .rev()
+                .fold(anyhow::Error::from(typed.clone()), |e, c| e.context(c));
+        }
+        context.push(layer.to_string());
+    }
+    anyhow::anyhow!("{:#}", e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn parse_location_test() {
+        let input = "12 34\n56 x8\n";
+        let err = Error::parse(input, &input[9..]);
+        assert_eq!(
+            err.to_string(),
+            "failed to parse input at line 2, column 4: unexpected `x8`"
+        );
+
+        let err = Error::parse(input, &input[5..]);
+        assert_eq!(
+            err,
+            Error::Parse {
+                line: 1,
+                column: 6,
+                message: "unexpected end of line".to_owned()
+            }
+        );
+
+        let err = Error::parse(input, "");
+        assert_eq!(
+            err.to_string(),
+            "failed to parse input at line 3, column 1: unexpected end of input"
+        );
+
+        let input = "é".repeat(30);
+        let err = Error::parse(&input, &input[2..]);
+        let snippet = "é".repeat(PARSE_SNIPPET_LEN);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "failed to parse input at line 1, column 2: unexpected `{}...`",
+                snippet
+            )
+        );
+    }
+
+    #[test]
+    fn kind_test() {
+        let err = anyhow::Error::from(Error::NoSolution("stuck".to_owned()));
+        assert_eq!(kind(&err), ErrorKind::NoSolution);
+        let err = err.context("day 17").context("part 1");
+        assert_eq!(kind(&err), ErrorKind::NoSolution);
+        assert_eq!(format!("{:#}", err), "part 1: day 17: stuck");
+
+        let err: Result<(), _> = Err(Error::NotImplemented);
+        let err = err.context("problemfn failed").unwrap_err();
+        assert_eq!(kind(&err), ErrorKind::NotImplemented);
+
+        assert_eq!(kind(&anyhow!("something else")), ErrorKind::Internal);
+    }
+
+    #[test]
+    fn duplicate_test() {
+        let err = anyhow::Error::from(Error::parse("ab", "b"))
+            .context("day 1")
+            .context("input");
+        let copy = duplicate(&err);
+        assert_eq!(format!("{:#}", copy), format!("{:#}", err));
+        assert_eq!(kind(&copy), ErrorKind::Parse);
+
+        let err = anyhow!("missing file").context("day 2");
+        let copy = duplicate(&err);
+        assert_eq!(format!("{:#}", copy), "day 2: missing file");
+        assert_eq!(kind(&copy), ErrorKind::Internal);
+    }
+
+    #[test]
+    fn serde_test() {
+        let json = serde_json::to_string(&ErrorKind::InvalidInput).unwrap();
+        assert_eq!(json, "\"invalid_input\"");
+        let decoded: ErrorKind = serde_json::from_str("\"not_implemented\"").unwrap();
+        assert_eq!(decoded, ErrorKind::NotImplemented);
+    }
+}
@@ -0,0 +1,100 @@
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+
+use adventofcode2023::solutions;
+
+// A set of days written as a comma separated list of days and inclusive
+// ranges. For example: `1-10,17`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DaySet(Vec<RangeInclusive<usize>>);
+
+impl DaySet {
+    pub fn contains(&self, day: usize) -> bool {
+        self.0.iter().any(|r| r.contains(&day))
+    }
+}
+
+impl std::str::FromStr for DaySet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let ranges = parser::parse(s)
+            .map_err(|_| anyhow!("expected a list of days like `1-10,17`"))?
+            .1;
+        if let Some(r) = ranges.iter().find(|r| r.is_empty()) {
+            return Err(anyhow!("empty range {}-{}", r.start(), r.end()));
+        }
+
+        Ok(DaySet(ranges))
+    }
+}
+
+#[derive(Args, Clone, Debug, Default)]
+pub struct ProblemFilter {
+    /// Only run these days, e.g. `1-10,17`
+    #[arg(long)]
+    pub days: Option<DaySet>,
+    /// Only run this part
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=2))]
+    pub part: Option<u8>,
+}
+
+impl ProblemFilter {
+    // Returns the selected parts of each selected registered day in order.
+    pub fn days(&self) -> Vec<(usize, Vec<usize>)> {
+        let mut days: Vec<usize> = solutions::SOLUTIONS
+            .keys()
+            .copied()
+            .filter(|&d| self.days.as_ref().map(|s| s.contains(d)).unwrap_or(true))
+            .collect();
+        days.sort_unstable();
+
+        let parts = match self.part {
+            Some(p) => vec![p as usize],
+            None => vec![1, 2],
+        };
+
+        days.into_iter().map(|d| (d, parts.clone())).collect()
+    }
+}
+
+mod parser {
+    use super::*;
+    use adventofcode2023::parser::prelude::*;
+
+    pub fn parse(input: &str) -> IResult<&str, Vec<RangeInclusive<usize>>> {
+        let range = pair(uint, opt(preceded(char('-'), uint)))
+            .map(|(start, end): (usize, Option<usize>)| start..=end.unwrap_or(start));
+        let ranges = separated_list1(char(','), range);
+        ws_all_consuming(ranges)(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_set_test() {
+        let set: DaySet = "1-10,17".parse().unwrap();
+        assert!(set.contains(1));
+        assert!(set.contains(10));
+        assert!(!set.contains(11));
+        assert!(set.contains(17));
+
+        assert!("".parse::<DaySet>().is_err());
+        assert!("10-1".parse::<DaySet>().is_err());
+        assert!("1,,2".parse::<DaySet>().is_err());
+    }
+
+    #[test]
+    fn days_test() {
+        let filter = ProblemFilter {
+            days: Some("1-2,25".parse().unwrap()),
+            part: Some(2),
+        };
+        assert_eq!(filter.days(), [(1, vec![2]), (2, vec![2]), (25, vec![2])]);
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::filter::ProblemFilter;
+use crate::inputs::Inputs;
+use crate::isolate;
+use crate::report::Status;
+use adventofcode2023::mutate::Mutator;
+
+pub struct Options {
+    pub seed: u64,
+    // Inputs to try per day on top of the corpus
+    pub iterations: usize,
+    pub timeout: Duration,
+    // Extra inputs to mutate in `<corpus>/dayNN/`, where failing inputs are
+    // saved too
+    pub corpus: PathBuf,
+}
+
+// Feeds the corpus of every selected day and mutations of it to the solvers,
+// each in a child process, and returns how many inputs made one panic or
+// hang. Errors are fine, malformed input is expected to be rejected.
+pub fn fuzz(inputs: &Inputs, filter: &ProblemFilter, options: &Options) -> Result<usize> {
+    let mut total = 0;
+    for (day, parts) in filter.days() {
+        let corpus = corpus(inputs, &options.corpus, day)?;
+        let mut rng = StdRng::seed_from_u64(options.seed.wrapping_add(day as u64));
+        let mut mutator = Mutator::new(rng.gen());
+
+        let candidates = corpus
+            .iter()
+            .cloned()
+            .chain(
+                (0..options.iterations).map(|_| match corpus.choose(&mut rng) {
+                    Some(input) if rng.gen_bool(0.9) => mutator.mutate(input),
+                    _ => mutator.random_input(256),
+                }),
+            );
+
+        // Most broken inputs hit the same few panics, only the first input
+        // to panic at each place is reported
+        let mut seen = HashSet::new();
+        let mut tried = 0;
+        for (i, input) in candidates.enumerate() {
+            tried += 1;
+            for &part in &parts {
+                let res =
+                    isolate::run_problem(inputs, day, part, Some(&input), &[], options.timeout);
+                if !matches!(res.status, Status::Panic | Status::Timeout) {
+                    continue;
+                }
+                let error = res.error.unwrap_or_default();
+                let location = error.rsplit_once(" at ").map_or("", |(_, loc)| loc);
+                if !seen.insert((part, res.status.as_str(), location.to_owned())) {
+                    continue;
+                }
+
+                println!("{:>2}-{}: {}: {}", day, part, res.status.as_str(), error);
+                if i < corpus.len() {
+                    println!("      from the corpus");
+                } else {
+                    let name = format!("seed{}-{}.txt", options.seed, i);
+                    let path = save(&options.corpus, day, &name, &input)?;
+                    println!("      saved to {}", path.display());
+                }
+            }
+        }
+        println!("Day {}: {} inputs, {} failures", day, tried, seen.len());
+        total += seen.len();
+    }
+    Ok(total)
+}
+
+// The puzzle input of the day followed by the inputs saved for it, in name
+// order.
+fn corpus(inputs: &Inputs, dir: &Path, day: usize) -> Result<Vec<String>> {
+    let mut corpus = Vec::new();
+    if let Ok(input) = inputs.load(day) {
+        corpus.push(input.into_owned());
+    }
+
+    let day_dir = dir.join(format!("day{:02}", day));
+    if !day_dir.is_dir() {
+        return Ok(corpus);
+    }
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(&day_dir)
+        .with_context(|| format!("failed to read corpus `{}`", day_dir.display()))?
+    {
+        paths.push(entry?.path());
+    }
+    paths.sort_unstable();
+    for path in paths {
+        let bytes =
+            std::fs::read(&path).with_context(|| format!("failed to read `{}`", path.display()))?;
+        corpus.push(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    Ok(corpus)
+}
+
+fn save(dir: &Path, day: usize, name: &str, input: &str) -> Result<PathBuf> {
+    let day_dir = dir.join(format!("day{:02}", day));
+    std::fs::create_dir_all(&day_dir)
+        .with_context(|| format!("failed to create `{}`", day_dir.display()))?;
+    let path = day_dir.join(name);
+    std::fs::write(&path, input)
+        .with_context(|| format!("failed to write `{}`", path.display()))?;
+    Ok(path)
+}
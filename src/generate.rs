@@ -0,0 +1,124 @@
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+// The randomness and the size a solution gets to write an input from. The
+// same seed and size always give the same input.
+pub struct InputGenerator {
+    rng: StdRng,
+    size: usize,
+}
+
+impl InputGenerator {
+    pub fn new(seed: u64, size: usize) -> Self {
+        InputGenerator {
+            rng: StdRng::seed_from_u64(seed),
+            size,
+        }
+    }
+
+    // Roughly how many lines the input should have. Days whose inputs have a
+    // fixed shape treat it as a hint.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // A random lowercase word.
+    pub fn word(&mut self, len: usize) -> String {
+        (0..len)
+            .map(|_| self.gen_range(b'a'..=b'z') as char)
+            .collect()
+    }
+
+    // Distinct words of the given length, none of them in `taken`.
+    pub fn words(&mut self, count: usize, len: usize, taken: &[&str]) -> Vec<String> {
+        assert!(
+            count + taken.len() <= 26usize.saturating_pow(len as u32),
+            "not enough words of length {}",
+            len
+        );
+        let mut words = Vec::with_capacity(count);
+        let mut seen: ahash::AHashSet<String> = taken.iter().map(|x| x.to_string()).collect();
+        while words.len() < count {
+            let w = self.word(len);
+            if seen.insert(w.clone()) {
+                words.push(w);
+            }
+        }
+        words
+    }
+}
+
+impl RngCore for InputGenerator {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+// Inputs of a few sizes for property tests of a solution.
+#[cfg(test)]
+pub(crate) fn samples<S: crate::solutions::Solution>() -> Vec<String> {
+    (0..24)
+        .map(|seed| {
+            let size = [5, 12, 30][seed as usize % 3];
+            let mut gen = InputGenerator::new(seed, size);
+            S::generate(&mut gen).expect("the day has no generator")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::SOLUTIONS;
+
+    #[test]
+    fn words_test() {
+        let mut gen = InputGenerator::new(1, 0);
+        let words = gen.words(20, 2, &["in"]);
+        assert_eq!(words.len(), 20);
+        assert!(words.iter().all(|w| w.len() == 2 && w != "in"));
+
+        let mut again = InputGenerator::new(1, 0);
+        assert_eq!(again.words(20, 2, &["in"]), words);
+    }
+
+    #[test]
+    fn every_day_test() {
+        for (day, solution) in SOLUTIONS.iter() {
+            for seed in 0..3 {
+                let generate = || solution.solver.generate(&mut InputGenerator::new(seed, 20));
+                let input = generate().unwrap_or_else(|| panic!("day {} has no generator", day));
+                assert_eq!(generate(), Some(input.clone()), "day {}", day);
+
+                let parsed = solution
+                    .solver
+                    .parse(&input)
+                    .unwrap_or_else(|e| panic!("day {} seed {}: {:#}\n{}", day, seed, e, input));
+                for part in 1..=2 {
+                    // Day 24 part 2 is not solved yet
+                    if (*day, part) == (24, 2) {
+                        continue;
+                    }
+                    if let Err(e) = solution.solver.solve(&parsed, part) {
+                        panic!(
+                            "day {} part {} seed {}: {:#}\n{}",
+                            day, part, seed, e, input
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use anyhow::bail;
+
+use crate::error::Error;
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Grid<T> {
     pub cells: Vec<Vec<T>>,
@@ -8,11 +12,30 @@ impl<T> Grid<T> {
         Grid { cells: data }
     }
 
+    // Like `new`, but checks that there is at least one row and that every
+    // row is as long as the first, which the rest of the methods assume.
+    pub fn try_new(data: Vec<Vec<T>>) -> Result<Self, anyhow::Error> {
+        let Some(width) = data.first().map(|r| r.len()) else {
+            bail!(Error::InvalidInput("grid has no rows".to_owned()));
+        };
+        if width == 0 {
+            bail!(Error::InvalidInput("row 1 of the grid is empty".to_owned()));
+        }
+        if let Some((y, row)) = data.iter().enumerate().find(|(_, r)| r.len() != width) {
+            bail!(Error::InvalidInput(format!(
+                "row {} of the grid has {} cells, but row 1 has {}",
+                y + 1,
+                row.len(),
+                width
+            )));
+        }
+        Ok(Grid { cells: data })
+    }
+
     pub fn get(&self, p: Point) -> Option<&T> {
         self.cells.get(p.y)?.get(p.x)
     }
 
-    #[allow(dead_code)]
     pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
         self.cells.get_mut(p.y)?.get_mut(p.x)
     }
@@ -22,7 +45,6 @@ impl<T> Grid<T> {
         (0..y_len).flat_map(move |y| (0..x_len).map(move |x| Point::new(x, y)))
     }
 
-    #[allow(dead_code)]
     pub fn iter_line(&self, start: Point, d: Direction) -> impl Iterator<Item = (Point, &T)> {
         LineIterator {
             g: self,
@@ -32,7 +54,7 @@ impl<T> Grid<T> {
     }
 
     pub fn size(&self) -> (usize, usize) {
-        (self.cells[0].len(), self.cells.len())
+        (self.cells.first().map_or(0, |r| r.len()), self.cells.len())
     }
 }
 
@@ -143,3 +165,23 @@ impl Direction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_new_test() {
+        let grid = Grid::try_new(vec![vec![1, 2], vec![3, 4]]).unwrap();
+        assert_eq!(grid.size(), (2, 2));
+
+        let err = |cells: Vec<Vec<u8>>| Grid::try_new(cells).unwrap_err().to_string();
+        assert_eq!(err(vec![]), "grid has no rows");
+        assert_eq!(err(vec![vec![]]), "row 1 of the grid is empty");
+        assert_eq!(
+            err(vec![vec![1, 2], vec![3, 4], vec![5]]),
+            "row 3 of the grid has 1 cells, but row 1 has 2"
+        );
+        assert_eq!(Grid::<u8>::new(vec![]).size(), (0, 0));
+    }
+}
@@ -0,0 +1,338 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub const ENV_VAR: &str = "AOC_HISTORY";
+pub const DEFAULT_PATH: &str = ".aoc-history.jsonl";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timing {
+    pub day: usize,
+    pub part: usize,
+    pub parse_ns: u64,
+    pub duration_ns: u64,
+}
+
+impl Timing {
+    pub fn new(day: usize, part: usize, parse: Duration, solve: Duration) -> Self {
+        Self {
+            day,
+            part,
+            parse_ns: parse.as_nanos() as u64,
+            duration_ns: solve.as_nanos() as u64,
+        }
+    }
+
+    // Parsing and solving together, since either may regress.
+    fn total(&self) -> Duration {
+        Duration::from_nanos(self.parse_ns + self.duration_ns)
+    }
+}
+
+// The timings of one `run-all` or `bench` invocation. The history file holds
+// one record per line as JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub commit: Option<String>,
+    // Seconds since the unix epoch
+    pub timestamp: u64,
+    // The subcommand that recorded the timings
+    pub source: String,
+    pub profile: String,
+    pub timings: Vec<Timing>,
+}
+
+impl Record {
+    pub fn new(source: &str, profile: &str, timings: Vec<Timing>) -> Self {
+        Self {
+            commit: commit_id(),
+            timestamp: now(),
+            source: source.to_owned(),
+            profile: profile.to_owned(),
+            timings,
+        }
+    }
+
+    fn describe(&self, now: u64) -> String {
+        format!(
+            "{} ({}, {})",
+            self.commit.as_deref().unwrap_or("unknown commit"),
+            self.source,
+            format_age(now.saturating_sub(self.timestamp))
+        )
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// Names the checked out commit, with a `-dirty` suffix if there are
+// uncommitted changes. Returns None outside of a git checkout.
+fn commit_id() -> Option<String> {
+    let out = Command::new("git")
+        .args(["describe", "--always", "--dirty"])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let id = String::from_utf8(out.stdout).ok()?;
+    Some(id.trim().to_owned()).filter(|x| !x.is_empty())
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+pub fn append(path: &Path, record: &Record) -> Result<()> {
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open history file `{}`", path.display()))?;
+    writeln!(f, "{}", serde_json::to_string(record)?)
+        .with_context(|| format!("failed to write history file `{}`", path.display()))
+}
+
+// Loads every record in the file. A missing file is an empty history.
+pub fn load(path: &Path) -> Result<Vec<Record>> {
+    let f = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("failed to read history file `{}`", path.display()))
+        }
+    };
+
+    let mut records = Vec::new();
+    for (i, line) in BufReader::new(f).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .with_context(|| format!("{}: line {}: invalid record", path.display(), i + 1))?;
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+// Picks the latest record of the profile and the record to compare it with.
+// The baseline is the latest earlier record of the same kind, or if given, the
+// latest earlier record whose commit starts with `baseline`.
+pub fn select<'a>(
+    records: &'a [Record],
+    profile: &str,
+    baseline: Option<&str>,
+) -> Result<(&'a Record, &'a Record)> {
+    let records: Vec<&Record> = records.iter().filter(|r| r.profile == profile).collect();
+    let Some((current, earlier)) = records.split_last() else {
+        bail!("no timings recorded for profile `{}`", profile);
+    };
+
+    let mut candidates = earlier
+        .iter()
+        .rev()
+        .copied()
+        .filter(|r| r.source == current.source);
+    let found = match baseline {
+        Some(b) => candidates
+            .find(|r| r.commit.as_deref().is_some_and(|c| c.starts_with(b)))
+            .ok_or_else(|| anyhow!("no earlier {} timings for commit `{}`", current.source, b)),
+        None => candidates
+            .next()
+            .ok_or_else(|| anyhow!("no earlier {} timings to compare with", current.source)),
+    }?;
+
+    Ok((found, current))
+}
+
+// Problems are flagged once their combined parse and solve time changes by
+// more than `threshold` (a fraction) and by at least `min_delta`.
+#[derive(Clone, Copy, Debug)]
+pub struct Threshold {
+    pub ratio: f64,
+    pub min_delta: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Change {
+    Slower,
+    Faster,
+    Unchanged,
+}
+
+impl Threshold {
+    fn classify(&self, baseline: Duration, current: Duration) -> Change {
+        let delta = current.abs_diff(baseline);
+        let ratio = current.as_secs_f64() / baseline.as_secs_f64();
+        if delta < self.min_delta {
+            Change::Unchanged
+        } else if ratio > 1.0 + self.ratio {
+            Change::Slower
+        } else if ratio < 1.0 / (1.0 + self.ratio) {
+            Change::Faster
+        } else {
+            Change::Unchanged
+        }
+    }
+}
+
+// Writes a table of the problems timed in both records and returns the number
+// of problems that got slower.
+pub fn write_comparison(
+    w: &mut impl Write,
+    baseline: &Record,
+    current: &Record,
+    threshold: &Threshold,
+) -> Result<usize> {
+    let now = now();
+    writeln!(w, "baseline: {}", baseline.describe(now))?;
+    writeln!(w, "current:  {}\n", current.describe(now))?;
+    writeln!(
+        w,
+        "{:5} {:>12} {:>12} {:>9}",
+        "", "baseline", "current", "change"
+    )?;
+
+    let (mut slower, mut faster, mut unchanged) = (0, 0, 0);
+    for cur in &current.timings {
+        let Some(base) = baseline
+            .timings
+            .iter()
+            .find(|t| t.day == cur.day && t.part == cur.part)
+        else {
+            continue;
+        };
+
+        let (b, c) = (base.total(), cur.total());
+        let change = threshold.classify(b, c);
+        let label = match change {
+            Change::Slower => {
+                slower += 1;
+                "  SLOWER"
+            }
+            Change::Faster => {
+                faster += 1;
+                "  faster"
+            }
+            Change::Unchanged => {
+                unchanged += 1;
+                ""
+            }
+        };
+        writeln!(
+            w,
+            "{:2}-{}: {:>12} {:>12} {:>+8.1}%{}",
+            cur.day,
+            cur.part,
+            format!("{:.2?}", b),
+            format!("{:.2?}", c),
+            (c.as_secs_f64() / b.as_secs_f64() - 1.0) * 100.0,
+            label
+        )?;
+    }
+
+    writeln!(
+        w,
+        "\n{} slower, {} faster, {} unchanged",
+        slower, faster, unchanged
+    )?;
+
+    Ok(slower)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(commit: &str, source: &str, timings: &[(usize, usize, u64)]) -> Record {
+        Record {
+            commit: Some(commit.to_owned()),
+            timestamp: 0,
+            source: source.to_owned(),
+            profile: "default".to_owned(),
+            timings: timings
+                .iter()
+                .map(|&(day, part, us)| Timing {
+                    day,
+                    part,
+                    parse_ns: 0,
+                    duration_ns: us * 1000,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn load_test() {
+        let path = std::env::temp_dir().join(format!("aoc2023-history-{}", std::process::id()));
+        assert!(load(&path).unwrap().is_empty());
+
+        let a = record("abc", "run-all", &[(1, 1, 10)]);
+        let b = record("def", "bench", &[(1, 1, 20)]);
+        append(&path, &a).unwrap();
+        append(&path, &b).unwrap();
+        assert_eq!(load(&path).unwrap(), [a, b]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn select_test() {
+        let records = [
+            record("aaa", "run-all", &[]),
+            record("bbb", "run-all", &[]),
+            record("ccc", "bench", &[]),
+            record("ddd", "run-all", &[]),
+        ];
+
+        let (base, cur) = select(&records, "default", None).unwrap();
+        assert_eq!(base.commit.as_deref(), Some("bbb"));
+        assert_eq!(cur.commit.as_deref(), Some("ddd"));
+
+        let (base, _) = select(&records, "default", Some("aa")).unwrap();
+        assert_eq!(base.commit.as_deref(), Some("aaa"));
+
+        // bench records are only compared with other bench records
+        assert!(select(&records, "default", Some("ccc")).is_err());
+        assert!(select(&records, "alice", None).is_err());
+    }
+
+    #[test]
+    fn write_comparison_test() {
+        let base = record("aaa", "run-all", &[(1, 1, 1000), (1, 2, 1000), (2, 1, 10)]);
+        let cur = record("bbb", "run-all", &[(1, 1, 1500), (1, 2, 500), (2, 1, 20)]);
+        let threshold = Threshold {
+            ratio: 0.1,
+            min_delta: Duration::from_micros(100),
+        };
+
+        let mut buf = Vec::new();
+        let slower = write_comparison(&mut buf, &base, &cur, &threshold).unwrap();
+        assert_eq!(slower, 1);
+
+        let out = String::from_utf8(buf).unwrap();
+        assert!(out.contains(" 1-1:       1.00ms       1.50ms    +50.0%  SLOWER\n"));
+        assert!(out.contains(" 1-2:       1.00ms     500.00µs    -50.0%  faster\n"));
+        // below the minimum delta
+        assert!(out.contains(" 2-1:      10.00µs      20.00µs   +100.0%\n"));
+        assert!(out.ends_with("\n1 slower, 1 faster, 1 unchanged\n"));
+    }
+}
@@ -0,0 +1,296 @@
+use std::borrow::Cow;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use adventofcode2023::solutions::SOLUTIONS;
+
+pub const ENV_VAR: &str = "AOC_INPUTS_DIR";
+pub const PROFILE_ENV_VAR: &str = "AOC_PROFILE";
+pub const DEFAULT_DIR: &str = "puzzle-inputs";
+pub const DEFAULT_PROFILE: &str = "default";
+
+// Locates puzzle inputs. Inputs are read from `<dir>/dayNN.txt` at runtime
+// and fall back to the copy embedded at compile time. Without a directory the
+// embedded copy is preferred and `puzzle-inputs/` is only used when inputs are
+// not embedded.
+//
+// A profile selects the input set of another account stored in
+// `<dir>/<profile>/dayNN.txt`. Profiles never fall back to the embedded
+// inputs.
+#[derive(Clone, Debug, Default)]
+pub struct Inputs {
+    pub dir: Option<PathBuf>,
+    pub profile: Option<String>,
+}
+
+impl Inputs {
+    pub fn new(dir: Option<PathBuf>, profile: Option<String>) -> Self {
+        Self { dir, profile }
+    }
+
+    fn base_dir(&self) -> &Path {
+        self.dir.as_deref().unwrap_or(Path::new(DEFAULT_DIR))
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        match &self.profile {
+            Some(p) => self.base_dir().join(p),
+            None => self.base_dir().to_owned(),
+        }
+    }
+
+    pub fn profile_name(&self) -> &str {
+        self.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    // Returns the default input set followed by every profile found in the
+    // inputs directory.
+    pub fn profiles(&self) -> Result<Vec<Inputs>> {
+        let base = self.base_dir();
+        let entries = std::fs::read_dir(base)
+            .with_context(|| format!("failed to read inputs directory `{}`", base.display()))?;
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            if let Some(name) = entry.file_name().to_str() {
+                names.push(name.to_owned());
+            }
+        }
+        names.sort_unstable();
+
+        let default = Inputs::new(self.dir.clone(), None);
+        let profiles = names
+            .into_iter()
+            .map(|name| Inputs::new(self.dir.clone(), Some(name)));
+
+        Ok(std::iter::once(default).chain(profiles).collect())
+    }
+
+    pub fn answers_path(&self) -> PathBuf {
+        self.dir().join("answers.txt")
+    }
+
+    // The file the input of a day is read from, whether or not it exists or
+    // the day is solved.
+    pub fn path(&self, day: usize) -> PathBuf {
+        self.dir().join(format!("day{:02}.txt", day))
+    }
+
+    // The file to watch for changes to the input of a day. The embedded
+    // inputs never change, so there has to be a file.
+    pub fn watch_path(&self, day: usize) -> Result<PathBuf> {
+        let path = self.path(day);
+        if !path.is_file() {
+            bail!(
+                "cannot watch the input of day {}: `{}` does not exist, pass --input or \
+                 --inputs-dir",
+                day,
+                path.display()
+            );
+        }
+        Ok(path)
+    }
+
+    pub fn load(&self, day: usize) -> Result<Cow<'static, str>> {
+        let solution = SOLUTIONS
+            .get(&day)
+            .ok_or_else(|| anyhow!("unknown day: {}", day))?;
+        let path = self.path(day);
+
+        if self.dir.is_some() || self.profile.is_some() || solution.input.is_none() {
+            match std::fs::read_to_string(&path) {
+                Ok(s) => return Ok(Cow::Owned(s)),
+                Err(e) if e.kind() == ErrorKind::NotFound => (),
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("failed to read input `{}`", path.display()))
+                }
+            }
+        }
+
+        if let Some(profile) = &self.profile {
+            bail!(
+                "no input for day {} in profile `{}`: `{}` does not exist",
+                day,
+                profile,
+                path.display()
+            );
+        }
+
+        solution.input.map(Cow::Borrowed).ok_or_else(|| {
+            anyhow!(
+                "no input for day {}: `{}` does not exist and inputs are not embedded",
+                day,
+                path.display()
+            )
+        })
+    }
+}
+
+// An input given on the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputArg {
+    Stdin,
+    File(PathBuf),
+}
+
+impl InputArg {
+    pub fn read(&self) -> Result<String> {
+        match self {
+            Self::Stdin => {
+                std::io::read_to_string(std::io::stdin()).context("failed to read stdin")
+            }
+            Self::File(p) => std::fs::read_to_string(p)
+                .with_context(|| format!("failed to read input file `{}`", p.display())),
+        }
+    }
+}
+
+impl std::fmt::Display for InputArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stdin => f.write_str("<stdin>"),
+            Self::File(p) => write!(f, "{}", p.display()),
+        }
+    }
+}
+
+// Expands `--input` arguments. Each argument is `-` for stdin, a glob pattern
+// or a plain path.
+pub fn expand_input_args(args: &[String]) -> Result<Vec<InputArg>> {
+    let mut ret = Vec::new();
+
+    for arg in args {
+        if arg == "-" {
+            if ret.contains(&InputArg::Stdin) {
+                bail!("stdin may only be used as an input once");
+            }
+            ret.push(InputArg::Stdin);
+        } else if arg.contains(['*', '?', '[']) {
+            let mut paths = glob::glob(arg)
+                .with_context(|| format!("invalid glob pattern `{}`", arg))?
+                .collect::<Result<Vec<_>, _>>()?;
+            if paths.is_empty() {
+                bail!("no inputs match `{}`", arg);
+            }
+            paths.sort_unstable();
+            ret.extend(paths.into_iter().map(InputArg::File));
+        } else {
+            ret.push(InputArg::File(arg.into()));
+        }
+    }
+
+    Ok(ret)
+}
+
+// Validates a profile name given on the command line. Profiles are plain
+// directory names.
+pub fn parse_profile(s: &str) -> Result<String> {
+    let valid = !s.is_empty()
+        && s != "."
+        && s != ".."
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if !valid {
+        bail!("profile names may only contain letters, digits, `-`, `_` and `.`");
+    }
+    Ok(s.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_test() {
+        let dir = std::env::temp_dir().join(format!("aoc2023-inputs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("day06.txt"), "Time: 7\nDistance: 9\n").unwrap();
+
+        let inputs = Inputs::new(Some(dir.clone()), None);
+        assert_eq!(inputs.load(6).unwrap(), "Time: 7\nDistance: 9\n");
+        assert!(inputs.load(18).is_err());
+
+        #[cfg(feature = "embedded-inputs")]
+        assert_eq!(
+            inputs.load(2).unwrap(),
+            include_str!("../puzzle-inputs/day02.txt")
+        );
+        #[cfg(not(feature = "embedded-inputs"))]
+        assert!(inputs.load(2).is_err());
+
+        assert_eq!(inputs.watch_path(6).unwrap(), dir.join("day06.txt"));
+        let err = inputs.watch_path(2).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "cannot watch the input of day 2: `{}` does not exist, pass --input or \
+                 --inputs-dir",
+                dir.join("day02.txt").display()
+            )
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn profiles_test() {
+        let dir = std::env::temp_dir().join(format!("aoc2023-profiles-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("bob")).unwrap();
+        std::fs::create_dir_all(dir.join("alice")).unwrap();
+        std::fs::write(dir.join("alice/day06.txt"), "Time: 7\nDistance: 9\n").unwrap();
+        std::fs::write(dir.join("day06.txt"), "Time: 30\nDistance: 200\n").unwrap();
+
+        let profiles = Inputs::new(Some(dir.clone()), None).profiles().unwrap();
+        let names: Vec<_> = profiles.iter().map(|p| p.profile_name()).collect();
+        assert_eq!(names, ["default", "alice", "bob"]);
+
+        assert_eq!(profiles[0].load(6).unwrap(), "Time: 30\nDistance: 200\n");
+        assert_eq!(profiles[1].load(6).unwrap(), "Time: 7\nDistance: 9\n");
+        assert_eq!(profiles[1].answers_path(), dir.join("alice/answers.txt"));
+        // profiles do not fall back to the embedded inputs
+        assert!(profiles[2].load(6).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn expand_input_args_test() {
+        let dir = std::env::temp_dir().join(format!("aoc2023-glob-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["b.txt", "a.txt", "c.dat"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let pattern = format!("{}/*.txt", dir.display());
+        let args = ["-".to_owned(), pattern, "x.txt".to_owned()];
+        assert_eq!(
+            expand_input_args(&args).unwrap(),
+            [
+                InputArg::Stdin,
+                InputArg::File(dir.join("a.txt")),
+                InputArg::File(dir.join("b.txt")),
+                InputArg::File("x.txt".into()),
+            ]
+        );
+
+        assert!(expand_input_args(&["-".to_owned(), "-".to_owned()]).is_err());
+        assert!(expand_input_args(&[format!("{}/*.none", dir.display())]).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn parse_profile_test() {
+        assert_eq!(parse_profile("alice-2").unwrap(), "alice-2");
+        assert!(parse_profile("").is_err());
+        assert!(parse_profile("..").is_err());
+        assert!(parse_profile("a/b").is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use adventofcode2023::error::ErrorKind;
+use anyhow::{Context, Result};
+
+use crate::inputs::Inputs;
+use crate::report::{self, ProblemResult, Status};
+
+// Name of the hidden subcommand used to run a single problem in a child
+// process.
+pub const CHILD_COMMAND: &str = "child";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// Runs a single problem in a child process so that a panic, abort or hang
+// only affects that problem. The child prints its `ProblemResult` as JSON.
+// Without an `input` the child loads the day's input itself. `params` are
+// `--param` overrides passed on to the child.
+pub fn run_problem(
+    inputs: &Inputs,
+    day: usize,
+    part: usize,
+    input: Option<&str>,
+    params: &[String],
+    timeout: Duration,
+) -> ProblemResult {
+    match run_child(inputs, day, part, input, params, timeout) {
+        Ok(r) => r,
+        Err(e) => ProblemResult::failed(day, part, Status::Error, format!("{:#}", e)),
+    }
+}
+
+fn run_child(
+    inputs: &Inputs,
+    day: usize,
+    part: usize,
+    input: Option<&str>,
+    params: &[String],
+    timeout: Duration,
+) -> Result<ProblemResult> {
+    let exe = std::env::current_exe().context("failed to locate current executable")?;
+    let mut cmd = Command::new(exe);
+    if let Some(dir) = &inputs.dir {
+        cmd.arg("--inputs-dir").arg(dir);
+    }
+    if let Some(profile) = &inputs.profile {
+        cmd.arg("--profile").arg(profile);
+    }
+    cmd.args([CHILD_COMMAND, &day.to_string(), &part.to_string()]);
+    for p in params {
+        cmd.arg("--param").arg(p);
+    }
+    if input.is_some() {
+        cmd.arg("--stdin").stdin(Stdio::piped());
+    } else {
+        cmd.stdin(Stdio::null());
+    }
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to spawn child process")?;
+
+    // Feed the input in the background for the same reason as draining the
+    // output below. A child that exits early closes the pipe, which only
+    // fails the write.
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        let input = input.to_owned();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+
+    // Drain the pipes in the background so a chatty child cannot block on a
+    // full pipe while we wait on it.
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().context("failed to wait on child")? {
+            break Some(status);
+        }
+        if start.elapsed() >= timeout {
+            // The child may have exited between the calls; either way it is
+            // gone after this.
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let Some(status) = status else {
+        return Ok(ProblemResult::failed(
+            day,
+            part,
+            Status::Timeout,
+            format!("did not finish within {:?}", timeout),
+        ));
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    if !status.success() {
+        let (status_kind, error_kind) = failure(status.code());
+        let message = match status_kind {
+            Status::Panic => panic_message(&stderr),
+            _ => stderr
+                .lines()
+                .rev()
+                .find(|l| !l.trim().is_empty())
+                .map(str::to_owned),
+        };
+        let message = message.unwrap_or_else(|| format!("child exited with {}", status));
+        return Ok(ProblemResult {
+            error_kind,
+            ..ProblemResult::failed(day, part, status_kind, message)
+        });
+    }
+
+    serde_json::from_str(stdout.trim()).context("failed to decode child output")
+}
+
+// How a child that exited with `code` failed. Rust exits with 101 on a panic
+// and a child killed by a signal has no code, which is how aborts end. The
+// typed codes of `report` keep their meaning, anything else failed to run.
+fn failure(code: Option<i32>) -> (Status, Option<ErrorKind>) {
+    match code {
+        None | Some(101) => (Status::Panic, None),
+        Some(code) => report::failure_for_exit_code(code).unwrap_or((Status::Error, None)),
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(mut p) = pipe {
+            let _ = p.read_to_string(&mut buf);
+        }
+        buf
+    })
+}
+
+// Extracts the panic message from the default panic hook output, which looks
+// like:
+//
+//   thread 'main' panicked at src/solutions/day19.rs:22:47:
+//   called `Option::unwrap()` on a `None` value
+//   note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+fn panic_message(stderr: &str) -> Option<String> {
+    let mut lines = stderr.lines().skip_while(|l| !l.contains("panicked at"));
+    let location = lines.next()?;
+    let location = location
+        .split_once("panicked at ")
+        .map(|(_, loc)| loc.trim_end_matches(':'))
+        .unwrap_or(location);
+    let message: Vec<&str> = lines
+        .take_while(|l| !l.starts_with("note: ") && !l.starts_with("stack backtrace:"))
+        .collect();
+
+    Some(format!("{} at {}", message.join("\n"), location))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_message_test() {
+        let stderr = "
+thread 'main' panicked at src/solutions/day17.rs:56:5:
+internal error: entered unreachable code: no solution
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+";
+        assert_eq!(
+            panic_message(stderr).unwrap(),
+            "internal error: entered unreachable code: no solution at src/solutions/day17.rs:56:5"
+        );
+        assert_eq!(panic_message("Aborted"), None);
+    }
+
+    #[test]
+    fn failure_test() {
+        assert_eq!(failure(None), (Status::Panic, None));
+        assert_eq!(failure(Some(101)), (Status::Panic, None));
+        assert_eq!(
+            failure(Some(4)),
+            (Status::Error, Some(ErrorKind::InvalidInput))
+        );
+        assert_eq!(failure(Some(7)), (Status::Timeout, None));
+        assert_eq!(failure(Some(1)), (Status::Error, None));
+    }
+}
@@ -0,0 +1,129 @@
+#[macro_use]
+extern crate lazy_static;
+
+pub mod animation;
+pub mod answer;
+pub mod cross_check;
+pub mod error;
+pub mod generate;
+pub mod grid;
+pub mod mutate;
+pub mod params;
+pub mod parser;
+pub mod render;
+pub mod solutions;
+pub mod trace;
+pub mod utils;
+
+use std::ops::Range;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+pub use answer::Answer;
+
+// Parses the input and solves one part of a day.
+pub fn solve(day: usize, part: usize, input: &str) -> Result<Answer> {
+    let solution = solutions::SOLUTIONS
+        .get(&day)
+        .ok_or_else(|| anyhow!("unknown day: {}", day))?;
+    if !(1..=2).contains(&part) {
+        bail!(solutions::unknown_part(part));
+    }
+
+    let parsed = solution.solver.parse(input)?;
+    solution.solver.solve(&parsed, part)
+}
+
+// Parses the input and draws one part of a day, for the days that can.
+pub fn render(day: usize, part: usize, input: &str) -> Result<render::Canvas> {
+    let solution = solutions::SOLUTIONS
+        .get(&day)
+        .ok_or_else(|| anyhow!("unknown day: {}", day))?;
+    if !(1..=2).contains(&part) {
+        bail!(solutions::unknown_part(part));
+    }
+
+    let parsed = solution.solver.parse(input)?;
+    solution
+        .solver
+        .render(&parsed, part)?
+        .ok_or_else(|| anyhow!("day {} has nothing to render", day))
+}
+
+// Writes a random input of a day.
+pub fn generate(day: usize, seed: u64, size: usize) -> Result<String> {
+    let solution = solutions::SOLUTIONS
+        .get(&day)
+        .ok_or_else(|| anyhow!("unknown day: {}", day))?;
+    let mut gen = generate::InputGenerator::new(seed, size);
+    solution
+        .solver
+        .generate(&mut gen)
+        .ok_or_else(|| anyhow!("day {} has no input generator", day))
+}
+
+// Checks the fast solutions of a day against its reference solutions on
+// generated inputs, and reports the smallest disagreeing input found.
+pub fn cross_check(day: usize, seeds: Range<u64>, size: usize) -> Result<cross_check::Report> {
+    let solution = solutions::SOLUTIONS
+        .get(&day)
+        .ok_or_else(|| anyhow!("unknown day: {}", day))?;
+    cross_check::check(solution.solver, seeds, size)
+        .with_context(|| format!("cannot cross-check day {}", day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_test() {
+        let input = "Time:      7  15   30\nDistance:  9  40  200\n";
+        assert_eq!(solve(6, 1, input).unwrap(), "288");
+        assert_eq!(solve(6, 2, input).unwrap(), "71503");
+        assert!(solve(6, 3, input).is_err());
+        assert!(solve(18, 1, input).is_err());
+        assert!(solve(6, 1, "garbage").is_err());
+    }
+
+    #[test]
+    fn error_kind_test() {
+        use error::ErrorKind;
+
+        let err = solve(6, 1, "Time: 7 15\nDistance: 9 x0\n").unwrap_err();
+        assert_eq!(error::kind(&err), ErrorKind::Parse);
+        assert_eq!(
+            err.to_string(),
+            "failed to parse input at line 2, column 13: unexpected `x0`"
+        );
+
+        let err = solve(10, 1, "...\n.S.\n...\n").unwrap_err();
+        assert_eq!(error::kind(&err), ErrorKind::InvalidInput);
+
+        let input = "19, 13, 30 @ -2, 1, -2\n18, 19, 22 @ -1, -1, -2\n";
+        let err = solve(24, 2, input).unwrap_err();
+        assert_eq!(error::kind(&err), ErrorKind::NotImplemented);
+
+        let err = solve(6, 3, "").unwrap_err();
+        assert_eq!(error::kind(&err), ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "unknown problem number: 3");
+        let parsed = solutions::SOLUTIONS[&6]
+            .solver
+            .parse("Time: 7\nDistance: 9")
+            .unwrap();
+        let err = solutions::SOLUTIONS[&6]
+            .solver
+            .solve(&parsed, 3)
+            .unwrap_err();
+        assert_eq!(error::kind(&err), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn render_test() {
+        let input = "..F7.\n.FJ|.\nSJ.L7\n|F--J\nLJ...\n";
+        assert_eq!(render(10, 1, input).unwrap().size(), (5, 5));
+        assert!(render(10, 3, input).is_err());
+        let err = render(6, 1, "Time: 7\nDistance: 9\n").unwrap_err();
+        assert_eq!(err.to_string(), "day 6 has nothing to render");
+    }
+}
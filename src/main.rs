@@ -1,17 +1,25 @@
-mod grid;
-mod parser;
-mod solutions;
-mod utils;
+mod answers;
+mod bench;
+mod client;
+mod filter;
+mod fuzz;
+mod history;
+mod inputs;
+mod isolate;
+mod memory;
+mod player;
+mod report;
+mod serve;
+mod watch;
 
-#[macro_use]
-extern crate lazy_static;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use std::{
-    cmp::Ordering,
-    time::{Duration, Instant},
-};
-
-use anyhow::{anyhow, Context, Result};
+use adventofcode2023::cross_check::Outcome;
+use adventofcode2023::{answer, error, params, render, solutions, trace};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::{Parser, Subcommand};
 use rayon::prelude::*;
 
@@ -22,105 +30,1361 @@ use rayon::prelude::*;
 struct Cli {
     #[command(subcommand)]
     commands: Commands,
+    /// Directory containing dayNN.txt inputs. Days without a file there use
+    /// the embedded input.
+    #[arg(long, global = true, env = inputs::ENV_VAR)]
+    inputs_dir: Option<PathBuf>,
+    /// Use the inputs in <INPUTS_DIR>/<PROFILE>/ instead
+    #[arg(long, global = true, env = inputs::PROFILE_ENV_VAR, value_parser = inputs::parse_profile)]
+    profile: Option<String>,
+    /// File that run-all and bench append their timings to
+    #[arg(long, global = true, env = history::ENV_VAR, default_value = history::DEFAULT_PATH)]
+    history: PathBuf,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     Run {
         day: usize,
-        problem: usize,
+        /// Runs both parts if omitted
+        problem: Option<usize>,
+        /// Input file or glob to solve instead of the puzzle input. Repeat it
+        /// for more inputs; `-` reads from stdin.
         #[arg(long)]
-        input: Option<String>,
+        input: Vec<String>,
+        /// Solve multiple inputs in parallel
+        #[arg(long)]
+        parallel: bool,
+        /// Print the counters and spans recorded by the solution
+        #[arg(long, short)]
+        verbose: bool,
+        /// Solve again whenever the input file changes
+        #[arg(long, conflicts_with = "parallel")]
+        watch: bool,
+        /// Directory of example inputs to solve and watch along with the
+        /// input
+        #[arg(long, requires = "watch")]
+        examples: Option<PathBuf>,
+        /// How often to check for changes, in milliseconds
+        #[arg(long, default_value_t = 500, requires = "watch")]
+        interval_ms: u64,
+        /// Draw the problem to an image. The format is picked by the
+        /// extension: .png, .ppm or .svg
+        #[arg(long, requires = "problem", conflicts_with = "watch")]
+        render: Option<PathBuf>,
+        /// Pixels per grid cell in the rendered image
+        #[arg(long, default_value_t = 4, requires = "render")]
+        scale: usize,
+        /// Overrides a parameter of the day, see `list`
+        #[arg(long, value_name = "KEY=VALUE")]
+        param: Vec<String>,
     },
+    /// Runs every problem and reports the timings
+    ///
+    /// Exits with the code of the most serious failure: 3 if an input
+    /// failed to parse, 4 if it broke an assumption of the solution, 5 if a
+    /// problem has no solution, 6 on internal errors, 7 on timeouts and 8 on
+    /// panics. Parts that are not implemented yet do not fail the run.
     RunAll {
         #[arg(long)]
         parallel: bool,
+        #[arg(long, value_enum, default_value_t)]
+        format: report::Format,
+        /// Print the full error chain of failed problems
+        #[arg(long, short)]
+        verbose: bool,
+        /// Run each problem in a child process so panics and hangs only fail
+        /// that problem
+        #[arg(long)]
+        isolate: bool,
+        /// Wall-clock limit for each isolated problem, in seconds
+        #[arg(long, default_value_t = 60.0, requires = "isolate")]
+        timeout: f64,
+        /// Run against every profile in the inputs directory and report
+        /// differences between them
+        #[arg(long, conflicts_with = "profile")]
+        all_profiles: bool,
+        /// Do not record the timings in the history file
+        #[arg(long)]
+        no_history: bool,
+        /// Overrides a parameter of the days that declare it, see `list`
+        #[arg(long, value_name = "KEY=VALUE")]
+        param: Vec<String>,
+        #[command(flatten)]
+        filter: filter::ProblemFilter,
+    },
+    /// Runs a single problem and prints the result as JSON. Used by
+    /// `run-all --isolate` and `serve`.
+    #[command(name = isolate::CHILD_COMMAND, hide = true)]
+    Child {
+        day: usize,
+        problem: usize,
+        /// Read the input from stdin
+        #[arg(long)]
+        stdin: bool,
+        #[arg(long, value_name = "KEY=VALUE")]
+        param: Vec<String>,
+    },
+    /// Checks every problem against the known answers
+    Verify {
+        /// Defaults to answers.txt in the inputs directory
+        #[arg(long)]
+        answers: Option<PathBuf>,
+        #[arg(long)]
+        parallel: bool,
+        /// Record answers for problems missing from the answers file
+        #[arg(long)]
+        update: bool,
+        /// Store recorded answers as salted hashes
+        #[arg(long, requires = "update")]
+        hash: bool,
+        #[command(flatten)]
+        filter: filter::ProblemFilter,
+    },
+    /// Repeatedly runs problems and reports timing statistics
+    Bench {
+        #[arg(
+            required_unless_present_any = ["all", "days", "part"],
+            conflicts_with_all = ["all", "days"]
+        )]
+        day: Option<usize>,
+        #[arg(requires = "day", conflicts_with = "part")]
+        problem: Option<usize>,
+        /// Benchmark every problem. Implied by --days and --part.
+        #[arg(long)]
+        all: bool,
+        #[arg(long, requires = "day", conflicts_with_all = ["all", "days"])]
+        input: Option<String>,
+        /// Time spent warming up before measuring, in milliseconds
+        #[arg(long, default_value_t = 200)]
+        warmup_ms: u64,
+        /// Time spent measuring each problem, in milliseconds
+        #[arg(long, default_value_t = 2000)]
+        time_ms: u64,
+        #[arg(long, default_value_t = 5)]
+        min_iters: usize,
+        #[arg(long, default_value_t = 100_000)]
+        max_iters: usize,
+        /// Do not record the timings in the history file. Timings of an
+        /// --input file are never recorded.
+        #[arg(long)]
+        no_history: bool,
+        /// Overrides a parameter of the days that declare it, see `list`
+        #[arg(long, value_name = "KEY=VALUE")]
+        param: Vec<String>,
+        #[command(flatten)]
+        filter: filter::ProblemFilter,
+    },
+    /// Compares the latest recorded timings with an earlier run and fails if
+    /// any problem got slower
+    Compare {
+        /// Commit id of the run to compare with. Defaults to the previous run
+        /// of the same kind.
+        baseline: Option<String>,
+        /// Slowdown in percent at which a problem is flagged
+        #[arg(long, default_value_t = 10.0)]
+        threshold: f64,
+        /// Ignore changes smaller than this many microseconds
+        #[arg(long, default_value_t = 100)]
+        min_delta_us: u64,
+    },
+    /// Downloads the puzzle input of a day into the inputs directory
+    Fetch {
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=25))]
+        day: u8,
+        /// Download the input even if it was already downloaded
+        #[arg(long)]
+        force: bool,
+        #[command(flatten)]
+        client: client::Options,
+    },
+    /// Serves the solvers as a JSON API on localhost
+    Serve {
+        #[arg(long, default_value_t = 8023)]
+        port: u16,
+        /// Wall-clock limit for solving a problem, in seconds
+        #[arg(long, default_value_t = 60.0)]
+        timeout: f64,
+        /// Connections handled at once; more are answered with 503
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        max_connections: u16,
+    },
+    /// Submits an answer and records it in the answers file if it is right
+    Submit {
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=25))]
+        day: u8,
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=2))]
+        part: u8,
+        /// Solves the problem if omitted
+        answer: Option<String>,
+        #[command(flatten)]
+        client: client::Options,
+    },
+    /// Plays the simulation of a problem step by step in the terminal.
+    /// Prints every frame instead when stdout is not a terminal.
+    Animate {
+        day: usize,
+        #[arg(default_value_t = 1)]
+        problem: usize,
+        /// Input file to animate instead of the puzzle input. `-` reads from
+        /// stdin.
+        #[arg(long)]
+        input: Option<String>,
+        /// Frames per second to start playing at
+        #[arg(long, default_value_t = 10.0)]
+        fps: f64,
+        /// Overrides a parameter of the day, see `list`
+        #[arg(long, value_name = "KEY=VALUE")]
+        param: Vec<String>,
+    },
+    /// Lists the days and the parameters they take
+    List,
+    /// Prints a random input for a day
+    Generate {
+        day: usize,
+        /// Picked at random and printed to stderr if omitted
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Roughly how many lines the input has
+        #[arg(long, default_value_t = 20)]
+        size: usize,
+    },
+    /// Checks the solutions of a day against slow reference solutions on
+    /// random inputs
+    CrossCheck {
+        day: usize,
+        /// How many inputs to try, generated from seeds 0, 1, ...
+        #[arg(long, default_value_t = 100)]
+        seeds: u64,
+        /// Roughly how many lines each input has. The reference solutions
+        /// are slow on big inputs.
+        #[arg(long, default_value_t = 8)]
+        size: usize,
+        /// Wall-clock limit for each run of the fast solution, in seconds
+        #[arg(long, default_value_t = 10.0)]
+        timeout: f64,
+        /// Overrides a parameter of the day, see `list`. Day 14 part 2 is only
+        /// checked with a small `cycles=`, like 1000, since its reference runs
+        /// every cycle.
+        #[arg(long, value_name = "KEY=VALUE")]
+        param: Vec<String>,
+    },
+    /// Feeds broken and random inputs to the solvers and reports the ones
+    /// that make them panic or hang
+    Fuzz {
+        /// Mutated inputs to try per day
+        #[arg(long, default_value_t = 200)]
+        iterations: usize,
+        /// Picked at random and printed to stderr if omitted
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Wall-clock limit for each input, in seconds
+        #[arg(long, default_value_t = 10.0)]
+        timeout: f64,
+        /// Directory of inputs to mutate besides the puzzle inputs, in
+        /// `dayNN/` subdirectories. Failing inputs are saved there.
+        #[arg(long, default_value = "fuzz-corpus")]
+        corpus: PathBuf,
+        #[command(flatten)]
+        filter: filter::ProblemFilter,
     },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let inputs = inputs::Inputs::new(cli.inputs_dir, cli.profile);
 
     match cli.commands {
         Commands::Run {
             day,
             problem,
             input,
-        } => run(day, problem, input),
-        Commands::RunAll { parallel } => run_all(parallel),
+            watch: true,
+            examples,
+            interval_ms,
+            param,
+            ..
+        } => {
+            let overrides = overrides(day, &param)?;
+            params::with(&overrides, || {
+                run_watch(
+                    day,
+                    problem,
+                    &inputs,
+                    &input,
+                    examples,
+                    Duration::from_millis(interval_ms),
+                )
+            })
+        }
+        Commands::Run {
+            day,
+            problem,
+            input,
+            parallel,
+            verbose,
+            render,
+            scale,
+            param,
+            ..
+        } => {
+            let overrides = overrides(day, &param)?;
+            params::with(&overrides, || {
+                run(
+                    &inputs,
+                    day,
+                    problem,
+                    &input,
+                    parallel,
+                    verbose,
+                    render.as_deref().map(|path| (path, scale)),
+                )
+            })
+        }
+        Commands::RunAll {
+            parallel,
+            format,
+            verbose,
+            isolate,
+            timeout,
+            all_profiles,
+            no_history,
+            param,
+            filter,
+        } => {
+            let timeout = isolate
+                .then(|| Duration::try_from_secs_f64(timeout))
+                .transpose()
+                .context("invalid timeout")?;
+            let profiles = match all_profiles {
+                true => Some(inputs.profiles()?),
+                false => None,
+            };
+            let params = shared_params(&filter.days(), &param)?;
+            let results = run_all(
+                &inputs,
+                profiles.as_deref(),
+                &filter,
+                &params,
+                parallel,
+                timeout,
+            );
+            report::write(&mut std::io::stdout().lock(), format, verbose, &results)?;
+            if !no_history {
+                record_run_all(&cli.history, &inputs, &results)?;
+            }
+            let code = report::exit_code(&results);
+            if code != 0 {
+                std::io::stdout().flush()?;
+                std::process::exit(code.into());
+            }
+            Ok(())
+        }
+        Commands::Child {
+            day,
+            problem,
+            stdin,
+            param,
+        } => {
+            let input = stdin
+                .then(|| std::io::read_to_string(std::io::stdin()))
+                .transpose()
+                .context("failed to read stdin")?;
+            let overrides = overrides(day, &param)?;
+            let res = params::with(&overrides, || {
+                run_parts(&inputs, day, &[problem], input.as_deref()).remove(0)
+            });
+            let res = report::ProblemResult::new(day, problem, res);
+            println!("{}", serde_json::to_string(&res)?);
+            Ok(())
+        }
+        Commands::Verify {
+            answers,
+            parallel,
+            update,
+            hash,
+            filter,
+        } => {
+            let answers = answers.unwrap_or_else(|| inputs.answers_path());
+            verify(&inputs, &filter, &answers, parallel, update, hash)
+        }
+        Commands::Bench {
+            day,
+            problem,
+            all: _,
+            input,
+            warmup_ms,
+            time_ms,
+            min_iters,
+            max_iters,
+            no_history,
+            param,
+            filter,
+        } => {
+            let budget = bench::Budget {
+                warmup: Duration::from_millis(warmup_ms),
+                measure: Duration::from_millis(time_ms),
+                min_iters,
+                max_iters: max_iters.max(1),
+            };
+            let history = (!no_history).then_some(cli.history.as_path());
+            let days: Vec<(usize, Vec<usize>)> = match (day, problem, filter.part) {
+                (Some(day), Some(problem), _) => vec![(day, vec![problem])],
+                (Some(day), None, Some(part)) => vec![(day, vec![part as usize])],
+                (Some(day), None, None) => vec![(day, vec![1, 2])],
+                (None, _, _) => filter.days(),
+            };
+            let params = shared_params(&days, &param)?;
+            run_bench(&inputs, &days, &params, input, &budget, history)
+        }
+        Commands::Compare {
+            baseline,
+            threshold,
+            min_delta_us,
+        } => {
+            let threshold = history::Threshold {
+                ratio: threshold / 100.0,
+                min_delta: Duration::from_micros(min_delta_us),
+            };
+            compare(
+                &cli.history,
+                inputs.profile_name(),
+                baseline.as_deref(),
+                &threshold,
+            )
+        }
+        Commands::Fetch { day, force, client } => fetch(&inputs, day as usize, force, &client),
+        Commands::Serve {
+            port,
+            timeout,
+            max_connections,
+        } => {
+            let timeout = Duration::try_from_secs_f64(timeout).context("invalid timeout")?;
+            // Every problem runs in a child process like `run-all --isolate`
+            // so a panic or hang does not take the server down.
+            let inputs = inputs::Inputs::default();
+            serve::serve(port, max_connections as usize, &|day, part, input| {
+                isolate::run_problem(&inputs, day, part, Some(input), &[], timeout)
+            })
+        }
+        Commands::Submit {
+            day,
+            part,
+            answer,
+            client,
+        } => submit(&inputs, day as usize, part as usize, answer, &client),
+        Commands::Animate {
+            day,
+            problem,
+            input,
+            fps,
+            param,
+        } => {
+            let overrides = overrides(day, &param)?;
+            params::with(&overrides, || animate(&inputs, day, problem, input, fps))
+        }
+        Commands::List => {
+            list();
+            Ok(())
+        }
+        Commands::Generate { day, seed, size } => {
+            let seed = seed.unwrap_or_else(|| {
+                let seed = rand::random();
+                eprintln!("seed {}", seed);
+                seed
+            });
+            println!("{}", adventofcode2023::generate(day, seed, size)?);
+            Ok(())
+        }
+        Commands::Fuzz {
+            iterations,
+            seed,
+            timeout,
+            corpus,
+            filter,
+        } => {
+            let seed = seed.unwrap_or_else(|| {
+                let seed = rand::random();
+                eprintln!("seed {}", seed);
+                seed
+            });
+            let options = fuzz::Options {
+                seed,
+                iterations,
+                timeout: Duration::try_from_secs_f64(timeout).context("invalid timeout")?,
+                corpus,
+            };
+            let failures = fuzz::fuzz(&inputs, &filter, &options)?;
+            if failures > 0 {
+                bail!("{} inputs made the solvers panic or hang", failures);
+            }
+            Ok(())
+        }
+        Commands::CrossCheck {
+            day,
+            seeds,
+            size,
+            timeout,
+            param,
+        } => {
+            let timeout = Duration::try_from_secs_f64(timeout).context("invalid timeout")?;
+            let overrides = overrides(day, &param)?;
+            params::with(&overrides, || {
+                cross_check(&inputs, day, seeds, size, &param, timeout)
+            })
+        }
+    }
+}
+
+fn run(
+    inputs: &inputs::Inputs,
+    day: usize,
+    problem: Option<usize>,
+    input: &[String],
+    parallel: bool,
+    verbose: bool,
+    render: Option<(&Path, usize)>,
+) -> Result<()> {
+    if let Some((path, _)) = render {
+        render::Format::from_path(path)?;
+    }
+
+    let input_args = inputs::expand_input_args(input)?;
+    if input_args.len() > 1 {
+        if render.is_some() {
+            bail!("--render needs a single input");
+        }
+        return run_batch(day, problem, &input_args, parallel);
+    }
+
+    let flag_input = input_args.first().map(|x| x.read()).transpose()?;
+
+    let Some(problem) = problem else {
+        let results = run_day(inputs, day, &[1, 2], flag_input.as_deref())?;
+        println!("Parsed in {}", results.parse);
+        if verbose {
+            results.parse.print_trace();
+        }
+        println!();
+
+        let mut failed = false;
+        for (problem, res) in (1..).zip(results.parts) {
+            println!("Part {}:", problem);
+            match res {
+                Ok((ans, cost)) => {
+                    println!("{}", ans);
+                    println!("\nComputed in {}", cost);
+                    if verbose {
+                        cost.print_trace();
+                    }
+                    println!();
+                }
+                Err(e) => {
+                    println!("Error: {:?}\n", e);
+                    failed = true;
+                }
+            }
+        }
+
+        if failed {
+            bail!("day {} failed", day);
+        }
+        return Ok(());
+    };
+
+    let results = run_day(inputs, day, &[problem], flag_input.as_deref())?;
+    let (ans, cost) = results.parts.into_iter().next().unwrap()?;
+
+    println!("{}", ans);
+    println!("\nParsed in {}", results.parse);
+    if verbose {
+        results.parse.print_trace();
+    }
+    println!("Computed in {}", cost);
+    if verbose {
+        cost.print_trace();
+    }
+
+    if let Some((path, scale)) = render {
+        let input = match flag_input {
+            Some(x) => x.into(),
+            None => inputs.load(day)?,
+        };
+        let canvas = adventofcode2023::render(day, problem, &input)?;
+        canvas.save(path, scale)?;
+        println!("Rendered to {}", path.display());
+    }
+
+    Ok(())
+}
+
+// Solves each input on its own and reports the results in the order given.
+fn run_batch(
+    day: usize,
+    problem: Option<usize>,
+    input_args: &[inputs::InputArg],
+    parallel: bool,
+) -> Result<()> {
+    let problems = match problem {
+        Some(p) => vec![p],
+        None => vec![1, 2],
+    };
+    // Inputs are solved on their own, so the default inputs are never used.
+    let inputs = inputs::Inputs::default();
+    // Parameters are per thread, so carry them over to the workers
+    let overrides = params::current();
+
+    let results = map_items(input_args, parallel, |arg| {
+        params::with(&overrides, || {
+            arg.read()
+                .and_then(|input| run_day(&inputs, day, &problems, Some(&input)))
+        })
+    });
+
+    let mut failed = 0;
+    for (arg, res) in input_args.iter().zip(&results) {
+        let day_run = match res {
+            Ok(x) => x,
+            Err(e) => {
+                println!("{}\n  ERROR: {:#}", arg, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        println!("{} (parsed in {})", arg, day_run.parse);
+        for (p, res) in problems.iter().zip(&day_run.parts) {
+            match res {
+                Ok((ans, cost)) => println!("  part {}: {} ({})", p, ans, cost),
+                Err(e) => println!("  part {}: ERROR: {:#}", p, e),
+            }
+        }
+        if day_run.parts.iter().any(|x| x.is_err()) {
+            failed += 1;
+        }
+    }
+
+    println!("\n{} inputs, {} failed", results.len(), failed);
+    if failed > 0 {
+        bail!("{} inputs failed", failed);
+    }
+
+    Ok(())
+}
+
+// Solves the input, and any examples, every time they change until
+// interrupted. Each part's answer is compared with the one of the previous
+// run of the same file.
+fn run_watch(
+    day: usize,
+    problem: Option<usize>,
+    inputs: &inputs::Inputs,
+    input: &[String],
+    examples: Option<PathBuf>,
+    interval: Duration,
+) -> Result<()> {
+    let problems = match problem {
+        Some(p) => vec![p],
+        None => vec![1, 2],
+    };
+    lookup(day, &problems)?;
+    let input = match inputs::expand_input_args(input)?.as_slice() {
+        [] => inputs.watch_path(day)?,
+        [inputs::InputArg::File(p)] => p.clone(),
+        _ => bail!("--watch needs a single input file"),
+    };
+
+    let mut watcher = watch::Watcher::new(vec![input.clone()], examples.into_iter().collect());
+    let mut previous = HashMap::new();
+    println!("Watching for changes, press Ctrl-C to stop\n");
+    loop {
+        let mut changed = watcher.poll();
+        // The input comes before the examples
+        changed.sort_by_key(|p| *p != input);
+        for path in changed {
+            if path != input && !path.exists() {
+                println!("{} removed\n", path.display());
+                previous.retain(|(p, _), _| *p != path);
+                continue;
+            }
+            solve_watched(day, &problems, &path, &mut previous);
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+// The outcome of each part of each watched file, keyed by path and part.
+type WatchedAnswers = HashMap<(PathBuf, usize), Result<answer::Answer, String>>;
+
+fn solve_watched(day: usize, problems: &[usize], path: &Path, previous: &mut WatchedAnswers) {
+    // Only the watched file is ever read.
+    let inputs = inputs::Inputs::default();
+    let res = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read input file `{}`", path.display()))
+        .and_then(|input| run_day(&inputs, day, problems, Some(&input)));
+
+    let parts: Vec<_> = match res {
+        Ok(day_run) => {
+            println!("{} (parsed in {})", path.display(), day_run.parse);
+            day_run.parts
+        }
+        Err(e) => {
+            println!("{}", path.display());
+            // nom errors end with a newline
+            let e = format!("{:#}", e).trim_end().to_owned();
+            problems.iter().map(|_| Err(anyhow!("{}", e))).collect()
+        }
+    };
+
+    for (&p, res) in problems.iter().zip(parts) {
+        let (line, outcome) = match res {
+            Ok((ans, cost)) => (format!("{} ({})", ans, cost), Ok(ans)),
+            Err(e) => (format!("ERROR: {:#}", e), Err(format!("{:#}", e))),
+        };
+        let key = (path.to_owned(), p);
+        match watch::describe_change(previous.get(&key), &outcome) {
+            Some(change) => println!("  part {}: {} [{}]", p, line, change),
+            None => println!("  part {}: {}", p, line),
+        }
+        previous.insert(key, outcome);
+    }
+    println!();
+}
+
+fn run_all(
+    inputs: &inputs::Inputs,
+    profiles: Option<&[inputs::Inputs]>,
+    filter: &filter::ProblemFilter,
+    params: &DayParams,
+    parallel: bool,
+    isolate_timeout: Option<Duration>,
+) -> Vec<report::ProblemResult> {
+    // Isolated problems run in their own process, so each part parses the
+    // input itself.
+    let run = |inputs: &inputs::Inputs, day, problems: &[usize]| {
+        let (args, overrides) = &params[&day];
+        match isolate_timeout {
+            Some(timeout) => problems
+                .iter()
+                .map(|&p| isolate::run_problem(inputs, day, p, None, args, timeout))
+                .collect(),
+            None => params::with(overrides, || {
+                problems
+                    .iter()
+                    .zip(run_parts(inputs, day, problems, None))
+                    .map(|(&p, res)| report::ProblemResult::new(day, p, res))
+                    .collect::<Vec<_>>()
+            }),
+        }
+    };
+
+    let results = match profiles {
+        Some(profiles) => {
+            let items: Vec<_> = profiles
+                .iter()
+                .flat_map(|p| filter.days().into_iter().map(move |(d, ps)| (p, d, ps)))
+                .collect();
+            map_items(&items, parallel, |(p, day, problems)| {
+                run(p, *day, problems)
+                    .into_iter()
+                    .map(|r| r.with_profile(p.profile_name()))
+                    .collect()
+            })
+        }
+        None => map_items(&filter.days(), parallel, |(day, problems)| {
+            run(inputs, *day, problems)
+        }),
+    };
+    results.into_iter().flatten().collect()
+}
+
+// Appends one history record per profile with the problems that succeeded.
+fn record_run_all(
+    path: &Path,
+    inputs: &inputs::Inputs,
+    results: &[report::ProblemResult],
+) -> Result<()> {
+    let mut timings: BTreeMap<&str, Vec<history::Timing>> = BTreeMap::new();
+    for r in results {
+        let (Some(parse), Some(solve)) = (r.parse_duration, r.duration) else {
+            continue;
+        };
+        let profile = r.profile.as_deref().unwrap_or(inputs.profile_name());
+        timings
+            .entry(profile)
+            .or_default()
+            .push(history::Timing::new(r.day, r.part, parse, solve));
+    }
+
+    for (profile, timings) in timings {
+        history::append(path, &history::Record::new("run-all", profile, timings))?;
+    }
+
+    Ok(())
+}
+
+fn verify(
+    inputs: &inputs::Inputs,
+    filter: &filter::ProblemFilter,
+    answers_path: &Path,
+    parallel: bool,
+    update: bool,
+    hash: bool,
+) -> Result<()> {
+    let mut known = match answers::Answers::load(answers_path) {
+        Ok(x) => x,
+        Err(_) if update && !answers_path.exists() => Default::default(),
+        Err(e) => return Err(e),
+    };
+
+    let outcomes = map_items(&filter.days(), parallel, |(day, problems)| {
+        let results = run_parts(inputs, *day, problems, None);
+        problems
+            .iter()
+            .zip(results)
+            .map(|(&problem, res)| {
+                let got = res.map(|(ans, _)| ans).map_err(|e| format!("{:#}", e));
+                let outcome = answers::Outcome::new(known.get(*day, problem), got);
+                (*day, problem, outcome)
+            })
+            .collect::<Vec<_>>()
+    });
+    let outcomes: Vec<_> = outcomes.into_iter().flatten().collect();
+
+    let mut failed = 0;
+    let mut missing = 0;
+    for (day, problem, outcome) in &outcomes {
+        println!("{:2}-{}: {}", day, problem, outcome);
+        match outcome {
+            answers::Outcome::Pass => (),
+            answers::Outcome::Fail { .. } => failed += 1,
+            answers::Outcome::Missing { .. } => missing += 1,
+        }
+    }
+    println!(
+        "\n{} passed, {} failed, {} missing",
+        outcomes.len() - failed - missing,
+        failed,
+        missing
+    );
+
+    if update {
+        let recorded: Vec<_> = outcomes
+            .into_iter()
+            .filter_map(|(day, problem, outcome)| match outcome {
+                answers::Outcome::Missing { got: Ok(ans) } => Some((day, problem, ans)),
+                _ => None,
+            })
+            .collect();
+
+        for (day, problem, ans) in &recorded {
+            let expected = match hash {
+                true => answers::Expected::hashed(ans),
+                false => answers::Expected::Plain(ans.to_string()),
+            };
+            known.entries.insert((*day, *problem), expected);
+        }
+
+        std::fs::write(answers_path, known.to_string()).with_context(|| {
+            format!("failed to write answers file `{}`", answers_path.display())
+        })?;
+        println!(
+            "recorded {} answers in {}",
+            recorded.len(),
+            answers_path.display()
+        );
+    }
+
+    if failed > 0 {
+        bail!("{} problems did not match the known answers", failed);
+    }
+
+    Ok(())
+}
+
+// Requests from every run share one rate limit.
+fn new_client(options: &client::Options) -> Result<client::Client> {
+    let state_path = std::env::temp_dir().join("aoc2023-last-request");
+    client::Client::new(options, state_path)
+}
+
+// Downloads the input of a day unless it is already there.
+fn fetch(
+    inputs: &inputs::Inputs,
+    day: usize,
+    force: bool,
+    options: &client::Options,
+) -> Result<()> {
+    let path = inputs.path(day);
+    if path.exists() && !force {
+        println!("{} already exists", path.display());
+        return Ok(());
+    }
+
+    let input = new_client(options)?.fetch_input(day)?;
+    std::fs::create_dir_all(inputs.dir())
+        .with_context(|| format!("failed to create `{}`", inputs.dir().display()))?;
+    std::fs::write(&path, input)
+        .with_context(|| format!("failed to write input `{}`", path.display()))?;
+    println!("saved the input of day {} to {}", day, path.display());
+
+    Ok(())
+}
+
+// Submits an answer, solving the problem first if no answer is given. Answers
+// the server accepts are recorded in the answers file, and answers already
+// recorded there are not submitted again.
+fn submit(
+    inputs: &inputs::Inputs,
+    day: usize,
+    part: usize,
+    answer: Option<String>,
+    options: &client::Options,
+) -> Result<()> {
+    let answer = match answer {
+        Some(a) => answer::Answer::from(a.trim()),
+        None => {
+            let mut day_run = run_day(inputs, day, &[part], None)?;
+            let (ans, cost) = day_run.parts.remove(0)?;
+            println!("{}-{}: {} ({})", day, part, ans, cost);
+            ans
+        }
+    };
+    if answer == answer::Answer::None {
+        bail!("{}-{} has no answer to submit", day, part);
+    }
+
+    let answers_path = inputs.answers_path();
+    let mut known = match answers_path.exists() {
+        true => answers::Answers::load(&answers_path)?,
+        false => Default::default(),
+    };
+    match known.get(day, part) {
+        Some(e) if e.matches(&answer) => {
+            println!("{} was already accepted", answer);
+            return Ok(());
+        }
+        Some(_) => bail!(
+            "{}-{} was already solved with a different answer, see {}",
+            day,
+            part,
+            answers_path.display()
+        ),
+        None => (),
+    }
+
+    let verdict = new_client(options)?.submit(day, part, &answer.to_string())?;
+    match verdict {
+        client::Verdict::Correct => {
+            known
+                .entries
+                .insert((day, part), answers::Expected::Plain(answer.to_string()));
+            std::fs::write(&answers_path, known.to_string()).with_context(|| {
+                format!("failed to write answers file `{}`", answers_path.display())
+            })?;
+            println!(
+                "{} is the right answer, recorded in {}",
+                answer,
+                answers_path.display()
+            );
+            Ok(())
+        }
+        client::Verdict::Wrong { hint: Some(hint) } => {
+            bail!("{} is not the right answer, it is {}", answer, hint)
+        }
+        client::Verdict::Wrong { hint: None } => bail!("{} is not the right answer", answer),
+        client::Verdict::TooSoon { wait: Some(wait) } => {
+            bail!("answered too recently, try again in {:?}", wait)
+        }
+        client::Verdict::TooSoon { wait: None } => bail!("answered too recently"),
+        client::Verdict::AlreadySolved => {
+            println!(
+                "{}-{} is already solved, the answer was not checked",
+                day, part
+            );
+            Ok(())
+        }
+        client::Verdict::Unknown(text) => bail!("unexpected response: {}", text),
     }
 }
 
-fn run(day: usize, problem: usize, input: Option<String>) -> Result<()> {
+fn run_bench(
+    inputs: &inputs::Inputs,
+    days: &[(usize, Vec<usize>)],
+    params: &DayParams,
+    input: Option<String>,
+    budget: &bench::Budget,
+    history: Option<&Path>,
+) -> Result<()> {
     let flag_input = input
         .as_ref()
         .map(|x| std::fs::read_to_string(x).context("failed to read input file"))
         .transpose()?;
 
-    let (ans, duration) = run_problem(day, problem, flag_input.as_deref())?;
+    println!(
+        "{:9} {:>12} {:>12} {:>12} {:>12} {:>12} {:>8}",
+        "", "min", "median", "mean", "p95", "stddev", "samples"
+    );
 
-    println!("{}", ans);
-    println!("\nComputed in {:?}", duration);
+    let print_row = |label: String, res: Result<Measured>| match res {
+        Ok((s, alloc)) => println!(
+            "{:9} {:>12} {:>12} {:>12} {:>12} {:>12} {:>8}{}",
+            label + ":",
+            format!("{:.2?}", s.min),
+            format!("{:.2?}", s.median),
+            format!("{:.2?}", s.mean),
+            format!("{:.2?}", s.p95),
+            format!("{:.2?}", s.stddev),
+            s.samples,
+            alloc.map(|a| format!("  {}", a)).unwrap_or_default(),
+        ),
+        Err(e) => println!("{:9} ERROR: {:#}", label + ":", e),
+    };
+
+    let mut timings = Vec::new();
+    for (day, problems) in days {
+        let day = *day;
+        let res = params::with(&params[&day].1, || {
+            bench_day(inputs, day, problems, flag_input.as_deref(), budget)
+        });
+        match res {
+            Ok((parse, parts)) => {
+                print_row(format!("{:2} parse", day), Ok(parse));
+                for (&problem, res) in problems.iter().zip(parts) {
+                    if let Ok((s, _)) = &res {
+                        timings.push(history::Timing::new(day, problem, parse.0.median, s.median));
+                    }
+                    print_row(format!("{:2}-{}", day, problem), res);
+                }
+            }
+            Err(e) => print_row(format!("{:2} parse", day), Err(e)),
+        }
+    }
+
+    if let (Some(path), None) = (history, &flag_input) {
+        if !timings.is_empty() {
+            let record = history::Record::new("bench", inputs.profile_name(), timings);
+            history::append(path, &record)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn compare(
+    history_path: &Path,
+    profile: &str,
+    baseline: Option<&str>,
+    threshold: &history::Threshold,
+) -> Result<()> {
+    let records = history::load(history_path)?;
+    let (baseline, current) = history::select(&records, profile, baseline)?;
+
+    let slower =
+        history::write_comparison(&mut std::io::stdout().lock(), baseline, current, threshold)?;
+    if slower > 0 {
+        bail!("{} problems got slower", slower);
+    }
 
     Ok(())
 }
 
-fn run_all(parallel: bool) -> Result<()> {
-    let days = {
-        let mut d: Vec<usize> = solutions::SOLUTIONS.keys().copied().collect();
-        d.sort_unstable();
-        d
+// Timing statistics of a benchmarked step along with the allocations of one
+// extra run, if allocations are counted.
+type Measured = (bench::Stats, Option<memory::AllocStats>);
+
+// Benchmarks parsing a day's input and then solving each part from a single
+// parsed copy. The input is loaded once up front and is not timed.
+fn bench_day(
+    inputs: &inputs::Inputs,
+    day: usize,
+    problems: &[usize],
+    input: Option<&str>,
+    budget: &bench::Budget,
+) -> Result<(Measured, Vec<Result<Measured>>)> {
+    let solution = solutions::SOLUTIONS
+        .get(&day)
+        .ok_or(anyhow!("unknown day: {}", day))?;
+    let input = match input {
+        Some(x) => x.into(),
+        None => inputs.load(day)?,
     };
 
-    let mut times: Vec<_> = if parallel {
-        days.par_iter()
-            .copied()
-            .flat_map(|day| [(day, 1), (day, 2)])
-            .map(|(day, problem)| (day, problem, run_problem(day, problem, None)))
-            .collect()
+    let measure = |f: &dyn Fn() -> Result<()>| -> Result<Measured> {
+        let stats = bench::bench(budget, f)?;
+        let alloc = match memory::ENABLED {
+            true => memory::measure(f).1,
+            false => None,
+        };
+        Ok((stats, alloc))
+    };
+
+    let parse = measure(&|| solution.solver.parse(&input).map(drop))?;
+    let parsed = solution.solver.parse(&input)?;
+    let parts = problems
+        .iter()
+        .map(|&problem| measure(&|| solution.solver.solve(&parsed, problem).map(drop)))
+        .collect();
+
+    Ok((parse, parts))
+}
+
+// Calls `f` for every item, returning the results in the same order.
+fn map_items<I: Sync, T: Send>(items: &[I], parallel: bool, f: impl Fn(&I) -> T + Sync) -> Vec<T> {
+    if parallel {
+        items.par_iter().map(&f).collect()
     } else {
-        days.iter()
-            .copied()
-            .flat_map(|day| [(day, 1), (day, 2)])
-            .map(|(day, problem)| (day, problem, run_problem(day, problem, None)))
-            .collect()
-    };
-
-    // Sort by duration in descending order. Errors are sorted at the bottom
-    // by day/part.
-    times.sort_by(|a, b| match (&a.2, &b.2) {
-        (Ok(a_res), Ok(b_res)) => a_res.1.cmp(&b_res.1).reverse(),
-        (Err(_), Err(_)) => a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)),
-        (Ok(_), Err(_)) => Ordering::Less,
-        (Err(_), Ok(_)) => Ordering::Greater,
-    });
+        items.iter().map(f).collect()
+    }
+}
+
+// How long a step took, what it allocated when counted and what the
+// solution traced while running it.
+#[derive(Clone, Debug)]
+struct Cost {
+    duration: Duration,
+    alloc: Option<memory::AllocStats>,
+    trace: trace::Trace,
+}
+
+impl Cost {
+    fn measure<T>(f: impl FnOnce() -> T) -> (T, Self) {
+        let (((ret, duration), alloc), trace) = trace::collect(|| {
+            memory::measure(|| {
+                let start = Instant::now();
+                let ret = f();
+                (ret, start.elapsed())
+            })
+        });
+        let cost = Cost {
+            duration,
+            alloc,
+            trace,
+        };
+        (ret, cost)
+    }
 
-    for (day, problem, res) in &times {
-        if let Ok((_, duration)) = res {
-            println!("{:2}-{}: {:?}", day, problem, duration);
-        } else {
-            println!("{:2}-{}: ERROR", day, problem);
+    // Prints the trace indented below the step.
+    fn print_trace(&self) {
+        for line in self.trace.to_string().lines() {
+            println!("  {}", line);
         }
     }
+}
 
-    Ok(())
+impl std::fmt::Display for Cost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.duration)?;
+        if let Some(alloc) = &self.alloc {
+            write!(f, " ({})", alloc)?;
+        }
+        Ok(())
+    }
 }
 
-fn run_problem(day: usize, problem: usize, input: Option<&str>) -> Result<(String, Duration)> {
+// The answers of some parts of a day. `parse` is the cost of parsing the
+// input, which all parts share.
+struct DayRun {
+    parse: Cost,
+    parts: Vec<Result<(answer::Answer, Cost)>>,
+}
+
+// Finds the solution of a day and checks that it has the given parts.
+fn lookup(day: usize, problems: &[usize]) -> Result<&'static solutions::Entry> {
     let solution = solutions::SOLUTIONS
         .get(&day)
         .ok_or(anyhow!("unknown day: {}", day))?;
-    let problem_fn = match problem {
-        1 => solution.problem1,
-        2 => solution.problem2,
-        _ => return Err(anyhow!("unknown problem number: {}", problem)),
+    if let Some(p) = problems.iter().find(|p| !(1..=2).contains(*p)) {
+        bail!("unknown problem number: {}", p);
+    }
+    Ok(solution)
+}
+
+// Checks `--param` values against the parameters the day declares.
+fn overrides(day: usize, args: &[String]) -> Result<params::Overrides> {
+    let solution = lookup(day, &[])?;
+    params::Overrides::parse(solution.solver.params(), args)
+}
+
+// The `--param` values of each day, as given on the command line for isolated
+// children and checked.
+type DayParams = BTreeMap<usize, (Vec<String>, params::Overrides)>;
+
+// Splits `--param` values given to several days at once between the days
+// that declare them. Each value must be meant for at least one of the days.
+fn shared_params(days: &[(usize, Vec<usize>)], args: &[String]) -> Result<DayParams> {
+    let mut params = DayParams::new();
+    for &(day, _) in days {
+        let declared = lookup(day, &[])?.solver.params();
+        let own: Vec<String> = args
+            .iter()
+            .filter(|arg| {
+                let key = arg.split_once('=').map_or(arg.as_str(), |(key, _)| key);
+                declared.iter().any(|p| p.name() == key)
+            })
+            .cloned()
+            .collect();
+        let overrides = params::Overrides::parse(declared, &own)
+            .with_context(|| format!("invalid parameter for day {}", day))?;
+        params.insert(day, (own, overrides));
+    }
+    if let Some(arg) = args
+        .iter()
+        .find(|arg| !params.values().any(|(own, _)| own.contains(arg)))
+    {
+        bail!("no day that runs has the parameter `{}`, see `list`", arg);
+    }
+    Ok(params)
+}
+
+// Parses the input of a day once and solves the given parts from it. Failing
+// to load or parse the input fails the whole day.
+fn run_day(
+    inputs: &inputs::Inputs,
+    day: usize,
+    problems: &[usize],
+    input: Option<&str>,
+) -> Result<DayRun> {
+    let solution = lookup(day, problems)?;
+    let input = match input {
+        Some(x) => x.into(),
+        None => inputs.load(day)?,
+    };
+
+    let (parsed, parse) = Cost::measure(|| solution.solver.parse(&input));
+    let parsed = parsed?;
+
+    let parts = problems
+        .iter()
+        .map(|&problem| {
+            let (ans, cost) = Cost::measure(|| solution.solver.solve(&parsed, problem));
+            Ok((ans.context("problemfn failed")?, cost))
+        })
+        .collect();
+
+    Ok(DayRun { parse, parts })
+}
+
+// Like `run_day` but reports a failure to load or parse the input as a failure
+// of every part.
+fn run_parts(
+    inputs: &inputs::Inputs,
+    day: usize,
+    problems: &[usize],
+    input: Option<&str>,
+) -> Vec<Result<(answer::Answer, report::Timing)>> {
+    match run_day(inputs, day, problems, input) {
+        Ok(DayRun { parse, parts }) => parts
+            .into_iter()
+            .map(|res| {
+                res.map(|(ans, solve)| {
+                    let mut trace = parse.trace.clone();
+                    trace.merge(&solve.trace);
+                    let timing = report::Timing {
+                        parse: parse.duration,
+                        solve: solve.duration,
+                        parse_alloc: parse.alloc,
+                        solve_alloc: solve.alloc,
+                        trace,
+                    };
+                    (ans, timing)
+                })
+            })
+            .collect(),
+        Err(e) => problems.iter().map(|_| Err(error::duplicate(&e))).collect(),
+    }
+}
+
+fn animate(
+    inputs: &inputs::Inputs,
+    day: usize,
+    problem: usize,
+    input: Option<String>,
+    fps: f64,
+) -> Result<()> {
+    if fps.is_nan() || fps <= 0.0 {
+        bail!("--fps must be positive");
+    }
+    let solution = lookup(day, &[problem])?;
+    let input = match input {
+        Some(arg) => match inputs::expand_input_args(&[arg])?.as_slice() {
+            [arg] => arg.read()?.into(),
+            _ => bail!("--input must name a single file"),
+        },
+        None => inputs.load(day)?,
     };
 
-    let input = input.unwrap_or(solution.input);
+    let parsed = solution.solver.parse(&input)?;
+    let frames = solution
+        .solver
+        .animate(&parsed, problem)?
+        .ok_or_else(|| anyhow!("day {} part {} has nothing to animate", day, problem))?;
+    player::play(frames, fps)
+}
 
-    let start = Instant::now();
-    let ans = problem_fn(input).context("problemfn failed")?;
-    let end = Instant::now();
+// The fast solution runs in a child process like `fuzz` does, so panics and
+// hangs are shrunk and reported even though release builds abort on panic.
+// The reference solutions run here.
+fn cross_check(
+    inputs: &inputs::Inputs,
+    day: usize,
+    seeds: u64,
+    size: usize,
+    param: &[String],
+    timeout: Duration,
+) -> Result<()> {
+    let solution = lookup(day, &[])?;
+    let fast = |input: &str, part| {
+        let res = isolate::run_problem(inputs, day, part, Some(input), param, timeout);
+        let error = res.error.unwrap_or_default();
+        match (res.status, res.answer) {
+            (report::Status::Ok, Some(ans)) => Outcome::Answer(ans),
+            (report::Status::Ok | report::Status::Error, _) => Outcome::Error(error),
+            (report::Status::Panic, _) => Outcome::Panic(error),
+            (report::Status::Timeout, _) => Outcome::Timeout(error),
+        }
+    };
+    let report = adventofcode2023::cross_check::check_with(solution.solver, 0..seeds, size, fast)
+        .with_context(|| format!("cannot cross-check day {}", day))?;
+    for (part, reason) in &report.skipped {
+        println!("Part {} not checked: {}", part, reason);
+    }
+    let Some(mismatch) = report.mismatch else {
+        if report.checked.is_empty() {
+            bail!("no part of day {} could be checked", day);
+        }
+        println!("Day {} agrees with its reference on {} inputs", day, seeds);
+        return Ok(());
+    };
+
+    println!(
+        "Part {} disagrees on the input of seed {}, shrunk to:\n",
+        mismatch.part, mismatch.seed
+    );
+    println!("{}\n", mismatch.input);
+    println!("Fast:      {}", mismatch.fast);
+    println!("Reference: {}", mismatch.reference);
+    bail!(
+        "day {} part {} disagrees with its reference",
+        day,
+        mismatch.part
+    );
+}
 
-    return Ok((ans, end.duration_since(start)));
+fn list() {
+    let mut days: Vec<_> = solutions::SOLUTIONS.iter().collect();
+    days.sort_by_key(|(day, _)| **day);
+
+    for (day, solution) in days {
+        println!("day {}", day);
+        let declared = solution.solver.params();
+        let defaults: Vec<String> = declared
+            .iter()
+            .map(|p| format!("{} = {}", p.name(), p.default_value()))
+            .collect();
+        let width = defaults.iter().map(|x| x.len()).max().unwrap_or(0);
+        for (p, default) in declared.iter().zip(&defaults) {
+            println!("  {:<width$}  {} ({})", default, p.help(), p.kind());
+        }
+    }
 }
@@ -0,0 +1,205 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+// Allocations made while running some code. Only collected when built with
+// the `alloc-stats` feature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllocStats {
+    pub allocations: u64,
+    pub bytes: u64,
+    // Most bytes live at once, relative to when measuring started
+    pub peak_bytes: u64,
+}
+
+impl Display for AllocStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} allocations, {}, peak {}",
+            self.allocations,
+            Bytes(self.bytes),
+            Bytes(self.peak_bytes)
+        )
+    }
+}
+
+// Formats a byte count with a binary unit, e.g. `1.50 MiB`.
+pub struct Bytes(pub u64);
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = UNITS[0];
+        for u in &UNITS[1..] {
+            if value < 1024.0 {
+                break;
+            }
+            value /= 1024.0;
+            unit = u;
+        }
+        write!(f, "{:.2} {}", value, unit)
+    }
+}
+
+pub const ENABLED: bool = cfg!(feature = "alloc-stats");
+
+// Runs `f` and returns the allocations it made on the current thread, or None
+// if allocations are not counted.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocStats>) {
+    #[cfg(feature = "alloc-stats")]
+    {
+        let (ret, stats) = counting::measure(f);
+        (ret, Some(stats))
+    }
+    #[cfg(not(feature = "alloc-stats"))]
+    {
+        (f(), None)
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use super::AllocStats;
+
+    #[global_allocator]
+    static GLOBAL: Counting = Counting;
+
+    // Counters are kept per thread so problems running in parallel do not see
+    // each other's allocations. Memory freed on another thread than it was
+    // allocated on can make `live` go negative.
+    #[derive(Clone, Copy)]
+    struct Counters {
+        allocations: u64,
+        bytes: u64,
+        live: i64,
+        peak: i64,
+    }
+
+    thread_local! {
+        static COUNTERS: Cell<Counters> = const {
+            Cell::new(Counters {
+                allocations: 0,
+                bytes: 0,
+                live: 0,
+                peak: 0,
+            })
+        };
+    }
+
+    fn update(f: impl FnOnce(&mut Counters)) {
+        // The thread local is gone while the thread shuts down.
+        let _ = COUNTERS.try_with(|c| {
+            let mut counters = c.get();
+            f(&mut counters);
+            counters.peak = counters.peak.max(counters.live);
+            c.set(counters);
+        });
+    }
+
+    fn record_alloc(size: usize) {
+        update(|c| {
+            c.allocations += 1;
+            c.bytes += size as u64;
+            c.live += size as i64;
+        });
+    }
+
+    struct Counting;
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ret = System.alloc(layout);
+            if !ret.is_null() {
+                record_alloc(layout.size());
+            }
+            ret
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ret = System.alloc_zeroed(layout);
+            if !ret.is_null() {
+                record_alloc(layout.size());
+            }
+            ret
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            update(|c| c.live -= layout.size() as i64);
+        }
+
+        // A reallocation counts as a new allocation of the new size.
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let ret = System.realloc(ptr, layout, new_size);
+            if !ret.is_null() {
+                update(|c| {
+                    c.allocations += 1;
+                    c.bytes += new_size as u64;
+                    c.live += new_size as i64 - layout.size() as i64;
+                });
+            }
+            ret
+        }
+    }
+
+    pub fn measure<T>(f: impl FnOnce() -> T) -> (T, AllocStats) {
+        let mut start = None;
+        update(|c| {
+            c.peak = c.live;
+            start = Some(*c);
+        });
+        let start = start.expect("allocation counters are not available");
+
+        let ret = f();
+
+        let end = COUNTERS.with(|c| c.get());
+        let stats = AllocStats {
+            allocations: end.allocations - start.allocations,
+            bytes: end.bytes - start.bytes,
+            peak_bytes: (end.peak - start.live).max(0) as u64,
+        };
+        (ret, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_test() {
+        assert_eq!(Bytes(512).to_string(), "512 B");
+        assert_eq!(Bytes(1536).to_string(), "1.50 KiB");
+        assert_eq!(Bytes(3 << 30).to_string(), "3.00 GiB");
+    }
+
+    #[cfg(feature = "alloc-stats")]
+    #[test]
+    fn measure_test() {
+        let (v, stats) = measure(|| {
+            let a = vec![0u8; 1000];
+            drop(a);
+            vec![0u8; 500]
+        });
+        let stats = stats.unwrap();
+        assert_eq!(stats.allocations, 2);
+        assert_eq!(stats.bytes, 1500);
+        assert_eq!(stats.peak_bytes, 1000);
+        drop(v);
+    }
+
+    #[cfg(not(feature = "alloc-stats"))]
+    #[test]
+    fn measure_disabled_test() {
+        assert_eq!(measure(|| 1), (1, None));
+    }
+}
@@ -0,0 +1,157 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+// Bytes that mean something to at least one of the parsers, so that edits get
+// past the first check more often than random bytes would.
+const INTERESTING: &[u8] = b"0123456789 \n.#-+=,:;{}()<>@|/\\?OSLJF7AZR";
+
+// Numbers at the edges of what the solvers expect.
+const EXTREMES: &[&str] = &[
+    "0",
+    "1",
+    "-1",
+    "4294967296",
+    "18446744073709551616",
+    "99999999999999999999999999999999999999999",
+];
+
+// Turns inputs into slightly broken ones for fuzzing. The same seed always
+// makes the same edits.
+pub struct Mutator {
+    rng: StdRng,
+}
+
+impl Mutator {
+    pub fn new(seed: u64) -> Self {
+        Mutator {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // Up to `max_len` random bytes, with invalid UTF-8 replaced.
+    pub fn random_input(&mut self, max_len: usize) -> String {
+        let len = self.rng.gen_range(0..=max_len);
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| match self.rng.gen_bool(0.5) {
+                true => *INTERESTING.choose(&mut self.rng).unwrap(),
+                false => self.rng.gen(),
+            })
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    // Makes a few random edits to the input.
+    pub fn mutate(&mut self, input: &str) -> String {
+        let mut bytes = input.as_bytes().to_vec();
+        for _ in 0..self.rng.gen_range(1..=4) {
+            self.edit(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    fn edit(&mut self, bytes: &mut Vec<u8>) {
+        let len = bytes.len();
+        let pos = self.rng.gen_range(0..=len);
+        let end = (pos + self.rng.gen_range(1..=16)).min(len);
+        match self.rng.gen_range(0..8) {
+            0 if pos < len => bytes[pos] = *INTERESTING.choose(&mut self.rng).unwrap(),
+            1 if pos < len => bytes[pos] ^= 1 << self.rng.gen_range(0..8),
+            2 => {
+                bytes.drain(pos..end);
+            }
+            3 => {
+                let span = bytes[pos..end].to_vec();
+                bytes.splice(pos..pos, span);
+            }
+            4 => bytes.truncate(pos),
+            5 => self.edit_number(bytes),
+            6 => self.edit_lines(bytes),
+            _ => bytes.insert(pos, *INTERESTING.choose(&mut self.rng).unwrap()),
+        }
+    }
+
+    // Replaces a number by one the solvers are unlikely to expect.
+    fn edit_number(&mut self, bytes: &mut Vec<u8>) {
+        let starts: Vec<usize> = (0..bytes.len())
+            .filter(|&i| bytes[i].is_ascii_digit() && (i == 0 || !bytes[i - 1].is_ascii_digit()))
+            .collect();
+        let Some(&start) = starts.choose(&mut self.rng) else {
+            return;
+        };
+        let len = bytes[start..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        let number = EXTREMES.choose(&mut self.rng).unwrap().bytes();
+        bytes.splice(start..start + len, number);
+    }
+
+    // Removes, repeats or swaps whole lines.
+    fn edit_lines(&mut self, bytes: &mut Vec<u8>) {
+        let mut lines: Vec<Vec<u8>> = bytes.split(|&b| b == b'\n').map(|l| l.to_vec()).collect();
+        let i = self.rng.gen_range(0..lines.len());
+        let j = self.rng.gen_range(0..lines.len());
+        match self.rng.gen_range(0..3) {
+            0 => {
+                lines.remove(i);
+            }
+            1 => lines.insert(j, lines[i].clone()),
+            _ => lines.swap(i, j),
+        }
+        *bytes = lines.join(&b'\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::*;
+    use crate::generate::InputGenerator;
+    use crate::solutions::SOLUTIONS;
+
+    #[test]
+    fn mutate_test() {
+        let input = "Time:      7  15   30\nDistance:  9  40  200";
+        let mutants: Vec<String> = (0..50).map(|_| Mutator::new(3).mutate(input)).collect();
+        assert!(mutants.iter().all(|m| *m == mutants[0]));
+
+        let mut mutator = Mutator::new(3);
+        let changed = (0..50).filter(|_| mutator.mutate(input) != input).count();
+        assert!(changed > 40);
+
+        for _ in 0..50 {
+            mutator.mutate("");
+            assert!(mutator.random_input(10).chars().count() <= 10);
+        }
+    }
+
+    #[test]
+    fn no_panic_test() {
+        let mut panics = Vec::new();
+        for (day, solution) in SOLUTIONS.iter() {
+            let solver = solution.solver;
+            let mut mutator = Mutator::new(*day as u64);
+            let corpus = solver.generate(&mut InputGenerator::new(0, 10)).unwrap();
+            for i in 0..100 {
+                let input = match i % 10 {
+                    0 => mutator.random_input(64),
+                    _ => mutator.mutate(&corpus),
+                };
+                // Errors are fine, only panics fail the test
+                let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                    if let Ok(parsed) = solver.parse(&input) {
+                        for part in 1..=2 {
+                            let _ = solver.solve(&parsed, part);
+                        }
+                    }
+                }));
+                if res.is_err() {
+                    panics.push(format!("day {}:\n{}", day, input));
+                }
+            }
+        }
+        assert!(panics.is_empty(), "{}", panics.join("\n\n"));
+    }
+}
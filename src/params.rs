@@ -0,0 +1,205 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+
+// A puzzle constant a solution reads instead of hardcoding it, so it can be
+// changed from the command line.
+pub struct Param<T> {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub default: T,
+}
+
+// The types parameters can have.
+pub trait Value: Copy + Display + FromStr + Sync + 'static {
+    const KIND: &'static str;
+}
+
+impl Value for u32 {
+    const KIND: &'static str = "integer";
+}
+
+impl Value for u64 {
+    const KIND: &'static str = "integer";
+}
+
+impl Value for usize {
+    const KIND: &'static str = "integer";
+}
+
+impl Value for f64 {
+    const KIND: &'static str = "number";
+}
+
+impl<T: Value> Param<T> {
+    pub const fn new(name: &'static str, default: T, help: &'static str) -> Self {
+        Param {
+            name,
+            help,
+            default,
+        }
+    }
+
+    // The value given inside `with`, or the default.
+    pub fn get(&self) -> T {
+        CURRENT
+            .with(|c| {
+                c.borrow()
+                    .values
+                    .get(self.name)
+                    .and_then(|v| v.parse().ok())
+            })
+            .unwrap_or(self.default)
+    }
+}
+
+// Parameters of any type, as declared by a solution.
+pub trait ParamInfo: Sync {
+    fn name(&self) -> &'static str;
+    fn help(&self) -> &'static str;
+    fn kind(&self) -> &'static str;
+    fn default_value(&self) -> String;
+    fn check(&self, value: &str) -> Result<()>;
+}
+
+impl<T: Value> ParamInfo for Param<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn help(&self) -> &'static str {
+        self.help
+    }
+
+    fn kind(&self) -> &'static str {
+        T::KIND
+    }
+
+    fn default_value(&self) -> String {
+        self.default.to_string()
+    }
+
+    fn check(&self, value: &str) -> Result<()> {
+        match value.parse::<T>() {
+            Ok(_) => Ok(()),
+            Err(_) => bail!("invalid {} `{}` for {}", T::KIND, value, self.name),
+        }
+    }
+}
+
+// Values given for the parameters of a day, checked against what it declares.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Overrides {
+    values: BTreeMap<String, String>,
+}
+
+impl Overrides {
+    // Parses `key=value` arguments.
+    pub fn parse(declared: &[&dyn ParamInfo], args: &[String]) -> Result<Self> {
+        let mut values = BTreeMap::new();
+        for arg in args {
+            let (key, value) = arg
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid parameter `{}`, expected KEY=VALUE", arg))?;
+            let param = declared.iter().find(|p| p.name() == key).ok_or_else(|| {
+                if declared.is_empty() {
+                    return anyhow!("unknown parameter `{}`, the day has no parameters", key);
+                }
+                let names: Vec<&str> = declared.iter().map(|p| p.name()).collect();
+                anyhow!(
+                    "unknown parameter `{}`, expected one of {}",
+                    key,
+                    names.join(", ")
+                )
+            })?;
+            param.check(value)?;
+            values.insert(key.to_owned(), value.to_owned());
+        }
+        Ok(Overrides { values })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Overrides> = RefCell::new(Overrides::default());
+}
+
+// Runs `f` with the overrides applied to parameters read on this thread.
+pub fn with<T>(overrides: &Overrides, f: impl FnOnce() -> T) -> T {
+    let outer = CURRENT.with(|c| c.replace(overrides.clone()));
+    let ret = f();
+    CURRENT.with(|c| c.replace(outer));
+    ret
+}
+
+// The overrides in effect on this thread, to carry them over to others.
+pub fn current() -> Overrides {
+    CURRENT.with(|c| c.borrow().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: Param<u64> = Param::new("limit", 12, "");
+    const AREA: Param<f64> = Param::new("area", 2.5, "");
+    const DECLARED: &[&dyn ParamInfo] = &[&LIMIT, &AREA];
+
+    fn args(xs: &[&str]) -> Vec<String> {
+        xs.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn with_test() {
+        assert_eq!(LIMIT.get(), 12);
+
+        let overrides = Overrides::parse(DECLARED, &args(&["limit=20"])).unwrap();
+        with(&overrides, || {
+            assert_eq!(LIMIT.get(), 20);
+            assert_eq!(AREA.get(), 2.5);
+            assert_eq!(current(), overrides);
+
+            with(&Overrides::default(), || assert_eq!(LIMIT.get(), 12));
+            assert_eq!(LIMIT.get(), 20);
+        });
+        assert_eq!(LIMIT.get(), 12);
+    }
+
+    #[test]
+    fn parse_test() {
+        let overrides = Overrides::parse(DECLARED, &args(&["limit=1", "area=1e3"])).unwrap();
+        assert!(!overrides.is_empty());
+        assert!(Overrides::parse(DECLARED, &[]).unwrap().is_empty());
+
+        let err = |xs: &[&str]| {
+            Overrides::parse(DECLARED, &args(xs))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err(&["limit"]),
+            "invalid parameter `limit`, expected KEY=VALUE"
+        );
+        assert_eq!(
+            err(&["size=3"]),
+            "unknown parameter `size`, expected one of limit, area"
+        );
+        assert_eq!(err(&["limit=-1"]), "invalid integer `-1` for limit");
+        assert_eq!(err(&["area=x"]), "invalid number `x` for area");
+        assert_eq!(
+            Overrides::parse(&[], &args(&["limit=1"]))
+                .unwrap_err()
+                .to_string(),
+            "unknown parameter `limit`, the day has no parameters"
+        );
+
+        assert_eq!(LIMIT.default_value(), "12");
+        assert_eq!(AREA.kind(), "number");
+    }
+}
@@ -26,7 +26,6 @@ pub fn uint<T: FromStr>(input: &str) -> IResult<&str, T> {
     parser(input)
 }
 
-#[allow(dead_code)]
 pub fn int<T: FromStr>(input: &str) -> IResult<&str, T> {
     let digits = is_a("0123456789");
     let num = tuple((opt(tag("-")), digits));
@@ -34,7 +33,6 @@ pub fn int<T: FromStr>(input: &str) -> IResult<&str, T> {
     parser(input)
 }
 
-#[allow(dead_code)]
 pub fn complete<I, O, E, P>(parser: P) -> impl FnMut(I) -> IResult<I, O, E>
 where
     I: nom::InputLength + nom::InputTakeAtPosition + Clone,
@@ -0,0 +1,247 @@
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use adventofcode2023::animation::{Frame, Frames};
+use anyhow::{bail, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{cursor, execute, queue};
+
+const MIN_FPS: f64 = 0.25;
+const MAX_FPS: f64 = 240.0;
+
+const HELP: &str = "space pause, → step, +/- speed, q quit";
+
+// Plays the frames in the terminal, or prints them all one after another when
+// stdout is not a terminal.
+pub fn play(frames: Frames, fps: f64) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        return match dump(frames, &mut stdout.lock()) {
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            res => Ok(res?),
+        };
+    }
+
+    let _screen = Screen::enter(&mut stdout)?;
+    let mut frames = frames;
+    let Some(mut frame) = frames.next() else {
+        bail!("the animation has no frames");
+    };
+    let mut number = 1;
+    let mut ended = false;
+    let mut controls = Controls {
+        paused: false,
+        fps: fps.clamp(MIN_FPS, MAX_FPS),
+    };
+    let mut next_at = Instant::now() + controls.interval();
+
+    loop {
+        draw(&mut stdout, &frame, number, &controls, ended)?;
+
+        // Wait for a key or until the next frame is due
+        let waiting = controls.paused || ended;
+        let timeout = next_at.saturating_duration_since(Instant::now());
+        let advance = if waiting || event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => match controls.handle(key) {
+                    Action::Quit => return Ok(()),
+                    Action::Step => true,
+                    Action::None => false,
+                },
+                _ => false,
+            }
+        } else {
+            true
+        };
+
+        if advance && !ended {
+            match frames.next() {
+                Some(f) => {
+                    frame = f;
+                    number += 1;
+                }
+                None => ended = true,
+            }
+        }
+        if advance || waiting {
+            next_at = Instant::now() + controls.interval();
+        }
+    }
+}
+
+// Prints every frame with its number and caption.
+pub fn dump(frames: Frames, w: &mut impl Write) -> std::io::Result<()> {
+    for (i, frame) in frames.enumerate() {
+        writeln!(w, "Frame {}: {}", i + 1, frame.caption)?;
+        for line in &frame.lines {
+            writeln!(w, "{}", line)?;
+        }
+        writeln!(w)?;
+    }
+    w.flush()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Action {
+    None,
+    Step,
+    Quit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Controls {
+    paused: bool,
+    fps: f64,
+}
+
+impl Controls {
+    fn handle(&mut self, key: KeyEvent) -> Action {
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            KeyCode::Char(' ') | KeyCode::Char('p') => {
+                self.paused = !self.paused;
+                Action::None
+            }
+            // Stepping pauses, so the next frame stays up
+            KeyCode::Right | KeyCode::Char('n') | KeyCode::Char('.') => {
+                self.paused = true;
+                Action::Step
+            }
+            KeyCode::Up | KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.fps = (self.fps * 2.0).min(MAX_FPS);
+                Action::None
+            }
+            KeyCode::Down | KeyCode::Char('-') => {
+                self.fps = (self.fps / 2.0).max(MIN_FPS);
+                Action::None
+            }
+            _ => Action::None,
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.fps)
+    }
+}
+
+// Draws a frame at the top left, cut to the size of the terminal, with a
+// status line below it.
+fn draw(
+    w: &mut impl Write,
+    frame: &Frame,
+    number: usize,
+    controls: &Controls,
+    ended: bool,
+) -> Result<()> {
+    let (cols, rows) = terminal::size()?;
+    let (cols, rows) = (cols as usize, rows as usize);
+    let cut = |s: &str| s.chars().take(cols).collect::<String>();
+
+    queue!(w, cursor::MoveTo(0, 0))?;
+    for line in frame.lines.iter().take(rows.saturating_sub(3)) {
+        queue!(
+            w,
+            Print(cut(line)),
+            Clear(ClearType::UntilNewLine),
+            Print("\r\n")
+        )?;
+    }
+
+    let state = if ended {
+        "ended".to_owned()
+    } else if controls.paused {
+        "paused".to_owned()
+    } else {
+        format!("{} fps", controls.fps)
+    };
+    let caption = format!("frame {}: {}", number, frame.caption);
+    let status = format!("{} | {}", state, HELP);
+    queue!(
+        w,
+        Clear(ClearType::UntilNewLine),
+        Print("\r\n"),
+        Print(cut(&caption)),
+        Clear(ClearType::UntilNewLine),
+        Print("\r\n"),
+        Print(cut(&status)),
+        Clear(ClearType::FromCursorDown),
+    )?;
+    w.flush()?;
+    Ok(())
+}
+
+// Puts the terminal in raw mode on the alternate screen for as long as it
+// lives, so it is restored however playing ends.
+struct Screen;
+
+impl Screen {
+    fn enter(w: &mut impl Write) -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        let screen = Screen;
+        execute!(w, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_test() {
+        let frames = vec![
+            Frame::new("start", vec!["#.".to_owned(), ".#".to_owned()]),
+            Frame::new("end", vec![]),
+        ];
+        let mut buf = Vec::new();
+        dump(Box::new(frames.into_iter()), &mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "Frame 1: start\n#.\n.#\n\nFrame 2: end\n\n"
+        );
+    }
+
+    #[test]
+    fn controls_test() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let mut controls = Controls {
+            paused: false,
+            fps: 10.0,
+        };
+
+        assert_eq!(controls.handle(key(KeyCode::Char(' '))), Action::None);
+        assert!(controls.paused);
+        assert_eq!(controls.handle(key(KeyCode::Char(' '))), Action::None);
+        assert!(!controls.paused);
+
+        assert_eq!(controls.handle(key(KeyCode::Right)), Action::Step);
+        assert!(controls.paused);
+
+        controls.handle(key(KeyCode::Char('+')));
+        assert_eq!(controls.fps, 20.0);
+        assert_eq!(controls.interval(), Duration::from_millis(50));
+        for _ in 0..10 {
+            controls.handle(key(KeyCode::Char('-')));
+        }
+        assert_eq!(controls.fps, MIN_FPS);
+
+        assert_eq!(controls.handle(key(KeyCode::Char('x'))), Action::None);
+        assert_eq!(controls.handle(key(KeyCode::Char('q'))), Action::Quit);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(controls.handle(ctrl_c), Action::Quit);
+    }
+}
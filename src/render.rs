@@ -0,0 +1,360 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::grid::{Grid, Point};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    // A shade between black and white.
+    pub const fn gray(v: u8) -> Self {
+        Color::rgb(v, v, v)
+    }
+
+    // Draws `self` over `base` with an opacity out of 255.
+    fn over(self, base: Color, alpha: u8) -> Color {
+        let mix = |top: u8, bottom: u8| {
+            let a = alpha as u32;
+            ((top as u32 * a + bottom as u32 * (255 - a) + 127) / 255) as u8
+        };
+        Color::rgb(
+            mix(self.r, base.r),
+            mix(self.g, base.g),
+            mix(self.b, base.b),
+        )
+    }
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+// Points highlighted on top of the cells, like the path a solution took.
+#[derive(Clone, Debug)]
+struct Layer {
+    name: String,
+    color: Color,
+    alpha: u8,
+    points: Vec<Point>,
+}
+
+// A picture of a grid. Every cell is colored by its value and layers are drawn
+// over the cells in the order they were added. Each cell becomes a square of
+// `scale` pixels when written out.
+#[derive(Clone, Debug)]
+pub struct Canvas {
+    cols: usize,
+    rows: usize,
+    cells: Vec<Color>,
+    layers: Vec<Layer>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Ppm,
+    Png,
+    Svg,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .map(|x| x.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("ppm") => Ok(Format::Ppm),
+            Some("png") => Ok(Format::Png),
+            Some("svg") => Ok(Format::Svg),
+            _ => bail!(
+                "cannot tell the image format of {}, use .png, .ppm or .svg",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl Canvas {
+    pub fn new<T>(grid: &Grid<T>, color: impl Fn(&T) -> Color) -> Self {
+        let (cols, rows) = grid.size();
+        Canvas {
+            cols,
+            rows,
+            cells: grid.cells.iter().flatten().map(color).collect(),
+            layers: Vec::new(),
+        }
+    }
+
+    // Adds a layer with an opacity out of 255. Points outside the grid are
+    // dropped.
+    pub fn overlay(
+        &mut self,
+        name: &str,
+        color: Color,
+        alpha: u8,
+        points: impl IntoIterator<Item = Point>,
+    ) -> &mut Self {
+        let mut points: Vec<Point> = points
+            .into_iter()
+            .filter(|p| p.x < self.cols && p.y < self.rows)
+            .collect();
+        points.sort_unstable_by_key(|p| (p.y, p.x));
+        points.dedup();
+
+        self.layers.push(Layer {
+            name: name.to_owned(),
+            color,
+            alpha,
+            points,
+        });
+        self
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    // The color of every cell with the layers drawn, row by row.
+    pub fn pixels(&self) -> Vec<Color> {
+        let mut pixels = self.cells.clone();
+        for layer in &self.layers {
+            for p in &layer.points {
+                let px = &mut pixels[p.y * self.cols + p.x];
+                *px = layer.color.over(*px, layer.alpha);
+            }
+        }
+        pixels
+    }
+
+    pub fn save(&self, path: &Path, scale: usize) -> Result<()> {
+        let format = Format::from_path(path)?;
+        let file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut w = BufWriter::new(file);
+        self.write(&mut w, format, scale)?;
+        w.flush()
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn write(&self, w: &mut impl Write, format: Format, scale: usize) -> Result<()> {
+        if scale == 0 {
+            bail!("scale must be at least 1");
+        }
+        match format {
+            Format::Ppm => self.write_ppm(w, scale),
+            Format::Png => self.write_png(w, scale),
+            Format::Svg => self.write_svg(w, scale),
+        }
+    }
+
+    // RGB bytes of the scaled image, row by row.
+    fn raster(&self, scale: usize) -> Vec<u8> {
+        let pixels = self.pixels();
+        let mut data = Vec::with_capacity(pixels.len() * scale * scale * 3);
+        for row in pixels.chunks(self.cols.max(1)) {
+            let start = data.len();
+            for c in row {
+                for _ in 0..scale {
+                    data.extend_from_slice(&[c.r, c.g, c.b]);
+                }
+            }
+            let end = data.len();
+            for _ in 1..scale {
+                data.extend_from_within(start..end);
+            }
+        }
+        data
+    }
+
+    fn write_ppm(&self, w: &mut impl Write, scale: usize) -> Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.cols * scale, self.rows * scale)?;
+        w.write_all(&self.raster(scale))?;
+        Ok(())
+    }
+
+    fn write_png(&self, w: &mut impl Write, scale: usize) -> Result<()> {
+        let width = u32::try_from(self.cols * scale).map_err(|_| anyhow!("image is too wide"))?;
+        let height = u32::try_from(self.rows * scale).map_err(|_| anyhow!("image is too tall"))?;
+
+        let mut encoder = png::Encoder::new(w, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.raster(scale))?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    // Cells are unit squares scaled up by the view box. Neighbouring cells of
+    // the same color are merged into one rectangle to keep the file small, and
+    // each layer is a group of its own.
+    fn write_svg(&self, w: &mut impl Write, scale: usize) -> Result<()> {
+        writeln!(
+            w,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+            self.cols * scale,
+            self.rows * scale,
+            self.cols,
+            self.rows
+        )?;
+
+        writeln!(w, r#"<g id="cells">"#)?;
+        for (y, row) in self.cells.chunks(self.cols.max(1)).enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let len = row[x..].iter().take_while(|c| **c == row[x]).count();
+                writeln!(
+                    w,
+                    r#"<rect x="{}" y="{}" width="{}" height="1" fill="{}"/>"#,
+                    x,
+                    y,
+                    len,
+                    row[x].hex()
+                )?;
+                x += len;
+            }
+        }
+        writeln!(w, "</g>")?;
+
+        for layer in &self.layers {
+            writeln!(
+                w,
+                r#"<g id="{}" fill="{}" fill-opacity="{:.3}">"#,
+                escape(&layer.name),
+                layer.color.hex(),
+                layer.alpha as f64 / 255.0
+            )?;
+            // Points are sorted by row, so runs are consecutive.
+            let mut i = 0;
+            while i < layer.points.len() {
+                let p = layer.points[i];
+                let len = layer.points[i..]
+                    .iter()
+                    .zip(p.x..)
+                    .take_while(|(q, x)| q.y == p.y && q.x == *x)
+                    .count();
+                writeln!(
+                    w,
+                    r#"<rect x="{}" y="{}" width="{}" height="1"/>"#,
+                    p.x, p.y, len
+                )?;
+                i += len;
+            }
+            writeln!(w, "</g>")?;
+        }
+
+        writeln!(w, "</svg>")?;
+        Ok(())
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::rgb(255, 0, 0);
+
+    fn canvas() -> Canvas {
+        let grid = Grid::new(vec![vec![0, 1, 1], vec![1, 1, 0]]);
+        let mut canvas = Canvas::new(&grid, |&x| Color::gray(x * 200));
+        canvas
+            .overlay("path", RED, 255, [Point::new(1, 0), Point::new(2, 0)])
+            .overlay(
+                "half",
+                Color::WHITE,
+                128,
+                [Point::new(0, 1), Point::new(9, 9)],
+            );
+        canvas
+    }
+
+    #[test]
+    fn pixels_test() {
+        let g = Color::gray(200);
+        assert_eq!(
+            canvas().pixels(),
+            [Color::BLACK, RED, RED, Color::gray(228), g, Color::BLACK]
+        );
+        assert_eq!(Color::WHITE.over(Color::BLACK, 0), Color::BLACK);
+        assert_eq!(Color::WHITE.over(Color::BLACK, 255), Color::WHITE);
+    }
+
+    #[test]
+    fn ppm_test() {
+        let mut buf = Vec::new();
+        canvas().write(&mut buf, Format::Ppm, 2).unwrap();
+        let header = b"P6\n6 4\n255\n";
+        assert_eq!(&buf[..header.len()], header);
+        let data = &buf[header.len()..];
+        assert_eq!(data.len(), 6 * 4 * 3);
+        // The second pixel of the second row is still the first cell
+        assert_eq!(&data[6 * 3 + 3..6 * 3 + 6], &[0, 0, 0]);
+        assert_eq!(&data[6 * 3 + 6..6 * 3 + 9], &[255, 0, 0]);
+        assert!(canvas().write(&mut buf, Format::Ppm, 0).is_err());
+    }
+
+    #[test]
+    fn png_test() {
+        let mut buf = Vec::new();
+        canvas().write(&mut buf, Format::Png, 3).unwrap();
+
+        let decoder = png::Decoder::new(buf.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        assert_eq!((info.width, info.height), (9, 6));
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        assert_eq!(data, canvas().raster(3));
+    }
+
+    #[test]
+    fn svg_test() {
+        let mut buf = Vec::new();
+        canvas().write(&mut buf, Format::Svg, 4).unwrap();
+        let svg = String::from_utf8(buf).unwrap();
+
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="12" height="8" viewBox="0 0 3 2""#
+        ));
+        assert!(svg.contains(r##"<rect x="1" y="0" width="2" height="1" fill="#c8c8c8"/>"##));
+        assert!(svg.contains(r##"<g id="path" fill="#ff0000" fill-opacity="1.000">"##));
+        assert!(svg.contains(r#"<rect x="1" y="0" width="2" height="1"/>"#));
+        assert!(svg.contains(r##"<g id="half" fill="#ffffff" fill-opacity="0.502">"##));
+        assert!(!svg.contains(r#"x="9""#));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn format_test() {
+        assert_eq!(
+            Format::from_path(Path::new("a/b.PNG")).unwrap(),
+            Format::Png
+        );
+        assert_eq!(Format::from_path(Path::new("b.svg")).unwrap(), Format::Svg);
+        assert_eq!(Format::from_path(Path::new("b.ppm")).unwrap(), Format::Ppm);
+        assert!(Format::from_path(Path::new("b.jpg")).is_err());
+        assert!(Format::from_path(Path::new("b")).is_err());
+    }
+}
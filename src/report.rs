@@ -0,0 +1,718 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{AllocStats, Bytes};
+use adventofcode2023::answer::Answer;
+use adventofcode2023::error::{self, ErrorKind};
+use adventofcode2023::trace::Trace;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Markdown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Error,
+    Panic,
+    Timeout,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Error => "error",
+            Self::Panic => "panic",
+            Self::Timeout => "timeout",
+        }
+    }
+}
+
+// Time spent parsing a day's input and solving one of its parts. The parse
+// time is shared by both parts of a day. Allocations are only known when
+// they are counted. The trace covers both parsing and solving.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    pub parse: Duration,
+    pub solve: Duration,
+    pub parse_alloc: Option<AllocStats>,
+    pub solve_alloc: Option<AllocStats>,
+    pub trace: Trace,
+}
+
+// Runtimes of the same problem on different profiles are flagged when the
+// slowest is at least this many times slower than the fastest.
+const PROFILE_SPREAD_THRESHOLD: f64 = 2.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProblemResult {
+    // The input profile, only set when comparing profiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub day: usize,
+    pub part: usize,
+    pub status: Status,
+    // Left out when the problem failed, so that a part without an answer,
+    // written as null, still reads back as solved
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_answer"
+    )]
+    pub answer: Option<Answer>,
+    #[serde(
+        default,
+        rename = "parse_ns",
+        serialize_with = "serialize_nanos",
+        deserialize_with = "deserialize_nanos"
+    )]
+    pub parse_duration: Option<Duration>,
+    // Time spent solving, excluding parsing
+    #[serde(
+        rename = "duration_ns",
+        serialize_with = "serialize_nanos",
+        deserialize_with = "deserialize_nanos"
+    )]
+    pub duration: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_alloc: Option<AllocStats>,
+    // Allocations made while solving, excluding parsing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alloc: Option<AllocStats>,
+    // Counters and spans recorded by the solution
+    #[serde(default, skip_serializing_if = "Trace::is_empty")]
+    pub trace: Trace,
+    // What went wrong, for errors returned by the solution. Panics, timeouts
+    // and failures to run the problem have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ErrorKind>,
+    // The full error chain if the problem failed
+    pub error: Option<String>,
+}
+
+impl ProblemResult {
+    pub fn new(day: usize, part: usize, res: Result<(Answer, Timing)>) -> Self {
+        match res {
+            Ok((answer, timing)) => Self {
+                profile: None,
+                day,
+                part,
+                status: Status::Ok,
+                answer: Some(answer),
+                parse_duration: Some(timing.parse),
+                duration: Some(timing.solve),
+                parse_alloc: timing.parse_alloc,
+                alloc: timing.solve_alloc,
+                trace: timing.trace,
+                error_kind: None,
+                error: None,
+            },
+            Err(e) => Self {
+                profile: None,
+                day,
+                part,
+                status: Status::Error,
+                answer: None,
+                parse_duration: None,
+                duration: None,
+                parse_alloc: None,
+                alloc: None,
+                trace: Trace::default(),
+                error_kind: Some(error::kind(&e)),
+                error: Some(format!("{:#}", e).trim_end().to_owned()),
+            },
+        }
+    }
+
+    pub fn failed(day: usize, part: usize, status: Status, error: String) -> Self {
+        Self {
+            profile: None,
+            day,
+            part,
+            status,
+            answer: None,
+            parse_duration: None,
+            duration: None,
+            parse_alloc: None,
+            alloc: None,
+            trace: Trace::default(),
+            error_kind: None,
+            error: Some(error),
+        }
+    }
+
+    pub fn with_profile(self, profile: &str) -> Self {
+        Self {
+            profile: Some(profile.to_owned()),
+            ..self
+        }
+    }
+
+    // The status in upper case, followed by the kind of error if known.
+    fn status_label(&self) -> String {
+        let status = self.status.as_str().to_ascii_uppercase();
+        match self.error_kind {
+            Some(kind) => format!("{} ({})", status, kind),
+            None => status,
+        }
+    }
+
+    // Exit codes start at 3 to stay clear of anyhow's 1 and clap's 2. A part
+    // that is not implemented yet does not fail the run.
+    fn exit_code(&self) -> u8 {
+        match (self.status, self.error_kind) {
+            (Status::Ok, _) | (Status::Error, Some(ErrorKind::NotImplemented)) => 0,
+            (Status::Error, Some(ErrorKind::Parse)) => 3,
+            (Status::Error, Some(ErrorKind::InvalidInput)) => 4,
+            (Status::Error, Some(ErrorKind::NoSolution)) => 5,
+            (Status::Error, Some(ErrorKind::Internal) | None) => 6,
+            (Status::Timeout, _) => 7,
+            (Status::Panic, _) => 8,
+        }
+    }
+}
+
+// The status and kind of error of a failure that exited with `code`, the
+// reverse of `ProblemResult::exit_code`. Codes it never gives are None.
+pub fn failure_for_exit_code(code: i32) -> Option<(Status, Option<ErrorKind>)> {
+    match code {
+        3 => Some((Status::Error, Some(ErrorKind::Parse))),
+        4 => Some((Status::Error, Some(ErrorKind::InvalidInput))),
+        5 => Some((Status::Error, Some(ErrorKind::NoSolution))),
+        6 => Some((Status::Error, Some(ErrorKind::Internal))),
+        7 => Some((Status::Timeout, None)),
+        8 => Some((Status::Panic, None)),
+        _ => None,
+    }
+}
+
+// The exit code of the most serious failure, or 0 if nothing failed.
+pub fn exit_code(results: &[ProblemResult]) -> u8 {
+    results.iter().map(|r| r.exit_code()).max().unwrap_or(0)
+}
+
+fn serialize_nanos<S: serde::Serializer>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    match d {
+        Some(d) => s.serialize_some(&(d.as_nanos() as u64)),
+        None => s.serialize_none(),
+    }
+}
+
+fn deserialize_nanos<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<Duration>, D::Error> {
+    Ok(Option::<u64>::deserialize(d)?.map(Duration::from_nanos))
+}
+
+fn deserialize_answer<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<Answer>, D::Error> {
+    Answer::deserialize(d).map(Some)
+}
+
+// Writes the results in the given format. `verbose` only affects the text
+// format; the other formats always include errors.
+pub fn write(
+    w: &mut impl Write,
+    format: Format,
+    verbose: bool,
+    results: &[ProblemResult],
+) -> Result<()> {
+    let has_profiles = results.iter().any(|r| r.profile.is_some());
+
+    match format {
+        Format::Text if has_profiles => write_profile_comparison(w, verbose, results),
+        Format::Text => write_text(w, verbose, results),
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *w, results)?;
+            writeln!(w)?;
+            Ok(())
+        }
+        Format::Csv => write_csv(w, has_profiles, results),
+        Format::Markdown => write_markdown(w, has_profiles, results),
+    }
+}
+
+fn write_text(w: &mut impl Write, verbose: bool, results: &[ProblemResult]) -> Result<()> {
+    let mut sorted: Vec<_> = results.iter().collect();
+
+    // Sort by duration in descending order. Errors are sorted at the bottom
+    // by day/part.
+    sorted.sort_by(|a, b| match (a.duration, b.duration) {
+        (Some(a_dur), Some(b_dur)) => a_dur.cmp(&b_dur).reverse(),
+        (None, None) => a.day.cmp(&b.day).then_with(|| a.part.cmp(&b.part)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+    });
+
+    for r in sorted {
+        match r.duration {
+            Some(duration) => {
+                write!(w, "{:2}-{}: {:?}", r.day, r.part, duration)?;
+                if let Some(parse) = r.parse_duration {
+                    write!(w, " (parse {:?})", parse)?;
+                }
+                match r.alloc {
+                    Some(alloc) => writeln!(w, ", {}", alloc)?,
+                    None => writeln!(w)?,
+                }
+                if verbose {
+                    for line in r.trace.to_string().lines() {
+                        writeln!(w, "      {}", line)?;
+                    }
+                }
+            }
+            None => {
+                let status = r.status_label();
+                match (&r.error, verbose) {
+                    (Some(e), true) => writeln!(w, "{:2}-{}: {}: {}", r.day, r.part, status, e)?,
+                    _ => writeln!(w, "{:2}-{}: {}", r.day, r.part, status)?,
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Writes a table with a column per profile followed by the problems that
+// failed on some profiles or whose runtime differs a lot between profiles.
+fn write_profile_comparison(
+    w: &mut impl Write,
+    verbose: bool,
+    results: &[ProblemResult],
+) -> Result<()> {
+    let mut profiles: Vec<&str> = Vec::new();
+    let mut problems: BTreeMap<(usize, usize), Vec<&ProblemResult>> = BTreeMap::new();
+    for r in results {
+        let profile = r.profile.as_deref().unwrap_or_default();
+        if !profiles.contains(&profile) {
+            profiles.push(profile);
+        }
+        problems.entry((r.day, r.part)).or_default().push(r);
+    }
+
+    write!(w, "{:5}", "")?;
+    for p in &profiles {
+        write!(w, " {:>12}", p)?;
+    }
+    writeln!(w)?;
+
+    let mut differences = Vec::new();
+    for (&(day, part), rs) in &problems {
+        write!(w, "{:2}-{}:", day, part)?;
+        for p in &profiles {
+            let cell = match rs.iter().find(|r| r.profile.as_deref() == Some(p)) {
+                Some(ProblemResult {
+                    duration: Some(d), ..
+                }) => format!("{:.2?}", d),
+                Some(r) => r.status.as_str().to_ascii_uppercase(),
+                None => "-".to_owned(),
+            };
+            write!(w, " {:>12}", cell)?;
+        }
+        writeln!(w)?;
+
+        for r in rs.iter().filter(|r| r.status != Status::Ok) {
+            let profile = r.profile.as_deref().unwrap_or_default();
+            let status = r.status_label();
+            match (&r.error, verbose) {
+                (Some(e), true) => differences.push(format!(
+                    "{:2}-{}: {} in {}: {}",
+                    day, part, status, profile, e
+                )),
+                _ => differences.push(format!("{:2}-{}: {} in {}", day, part, status, profile)),
+            }
+        }
+
+        let timed = rs
+            .iter()
+            .filter_map(|r| Some((r.duration?, r.profile.as_deref()?)));
+        let fastest = timed.clone().min();
+        let slowest = timed.max();
+        if let (Some(fastest), Some(slowest)) = (fastest, slowest) {
+            let spread = slowest.0.as_secs_f64() / fastest.0.as_secs_f64();
+            if spread >= PROFILE_SPREAD_THRESHOLD {
+                differences.push(format!(
+                    "{:2}-{}: runtime varies {:.1}x (fastest {}, slowest {})",
+                    day, part, spread, fastest.1, slowest.1
+                ));
+            }
+        }
+    }
+
+    if !differences.is_empty() {
+        writeln!(w, "\nDifferences between profiles:")?;
+        for d in differences {
+            writeln!(w, "{}", d)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Allocation columns are only written when allocations were counted.
+fn write_csv(w: &mut impl Write, has_profiles: bool, results: &[ProblemResult]) -> Result<()> {
+    let has_alloc = results.iter().any(|r| r.alloc.is_some());
+
+    if has_profiles {
+        write!(w, "profile,")?;
+    }
+    write!(w, "day,part,status,answer,parse_ns,duration_ns,")?;
+    if has_alloc {
+        write!(w, "allocations,alloc_bytes,peak_bytes,")?;
+    }
+    writeln!(w, "error")?;
+    for r in results {
+        if has_profiles {
+            write!(w, "{},", csv_field(r.profile.as_deref().unwrap_or("")))?;
+        }
+        write!(
+            w,
+            "{},{},{},{},{},{},",
+            r.day,
+            r.part,
+            r.status.as_str(),
+            csv_field(&answer_field(r)),
+            r.parse_duration
+                .map(|d| d.as_nanos().to_string())
+                .unwrap_or_default(),
+            r.duration
+                .map(|d| d.as_nanos().to_string())
+                .unwrap_or_default(),
+        )?;
+        if has_alloc {
+            match r.alloc {
+                Some(a) => write!(w, "{},{},{},", a.allocations, a.bytes, a.peak_bytes)?,
+                None => write!(w, ",,,")?,
+            }
+        }
+        writeln!(w, "{}", csv_field(r.error.as_deref().unwrap_or("")))?;
+    }
+
+    Ok(())
+}
+
+// Problems without an answer leave the field empty like failed problems.
+fn answer_field(r: &ProblemResult) -> String {
+    match &r.answer {
+        Some(Answer::None) | None => String::new(),
+        Some(a) => a.to_string(),
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn write_markdown(w: &mut impl Write, has_profiles: bool, results: &[ProblemResult]) -> Result<()> {
+    let has_alloc = results.iter().any(|r| r.alloc.is_some());
+
+    let mut columns = vec![
+        ("Day", "---:"),
+        ("Part", "---:"),
+        ("Answer", "---:"),
+        ("Parse", "---:"),
+        ("Time", "---:"),
+    ];
+    if has_profiles {
+        columns.insert(0, ("Profile", ":---"));
+    }
+    if has_alloc {
+        columns.extend([
+            ("Allocations", "---:"),
+            ("Allocated", "---:"),
+            ("Peak", "---:"),
+        ]);
+    }
+    columns.extend([("Status", ":---"), ("Error", ":---")]);
+    for (name, _) in &columns {
+        write!(w, "| {} ", name)?;
+    }
+    writeln!(w, "|")?;
+    for (_, align) in &columns {
+        write!(w, "| {} ", align)?;
+    }
+    writeln!(w, "|")?;
+
+    for r in results {
+        if has_profiles {
+            write!(w, "| {} ", r.profile.as_deref().unwrap_or(""))?;
+        }
+        write!(
+            w,
+            "| {} | {} | {} | {} | {} ",
+            r.day,
+            r.part,
+            answer_field(r).replace('|', "\\|"),
+            r.parse_duration
+                .map(|d| format!("{:.2?}", d))
+                .unwrap_or_default(),
+            r.duration.map(|d| format!("{:.2?}", d)).unwrap_or_default(),
+        )?;
+        if has_alloc {
+            match r.alloc {
+                Some(a) => write!(
+                    w,
+                    "| {} | {} | {} ",
+                    a.allocations,
+                    Bytes(a.bytes),
+                    Bytes(a.peak_bytes)
+                )?,
+                None => write!(w, "|  |  |  ")?,
+            }
+        }
+        writeln!(
+            w,
+            "| {} | {} |",
+            r.status.as_str(),
+            r.error.as_deref().unwrap_or("").replace('|', "\\|"),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solved(
+        answer: impl Into<Answer>,
+        parse: Duration,
+        solve: Duration,
+    ) -> Result<(Answer, Timing)> {
+        Ok((
+            answer.into(),
+            Timing {
+                parse,
+                solve,
+                ..Default::default()
+            },
+        ))
+    }
+
+    fn example_results() -> Vec<ProblemResult> {
+        let us = Duration::from_micros;
+        let ns = Duration::from_nanos;
+        vec![
+            ProblemResult::new(1, 1, solved(142u64, us(2), us(15))),
+            ProblemResult::new(25, 2, solved("a, \"b\"", ns(100), ns(400))),
+            ProblemResult::new(
+                24,
+                2,
+                Err(anyhow::Error::from(error::Error::NotImplemented).context("problemfn failed")),
+            ),
+        ]
+    }
+
+    fn render(format: Format, verbose: bool) -> String {
+        let mut buf = Vec::new();
+        write(&mut buf, format, verbose, &example_results()).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn csv_test() {
+        let expected = "day,part,status,answer,parse_ns,duration_ns,error
+1,1,ok,142,2000,15000,
+25,2,ok,\"a, \"\"b\"\"\",100,400,
+24,2,error,,,,problemfn failed: not yet implemented
+";
+        assert_eq!(render(Format::Csv, false), expected);
+    }
+
+    #[test]
+    fn json_test() {
+        let v: serde_json::Value = serde_json::from_str(&render(Format::Json, false)).unwrap();
+        assert_eq!(v[0]["answer"], 142);
+        assert_eq!(v[1]["answer"], "a, \"b\"");
+        assert_eq!(v[0]["parse_ns"], 2000);
+        assert_eq!(v[0]["duration_ns"], 15000);
+        assert_eq!(v[2]["status"], "error");
+        assert!(v[2].get("answer").is_none());
+        assert_eq!(v[2]["error_kind"], "not_implemented");
+        assert_eq!(v[2]["error"], "problemfn failed: not yet implemented");
+        assert!(v[0].get("error_kind").is_none());
+    }
+
+    #[test]
+    fn json_round_trip_test() {
+        let results: Vec<ProblemResult> =
+            serde_json::from_str(&render(Format::Json, false)).unwrap();
+        assert_eq!(results[0].answer, Some(Answer::Unsigned(142)));
+        assert_eq!(results[0].parse_duration, Some(Duration::from_micros(2)));
+        assert_eq!(results[0].duration, Some(Duration::from_micros(15)));
+        assert_eq!(results[2].status, Status::Error);
+        assert_eq!(results[2].answer, None);
+        assert_eq!(results[2].error_kind, Some(ErrorKind::NotImplemented));
+
+        // Day 25 part 2 is solved but has nothing to answer
+        let none = ProblemResult::new(25, 2, Ok((Answer::None, Default::default())));
+        let json = serde_json::to_string(&none).unwrap();
+        assert!(json.contains(r#""answer":null"#), "{}", json);
+        let decoded: ProblemResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.status, Status::Ok);
+        assert_eq!(decoded.answer, Some(Answer::None));
+    }
+
+    #[test]
+    fn profile_comparison_test() {
+        let ms = Duration::from_millis;
+        let results = vec![
+            ProblemResult::new(1, 1, solved(1u64, ms(0), ms(1))).with_profile("default"),
+            ProblemResult::new(1, 1, solved(2u64, ms(0), ms(5))).with_profile("alice"),
+            ProblemResult::new(2, 1, solved(3u64, ms(0), ms(1))).with_profile("default"),
+            ProblemResult::failed(2, 1, Status::Timeout, "too slow".to_owned())
+                .with_profile("alice"),
+        ];
+
+        let mut buf = Vec::new();
+        write(&mut buf, Format::Text, false, &results).unwrap();
+        let expected = "           default        alice
+ 1-1:       1.00ms       5.00ms
+ 2-1:       1.00ms      TIMEOUT
+
+Differences between profiles:
+ 1-1: runtime varies 5.0x (fastest default, slowest alice)
+ 2-1: TIMEOUT in alice
+";
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+
+        let mut buf = Vec::new();
+        write(&mut buf, Format::Csv, false, &results).unwrap();
+        let csv = String::from_utf8(buf).unwrap();
+        assert!(csv.starts_with("profile,day,part,"));
+        assert!(csv.contains("\nalice,2,1,timeout,,,,too slow\n"));
+    }
+
+    #[test]
+    fn alloc_test() {
+        let alloc = AllocStats {
+            allocations: 3,
+            bytes: 2048,
+            peak_bytes: 1024,
+        };
+        let timing = Timing {
+            parse: Duration::from_micros(2),
+            solve: Duration::from_micros(15),
+            solve_alloc: Some(alloc),
+            ..Default::default()
+        };
+        let mut results = example_results();
+        results[0] = ProblemResult::new(1, 1, Ok((142u64.into(), timing)));
+
+        let mut buf = Vec::new();
+        write(&mut buf, Format::Text, false, &results).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert!(
+            text.starts_with(" 1-1: 15µs (parse 2µs), 3 allocations, 2.00 KiB, peak 1.00 KiB\n")
+        );
+
+        let mut buf = Vec::new();
+        write(&mut buf, Format::Csv, false, &results).unwrap();
+        let csv = String::from_utf8(buf).unwrap();
+        assert!(csv.starts_with("day,part,status,answer,parse_ns,duration_ns,allocations,alloc_bytes,peak_bytes,error\n1,1,ok,142,2000,15000,3,2048,1024,\n"));
+        assert!(csv.ends_with("\n24,2,error,,,,,,,problemfn failed: not yet implemented\n"));
+
+        let mut buf = Vec::new();
+        write(&mut buf, Format::Json, false, &results).unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(v[0]["alloc"]["peak_bytes"], 1024);
+        assert!(v[0].get("parse_alloc").is_none());
+        assert!(v[1].get("alloc").is_none());
+    }
+
+    #[test]
+    fn trace_test() {
+        let mut trace = Trace::default();
+        trace.counters.insert("pops".to_owned(), 42);
+        let timing = Timing {
+            trace,
+            ..Default::default()
+        };
+        let results = vec![ProblemResult::new(17, 1, Ok((5u64.into(), timing)))];
+
+        let mut buf = Vec::new();
+        write(&mut buf, Format::Text, true, &results).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "17-1: 0ns (parse 0ns)\n      pops: 42\n"
+        );
+
+        let mut buf = Vec::new();
+        write(&mut buf, Format::Json, false, &results).unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(v[0]["trace"]["counters"]["pops"], 42);
+        // Results without a trace leave it out
+        assert!(!render(Format::Json, false).contains("trace"));
+    }
+
+    #[test]
+    fn text_test() {
+        assert_eq!(
+            render(Format::Text, false),
+            " 1-1: 15µs (parse 2µs)\n25-2: 400ns (parse 100ns)\n24-2: ERROR (not implemented)\n"
+        );
+        assert_eq!(
+            render(Format::Text, true),
+            " 1-1: 15µs (parse 2µs)\n25-2: 400ns (parse 100ns)\n\
+             24-2: ERROR (not implemented): problemfn failed: not yet implemented\n"
+        );
+    }
+
+    #[test]
+    fn exit_code_test() {
+        let mut results = example_results();
+        assert_eq!(exit_code(&results), 0);
+
+        let kinds = [
+            (error::Error::parse("", ""), 3),
+            (error::Error::InvalidInput("bad".to_owned()), 4),
+            (error::Error::NoSolution("stuck".to_owned()), 5),
+            (error::Error::Internal("bug".to_owned()), 6),
+        ];
+        for (err, code) in kinds {
+            results.push(ProblemResult::new(2, 1, Err(err.into())));
+            assert_eq!(exit_code(&results), code);
+        }
+
+        // Errors of unknown kind count as internal, and the most serious
+        // failure wins regardless of order
+        let untyped = ProblemResult::new(3, 1, Err(anyhow::anyhow!("oops")));
+        assert_eq!(untyped.error_kind, Some(ErrorKind::Internal));
+        results.insert(
+            0,
+            ProblemResult::failed(3, 2, Status::Panic, "boom".to_owned()),
+        );
+        results.push(ProblemResult::failed(
+            4,
+            1,
+            Status::Timeout,
+            "slow".to_owned(),
+        ));
+        assert_eq!(exit_code(&results), 8);
+
+        for r in &results {
+            let code = r.exit_code();
+            if code != 0 {
+                let (status, kind) = failure_for_exit_code(code.into()).unwrap();
+                assert_eq!(status, r.status);
+                assert_eq!(kind, r.error_kind.filter(|_| r.status == Status::Error));
+            }
+        }
+        assert_eq!(failure_for_exit_code(1), None);
+    }
+}
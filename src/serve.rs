@@ -0,0 +1,355 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use adventofcode2023::solutions;
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+
+use crate::report::{ProblemResult, Status};
+
+// Inputs are a few KiB; anything much larger is a mistake.
+const MAX_BODY: usize = 16 << 20;
+// Clients that stall while sending a request are dropped after this long.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+// Turning a client away must not hold up the connections behind it.
+const BUSY_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Solves a part of a day from the given input.
+pub trait Solver: Fn(usize, usize, &str) -> ProblemResult + Sync {}
+
+impl<F: Fn(usize, usize, &str) -> ProblemResult + Sync> Solver for F {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl Response {
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+// Serves the API on localhost until the process is killed. Each connection is
+// handled on its own thread and answered with a single response. At most
+// `max_connections` are handled at once; the ones beyond that are answered
+// with 503 right away.
+//
+//   GET /days                  the registered days
+//   POST /solve/{day}/{part}   solves the part with the body as the input
+pub fn serve(port: u16, max_connections: usize, solve: &impl Solver) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .with_context(|| format!("failed to listen on port {}", port))?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    accept(&listener, max_connections, solve);
+    Ok(())
+}
+
+fn accept(listener: &TcpListener, max_connections: usize, solve: &impl Solver) {
+    let slots = Slots::new(max_connections);
+    std::thread::scope(|s| {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => match slots.try_acquire() {
+                    Some(slot) => {
+                        s.spawn(move || {
+                            handle_connection(stream, solve);
+                            drop(slot);
+                        });
+                    }
+                    None => turn_away(stream),
+                },
+                Err(e) => eprintln!("failed to accept connection: {}", e),
+            }
+        }
+    });
+}
+
+// Counts the connections being handled, up to a limit.
+struct Slots {
+    used: AtomicUsize,
+    max: usize,
+}
+
+// Frees its slot when dropped.
+struct Slot<'a>(&'a Slots);
+
+impl Slots {
+    fn new(max: usize) -> Self {
+        Self {
+            used: AtomicUsize::new(0),
+            max,
+        }
+    }
+
+    fn try_acquire(&self) -> Option<Slot<'_>> {
+        self.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                (used < self.max).then_some(used + 1)
+            })
+            .ok()
+            .map(|_| Slot(self))
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.used.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+// Answers on the accepting thread without reading the request, so a busy
+// server costs no more than a write per connection.
+fn turn_away(mut stream: TcpStream) {
+    let res = Response::error(503, "too many connections, try again later");
+    eprintln!("- {}", res.status);
+    let _ = stream.set_write_timeout(Some(BUSY_WRITE_TIMEOUT));
+    if let Err(e) = write_response(&mut stream, &res) {
+        eprintln!("failed to send response: {}", e);
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, solve: &impl Solver) {
+    let start = Instant::now();
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+
+    let (line, res) = match read_request(&mut stream) {
+        Ok(req) => {
+            let res = handle(&req.method, &req.path, &req.body, solve);
+            (format!("{} {}", req.method, req.path), res)
+        }
+        Err(e) => ("-".to_owned(), Response::error(400, format!("{:#}", e))),
+    };
+    eprintln!("{} {} {:.2?}", line, res.status, start.elapsed());
+
+    if let Err(e) = write_response(&mut stream, &res) {
+        eprintln!("failed to send response: {}", e);
+    }
+}
+
+// Routes a request. Solver failures are reported in the result like `run-all`
+// does, with a status code matching the kind of failure.
+pub fn handle(method: &str, path: &str, body: &[u8], solve: &impl Solver) -> Response {
+    let path = path.split_once('?').map_or(path, |(p, _)| p);
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["days"]) => {
+            let mut days: Vec<usize> = solutions::SOLUTIONS.keys().copied().collect();
+            days.sort_unstable();
+            Response {
+                status: 200,
+                body: json!({ "days": days }),
+            }
+        }
+        ("POST", ["solve", day, part]) => {
+            let (Ok(day), Ok(part)) = (day.parse(), part.parse()) else {
+                return Response::error(404, "day and part must be numbers");
+            };
+            if !solutions::SOLUTIONS.contains_key(&day) {
+                return Response::error(404, format!("unknown day: {}", day));
+            }
+            if !(1..=2).contains(&part) {
+                return Response::error(404, format!("unknown problem number: {}", part));
+            }
+            let Ok(input) = std::str::from_utf8(body) else {
+                return Response::error(400, "input is not valid UTF-8");
+            };
+
+            let res = solve(day, part, input);
+            let status = match res.status {
+                Status::Ok => 200,
+                Status::Error => 422,
+                Status::Panic => 500,
+                Status::Timeout => 504,
+            };
+            Response {
+                status,
+                body: serde_json::to_value(res).unwrap_or_default(),
+            }
+        }
+        (_, ["days"]) | (_, ["solve", _, _]) => Response::error(405, "method not allowed"),
+        _ => Response::error(404, format!("no such endpoint: {}", path)),
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+fn read_request(stream: &mut impl Read) -> Result<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        bail!("malformed request line");
+    };
+    let (method, path) = (method.to_owned(), path.to_owned());
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            bail!("connection closed in the headers");
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().context("invalid Content-Length")?;
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        bail!("request body is larger than {} bytes", MAX_BODY);
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request { method, path, body })
+}
+
+fn write_response(w: &mut impl Write, res: &Response) -> std::io::Result<()> {
+    let body = res.body.to_string();
+    write!(
+        w,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        res.status,
+        reason(res.status),
+        body.len(),
+        body
+    )?;
+    w.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        422 => "Unprocessable Entity",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Mutex};
+
+    use super::*;
+
+    fn solve(day: usize, part: usize, input: &str) -> ProblemResult {
+        let res = adventofcode2023::solve(day, part, input).map(|ans| (ans, Default::default()));
+        ProblemResult::new(day, part, res)
+    }
+
+    #[test]
+    fn handle_test() {
+        let res = handle("GET", "/days", b"", &solve);
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body["days"][0], 1);
+
+        let input = b"Time:      7  15   30\nDistance:  9  40  200\n";
+        let res = handle("POST", "/solve/6/2", input, &solve);
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body["answer"], 71503);
+        assert_eq!(res.body["status"], "ok");
+
+        let res = handle("POST", "/solve/6/1", b"garbage", &solve);
+        assert_eq!(res.status, 422);
+        assert_eq!(res.body["status"], "error");
+
+        assert_eq!(handle("POST", "/solve/18/1", input, &solve).status, 404);
+        assert_eq!(handle("POST", "/solve/6/3", input, &solve).status, 404);
+        assert_eq!(handle("POST", "/solve/x/1", input, &solve).status, 404);
+        assert_eq!(handle("GET", "/solve/6/1", b"", &solve).status, 405);
+        assert_eq!(handle("GET", "/", b"", &solve).status, 404);
+    }
+
+    #[test]
+    fn slots_test() {
+        let slots = Slots::new(2);
+        let first = slots.try_acquire().unwrap();
+        let _second = slots.try_acquire().unwrap();
+        assert!(slots.try_acquire().is_none());
+        drop(first);
+        assert!(slots.try_acquire().is_some());
+    }
+
+    #[test]
+    fn busy_test() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (entered_tx, entered) = mpsc::channel();
+        let (release, release_rx) = mpsc::channel::<()>();
+        let (entered_tx, release_rx) = (Mutex::new(entered_tx), Mutex::new(release_rx));
+        // Holds the only slot until the test lets it go
+        std::thread::spawn(move || {
+            accept(&listener, 1, &move |day, part, input: &str| {
+                entered_tx.lock().unwrap().send(()).unwrap();
+                release_rx.lock().unwrap().recv().unwrap();
+                solve(day, part, input)
+            })
+        });
+
+        let input = "Time: 7\nDistance: 9\n";
+        let mut first = TcpStream::connect(addr).unwrap();
+        write!(
+            first,
+            "POST /solve/6/1 HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            input.len(),
+            input
+        )
+        .unwrap();
+        entered.recv().unwrap();
+
+        let mut busy = String::new();
+        let mut second = TcpStream::connect(addr).unwrap();
+        second.read_to_string(&mut busy).unwrap();
+        assert!(
+            busy.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+            "{}",
+            busy
+        );
+
+        release.send(()).unwrap();
+        let mut ok = String::new();
+        first.read_to_string(&mut ok).unwrap();
+        assert!(ok.starts_with("HTTP/1.1 200 OK\r\n"), "{}", ok);
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let request = "POST /solve/6/1?x=1 HTTP/1.1\r\nHost: localhost\r\n\
+                       content-length: 5\r\n\r\nabcde";
+        let req = read_request(&mut request.as_bytes()).unwrap();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/solve/6/1?x=1");
+        assert_eq!(req.body, b"abcde");
+        assert!(read_request(&mut "\r\n".as_bytes()).is_err());
+
+        let mut buf = Vec::new();
+        write_response(&mut buf, &Response::error(404, "nope")).unwrap();
+        let response = String::from_utf8(buf).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(response
+            .ends_with("Content-Length: 16\r\nConnection: close\r\n\r\n{\"error\":\"nope\"}"));
+    }
+}
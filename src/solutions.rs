@@ -1,18 +1,30 @@
+use std::any::Any;
 use std::collections::HashMap;
 
+use anyhow::anyhow;
+
+use crate::animation::Frames;
+use crate::answer::Answer;
+use crate::error::Error;
+use crate::generate::InputGenerator;
+use crate::params::ParamInfo;
+use crate::render::Canvas;
+
 macro_rules! days {
     ($($x:ident), *) => {
         $(
             mod $x;
         )*
 
-        const SOLUTIONS_ARR: &'static [Solution] = &[
+        const SOLUTIONS_ARR: &'static [Entry] = &[
             $(
-                Solution {
+                Entry {
                     day: stringify!($x),
-                    problem1: $x::problem1,
-                    problem2: $x::problem2,
-                    input: include_str!(concat!("../puzzle-inputs/", stringify!($x), ".txt"))
+                    solver: &$x::Solver,
+                    #[cfg(feature = "embedded-inputs")]
+                    input: Some(include_str!(concat!("../puzzle-inputs/", stringify!($x), ".txt"))),
+                    #[cfg(not(feature = "embedded-inputs"))]
+                    input: None,
                 },
             )*
         ];
@@ -22,7 +34,7 @@ macro_rules! days {
 }
 
 lazy_static! {
-    pub static ref SOLUTIONS: HashMap<usize, &'static Solution> =
+    pub static ref SOLUTIONS: HashMap<usize, &'static Entry> =
         HashMap::from_iter(SOLUTIONS_ARR.iter().map(|x| (mod_name_to_num(x.day), x)));
 }
 
@@ -33,26 +45,180 @@ fn mod_name_to_num(name: &str) -> usize {
         .fold(0, |acc, x| acc * 10 + x as usize)
 }
 
-pub type ProblemFn = fn(&str) -> Result<String, anyhow::Error>;
+// A day's solution. The input is parsed once and shared by both parts so
+// parsing and solving can be timed separately.
+pub trait Solution {
+    type Parsed: Send + Sync + 'static;
+
+    // Puzzle constants that can be changed from the command line.
+    const PARAMS: &'static [&'static dyn ParamInfo] = &[];
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error>;
+    fn part1(parsed: &Self::Parsed) -> Result<Answer, anyhow::Error>;
+    fn part2(parsed: &Self::Parsed) -> Result<Answer, anyhow::Error>;
+
+    // Draws the grid of a part with what solving it found on top. Days
+    // without a grid worth drawing return None.
+    fn render(_parsed: &Self::Parsed, _part: usize) -> Result<Option<Canvas>, anyhow::Error> {
+        Ok(None)
+    }
+
+    // Steps through the simulation of a part one frame at a time, for the
+    // days that have one worth watching.
+    fn animate(_parsed: &Self::Parsed, _part: usize) -> Result<Option<Frames<'_>>, anyhow::Error> {
+        Ok(None)
+    }
+
+    // Writes a random input that follows the puzzle's rules, for stress and
+    // property tests.
+    fn generate(_gen: &mut InputGenerator) -> Option<String> {
+        None
+    }
+
+    // A slow but obviously correct way to solve a part, for the days whose
+    // fast solution takes a shortcut worth checking with `cross-check`.
+    fn reference(_parsed: &Self::Parsed, _part: usize) -> Result<Option<Answer>, anyhow::Error> {
+        Ok(None)
+    }
+
+    // Parses and solves in one go, used by the example tests.
+    #[cfg(test)]
+    fn problem1(input: &str) -> Result<Answer, anyhow::Error> {
+        Self::part1(&Self::parse(input)?)
+    }
+
+    #[cfg(test)]
+    fn problem2(input: &str) -> Result<Answer, anyhow::Error> {
+        Self::part2(&Self::parse(input)?)
+    }
+}
+
+pub type Parsed = Box<dyn Any + Send + Sync>;
+
+// Asking for a part other than 1 or 2 is a mistake of the caller, not a bug.
+pub(crate) fn unknown_part(part: usize) -> anyhow::Error {
+    Error::InvalidInput(format!("unknown problem number: {}", part)).into()
+}
+
+// Object safe version of `Solution` used by the registry.
+pub trait DynSolution: Sync {
+    fn params(&self) -> &'static [&'static dyn ParamInfo];
+    fn parse(&self, input: &str) -> Result<Parsed, anyhow::Error>;
+    fn solve(&self, parsed: &Parsed, part: usize) -> Result<Answer, anyhow::Error>;
+    fn render(&self, parsed: &Parsed, part: usize) -> Result<Option<Canvas>, anyhow::Error>;
+    fn animate<'a>(
+        &self,
+        parsed: &'a Parsed,
+        part: usize,
+    ) -> Result<Option<Frames<'a>>, anyhow::Error>;
+    fn generate(&self, gen: &mut InputGenerator) -> Option<String>;
+    fn reference(&self, parsed: &Parsed, part: usize) -> Result<Option<Answer>, anyhow::Error>;
+}
+
+impl<S: Solution + Sync> DynSolution for S {
+    fn params(&self) -> &'static [&'static dyn ParamInfo] {
+        S::PARAMS
+    }
+
+    fn parse(&self, input: &str) -> Result<Parsed, anyhow::Error> {
+        Ok(Box::new(S::parse(input)?))
+    }
+
+    fn solve(&self, parsed: &Parsed, part: usize) -> Result<Answer, anyhow::Error> {
+        let parsed = downcast::<S>(parsed)?;
+        match part {
+            1 => S::part1(parsed),
+            2 => S::part2(parsed),
+            _ => Err(unknown_part(part)),
+        }
+    }
+
+    fn render(&self, parsed: &Parsed, part: usize) -> Result<Option<Canvas>, anyhow::Error> {
+        let parsed = downcast::<S>(parsed)?;
+        match part {
+            1 | 2 => S::render(parsed, part),
+            _ => Err(unknown_part(part)),
+        }
+    }
+
+    fn animate<'a>(
+        &self,
+        parsed: &'a Parsed,
+        part: usize,
+    ) -> Result<Option<Frames<'a>>, anyhow::Error> {
+        let parsed = downcast::<S>(parsed)?;
+        match part {
+            1 | 2 => S::animate(parsed, part),
+            _ => Err(unknown_part(part)),
+        }
+    }
+
+    fn generate(&self, gen: &mut InputGenerator) -> Option<String> {
+        S::generate(gen)
+    }
+
+    fn reference(&self, parsed: &Parsed, part: usize) -> Result<Option<Answer>, anyhow::Error> {
+        let parsed = downcast::<S>(parsed)?;
+        match part {
+            1 | 2 => S::reference(parsed, part),
+            _ => Err(unknown_part(part)),
+        }
+    }
+}
+
+fn downcast<S: Solution>(parsed: &Parsed) -> Result<&S::Parsed, anyhow::Error> {
+    parsed
+        .downcast_ref::<S::Parsed>()
+        .ok_or_else(|| anyhow!("parsed input belongs to another day"))
+}
 
-pub struct Solution {
+pub struct Entry {
     pub day: &'static str,
-    pub problem1: ProblemFn,
-    pub problem2: ProblemFn,
-    pub input: &'static str,
+    pub solver: &'static dyn DynSolution,
+    // The input embedded at compile time, if enabled.
+    pub input: Option<&'static str>,
 }
 
 #[macro_use]
 mod prelude {
-    pub use anyhow::{anyhow, bail, Context};
+    pub use super::Solution;
+    pub use crate::animation::{Frame, Frames};
+    pub use crate::answer::Answer;
+    pub use crate::error::Error;
+    pub use crate::generate::InputGenerator;
+    pub use crate::params::{Param, ParamInfo};
+    pub use crate::render::{Canvas, Color};
+    pub use crate::trace;
+    pub use anyhow::{anyhow, bail};
+    pub use rand::seq::SliceRandom;
+    pub use rand::Rng;
 
     macro_rules! parse {
         ($input:expr) => {
-            <::std::result::Result<_, _> as ::nom::Finish<_, _, _>>::finish(
-                parser::parse($input).map_err(|x| x.to_owned()),
-            )
-            .context("failed to parse input")?
-            .1
+            match ::nom::Finish::finish(parser::parse($input)) {
+                Ok((_, parsed)) => parsed,
+                Err(e) => return Err($crate::error::Error::parse($input, e.input).into()),
+            }
+        };
+    }
+
+    // Failures that are not about the input's format, to raise with `bail!`
+    // or return from `ok_or_else`.
+    macro_rules! invalid_input {
+        ($($arg:tt)*) => {
+            $crate::error::Error::InvalidInput(format!($($arg)*))
+        };
+    }
+
+    macro_rules! no_solution {
+        ($($arg:tt)*) => {
+            $crate::error::Error::NoSolution(format!($($arg)*))
+        };
+    }
+
+    macro_rules! internal {
+        ($($arg:tt)*) => {
+            $crate::error::Error::Internal(format!($($arg)*))
         };
     }
 }
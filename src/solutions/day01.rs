@@ -1,28 +1,70 @@
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let lines = parse!(input);
-    let ans: usize = lines.iter().map(|&x| extract_num(x)).sum();
-    Ok(ans.to_string())
-}
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = Vec<String>;
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let lines = parse!(input);
+        Ok(lines.into_iter().map(str::to_owned).collect())
+    }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let lines = parse!(input);
+    fn part1(lines: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut ans = 0;
+        for (i, line) in lines.iter().enumerate() {
+            let digits: Vec<usize> = line
+                .chars()
+                .filter_map(|x| x.to_digit(10))
+                .map(|x| x as usize)
+                .collect();
+            ans += calibration_value(&digits)
+                .ok_or_else(|| invalid_input!("line {} has no digits: {}", i + 1, line))?;
+        }
+        Ok(ans.into())
+    }
+
+    fn part2(lines: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut ans = 0;
+        for (i, line) in lines.iter().enumerate() {
+            ans += calibration_value(&parser::num_words(line))
+                .ok_or_else(|| {
+                    invalid_input!("line {} has no digits or number words: {}", i + 1, line)
+                })?;
+        }
+        Ok(ans.into())
+    }
 
-    let ans: usize = lines
-        .iter()
-        .map(|x| parser::parse_num_words(x).unwrap().1)
-        .map(|xs| xs.first().unwrap() * 10 + xs.last().unwrap())
-        .sum();
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        const WORDS: &[&str] = &[
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ];
 
-    Ok(ans.to_string())
+        let lines: Vec<String> = (0..gen.size().max(1))
+            .map(|_| {
+                // Part 1 needs a digit on every line
+                let mut pieces = vec![gen.gen_range(1..=9).to_string()];
+                for _ in 0..gen.gen_range(1..6) {
+                    let piece = match gen.gen_range(0..3) {
+                        0 => gen.gen_range(1..=9).to_string(),
+                        1 => WORDS.choose(gen).unwrap().to_string(),
+                        _ => {
+                            let len = gen.gen_range(1..4);
+                            gen.word(len)
+                        }
+                    };
+                    pieces.push(piece);
+                }
+                pieces.shuffle(gen);
+                pieces.concat()
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
 }
 
-fn extract_num(s: &str) -> usize {
-    let digits: Vec<_> = s.chars().filter_map(|x| x.to_digit(10)).collect();
-    let first = digits.first().unwrap();
-    let last = digits.last().unwrap();
-    (first * 10 + last) as usize
+fn calibration_value(digits: &[usize]) -> Option<usize> {
+    Some(digits.first()? * 10 + digits.last()?)
 }
 
 mod parser {
@@ -33,7 +75,9 @@ mod parser {
         ws_all_consuming(many1(l))(input)
     }
 
-    pub fn parse_num_words(input: &str) -> IResult<&str, Vec<usize>> {
+    // Every digit and spelled out digit in the line, overlapping ones
+    // included.
+    pub fn num_words(input: &str) -> Vec<usize> {
         let mut num = alt::<_, _, (), _>((
             value(1, tag("one")),
             value(2, tag("two")),
@@ -44,18 +88,16 @@ mod parser {
             value(7, tag("seven")),
             value(8, tag("eight")),
             value(9, tag("nine")),
-            one_of("0123456789").map(|x| x.to_digit(10).unwrap() as usize),
+            one_of("0123456789").map(|x| x as usize - '0' as usize),
         ));
 
-        let ret: Vec<_> = input
+        input
             .char_indices()
             .filter_map(|(i, _)| {
                 let s = &input[i..];
                 num(s).ok().map(|(_, x)| x)
             })
-            .collect();
-
-        Ok(("", ret))
+            .collect()
     }
 }
 
@@ -78,11 +120,19 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "142")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "142")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT_2).unwrap(), "281")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT_2).unwrap(), "281")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::problem1("1abc2\nnodigits").unwrap_err();
+        assert_eq!(err.to_string(), "line 2 has no digits: nodigits");
+        let err = Solver::problem2("two1\nabc").unwrap_err();
+        assert_eq!(err.to_string(), "line 2 has no digits or number words: abc");
     }
 }
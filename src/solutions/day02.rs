@@ -2,26 +2,68 @@ use crate::solutions::prelude::*;
 
 use std::cmp;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let games = parse!(input);
-    let ans: usize = games
-        .iter()
-        .map(|g| (g.id, g.max_combined_draw()))
-        .filter(|(_, s)| s.red <= 12 && s.blue <= 14 && s.green <= 13)
-        .map(|(id, _)| id)
-        .sum();
-
-    Ok(ans.to_string())
-}
+pub struct Solver;
+
+const MAX_RED: Param<usize> = Param::new("max-red", 12, "red cubes in the bag in part 1");
+const MAX_GREEN: Param<usize> = Param::new("max-green", 13, "green cubes in the bag in part 1");
+const MAX_BLUE: Param<usize> = Param::new("max-blue", 14, "blue cubes in the bag in part 1");
+
+impl Solution for Solver {
+    type Parsed = Vec<Game>;
+
+    const PARAMS: &'static [&'static dyn ParamInfo] = &[&MAX_RED, &MAX_GREEN, &MAX_BLUE];
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Ok(parse!(input))
+    }
+
+    fn part1(games: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let (red, green, blue) = (MAX_RED.get(), MAX_GREEN.get(), MAX_BLUE.get());
+        let ans: usize = games
+            .iter()
+            .map(|g| (g.id, g.max_combined_draw()))
+            .filter(|(_, s)| s.red <= red && s.blue <= blue && s.green <= green)
+            .map(|(id, _)| id)
+            .sum();
+
+        Ok(ans.into())
+    }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let games = parse!(input);
-    let ans: usize = games.iter().map(|g| g.max_combined_draw().power()).sum();
-    Ok(ans.to_string())
+    fn part2(games: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut ans: usize = 0;
+        for g in games {
+            let power = g.max_combined_draw().power();
+            ans = power
+                .and_then(|p| ans.checked_add(p))
+                .ok_or_else(|| invalid_input!("game {} needs too many cubes", g.id))?;
+        }
+        Ok(ans.into())
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        let lines: Vec<String> = (1..=gen.size().max(1))
+            .map(|id| {
+                let draws: Vec<String> = (0..gen.gen_range(1..=6))
+                    .map(|_| {
+                        let mut colors = ["red", "green", "blue"];
+                        colors.shuffle(gen);
+                        let n = gen.gen_range(1..=3);
+                        let cubes: Vec<String> = colors[..n]
+                            .iter()
+                            .map(|c| format!("{} {}", gen.gen_range(1..=20), c))
+                            .collect();
+                        cubes.join(", ")
+                    })
+                    .collect();
+                format!("Game {}: {}", id, draws.join("; "))
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
 }
 
 #[derive(Clone, Debug)]
-struct Game {
+pub struct Game {
     id: usize,
     draws: Vec<Draw>,
 }
@@ -50,11 +92,18 @@ impl Draw {
         }
     }
 
-    fn power(&self) -> usize {
-        self.red * self.blue * self.green
+    fn power(&self) -> Option<usize> {
+        self.red.checked_mul(self.blue)?.checked_mul(self.green)
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+enum Color {
+    Red,
+    Blue,
+    Green,
+}
+
 mod parser {
     use super::*;
     use crate::parser::prelude::*;
@@ -67,14 +116,17 @@ mod parser {
     }
 
     fn draw(input: &str) -> IResult<&str, Draw> {
-        let color = alt((tag("red"), tag("blue"), tag("green")));
+        let color = alt((
+            value(Color::Red, tag("red")),
+            value(Color::Blue, tag("blue")),
+            value(Color::Green, tag("green")),
+        ));
         let draw_value = separated_pair(uint, space1, color);
         let g = |mut acc: Draw, (count, color)| {
             match color {
-                "red" => acc.red = count,
-                "blue" => acc.blue = count,
-                "green" => acc.green = count,
-                _ => unreachable!(),
+                Color::Red => acc.red = count,
+                Color::Blue => acc.blue = count,
+                Color::Green => acc.green = count,
             };
             acc
         };
@@ -95,11 +147,18 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "8")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "8")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "2286")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "2286")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let input = "Game 1: 3 blue, 4 red\nGame 2: 4294967296 red, 4294967296 green, 1 blue";
+        let err = Solver::problem2(input).unwrap_err();
+        assert_eq!(err.to_string(), "game 2 needs too many cubes");
     }
 }
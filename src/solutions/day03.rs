@@ -3,52 +3,103 @@ use crate::solutions::prelude::*;
 
 use ahash::HashMap;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
-    let parts = locate_parts(&grid);
-    let ans: usize = parts
-        .iter()
-        .filter(|part| {
-            let digit_points = part.points();
-            let adj_points = digit_points.flat_map(|p| p.iter_adjacent8());
-            let mut adj_chars = adj_points.filter_map(|p| grid.get(p));
-            adj_chars.any(|c| is_symbol(*c))
-        })
-        .map(|part| part.num)
-        .sum();
-    Ok(ans.to_string())
-}
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = Grid<u8>;
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
-    let parts = locate_parts(&grid);
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Grid::try_new(parse!(input))
+    }
 
-    let part_locs: HashMap<_, _> = parts
-        .iter()
-        .flat_map(|p| p.points().zip(std::iter::repeat(p)))
-        .collect();
+    fn part1(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let parts = locate_parts(grid)?;
+        let ans: usize = parts
+            .iter()
+            .filter(|part| {
+                let digit_points = part.points();
+                let adj_points = digit_points.flat_map(|p| p.iter_adjacent8());
+                let mut adj_chars = adj_points.filter_map(|p| grid.get(p));
+                adj_chars.any(|c| is_symbol(*c))
+            })
+            .map(|part| part.num)
+            .sum();
+        Ok(ans.into())
+    }
 
-    let gear_locs = grid
-        .iter_points()
-        .filter(|p| *grid.get(*p).unwrap_or(&b'.') == b'*');
+    fn part2(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let parts = locate_parts(grid)?;
 
-    let gear_adj_parts = gear_locs.map(|loc| {
-        let mut adj_nums: Vec<_> = loc
-            .iter_adjacent8()
-            .filter_map(|p| part_locs.get(&p))
+        let part_locs: HashMap<_, _> = parts
+            .iter()
+            .flat_map(|p| p.points().zip(std::iter::repeat(p)))
             .collect();
-        adj_nums.sort_unstable();
-        adj_nums.dedup();
-        adj_nums
-    });
 
-    let true_gears = gear_adj_parts.filter(|x| x.len() == 2);
+        let gear_locs = grid
+            .iter_points()
+            .filter(|p| *grid.get(*p).unwrap_or(&b'.') == b'*');
+
+        let gear_adj_parts = gear_locs.map(|loc| {
+            let mut adj_nums: Vec<_> = loc
+                .iter_adjacent8()
+                .filter_map(|p| part_locs.get(&p))
+                .collect();
+            adj_nums.sort_unstable();
+            adj_nums.dedup();
+            adj_nums
+        });
+
+        let true_gears = gear_adj_parts.filter(|x| x.len() == 2);
+
+        let mut ans: usize = 0;
+        for x in true_gears {
+            ans = x[0]
+                .num
+                .checked_mul(x[1].num)
+                .and_then(|ratio| ans.checked_add(ratio))
+                .ok_or_else(|| {
+                    invalid_input!(
+                        "gear ratio of the numbers at row {} and row {} is too big",
+                        x[0].row + 1,
+                        x[1].row + 1
+                    )
+                })?;
+        }
+        Ok(ans.into())
+    }
 
-    let ans: usize = true_gears.map(|x| x[0].num * x[1].num).sum();
-    Ok(ans.to_string())
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        // Gears are the interesting symbol, so there are more of them
+        const SYMBOLS: &[u8] = b"***#+$/@=%&-";
+
+        let n = gen.size().max(3);
+        let rows: Vec<String> = (0..n)
+            .map(|_| {
+                let mut row = String::new();
+                while row.len() < n {
+                    match gen.gen_range(0..10) {
+                        0 | 1 => {
+                            let num = gen.gen_range(1..1000).to_string();
+                            if num.len() <= n - row.len() {
+                                row.push_str(&num);
+                                // Numbers next to each other would read as one
+                                if row.len() < n {
+                                    row.push('.');
+                                }
+                            }
+                        }
+                        2 => row.push(*SYMBOLS.choose(gen).unwrap() as char),
+                        _ => row.push('.'),
+                    }
+                }
+                row
+            })
+            .collect();
+        Some(rows.join("\n"))
+    }
 }
 
-fn locate_parts(g: &Grid<u8>) -> Vec<PartNum> {
+fn locate_parts(g: &Grid<u8>) -> Result<Vec<PartNum>, anyhow::Error> {
     let mut cur = PartNum::default();
     let mut ret = Vec::new();
 
@@ -60,7 +111,17 @@ fn locate_parts(g: &Grid<u8>) -> Vec<PartNum> {
                     cur.col_start = j;
                 }
 
-                cur.num = cur.num * 10 + d as usize;
+                cur.num = cur
+                    .num
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(d as usize))
+                    .ok_or_else(|| {
+                        invalid_input!(
+                            "number at row {} column {} is too big",
+                            i + 1,
+                            cur.col_start + 1
+                        )
+                    })?;
                 cur.col_end = j
             } else {
                 if cur.num != 0 {
@@ -76,7 +137,7 @@ fn locate_parts(g: &Grid<u8>) -> Vec<PartNum> {
         }
     }
 
-    ret
+    Ok(ret)
 }
 
 fn is_symbol(c: u8) -> bool {
@@ -101,15 +162,12 @@ impl PartNum {
 mod parser {
     use nom::bytes::complete::take_while1;
 
-    use super::*;
     use crate::parser::prelude::*;
 
-    pub fn parse(input: &str) -> IResult<&str, Grid<u8>> {
+    pub fn parse(input: &str) -> IResult<&str, Vec<Vec<u8>>> {
         let row = ws_line(take_while1(|c: char| !c.is_ascii_whitespace()))
             .map(|x| x.as_bytes().to_owned());
-        let rows = many1(row);
-        let grid = map(rows, |d| Grid::new(d));
-        ws_all_consuming(grid)(input)
+        ws_all_consuming(many1(row))(input)
     }
 }
 
@@ -130,11 +188,26 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "4361")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "4361")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "467835")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "467835")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::parse("12.\n4").unwrap_err();
+        assert_eq!(err.to_string(), "row 2 of the grid has 1 cells, but row 1 has 3");
+
+        let err = Solver::problem1("......................\n.99999999999999999999*").unwrap_err();
+        assert_eq!(err.to_string(), "number at row 2 column 2 is too big");
+
+        let err = Solver::problem2("4294967296*4294967296").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "gear ratio of the numbers at row 1 and row 1 is too big"
+        );
     }
 }
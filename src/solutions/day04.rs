@@ -2,29 +2,90 @@ use ahash::AHashSet;
 
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let cards = parse!(input);
-    Ok(cards.iter().map(|c| c.points()).sum::<usize>().to_string())
-}
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = Vec<ScratchCard>;
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let cards = parse!(input);
-    let mut card_counts = vec![1; cards.len()];
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Ok(parse!(input))
+    }
 
-    for (i, card) in cards.iter().enumerate() {
-        let cnt = card_counts[i];
-        let start = i + 1;
-        let end = start + card.num_matches();
-        for c in card_counts[start..end].iter_mut() {
-            *c += cnt;
+    fn part1(cards: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut ans: usize = 0;
+        for c in cards {
+            ans = c
+                .points()
+                .and_then(|p| ans.checked_add(p))
+                .ok_or_else(|| invalid_input!("card {} is worth too many points", c.id))?;
         }
+        Ok(ans.into())
     }
 
-    Ok(card_counts.iter().sum::<usize>().to_string())
+    fn part2(cards: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut card_counts: Vec<usize> = vec![1; cards.len()];
+
+        for (i, card) in cards.iter().enumerate() {
+            let cnt = card_counts[i];
+            let start = i + 1;
+            let end = start + card.num_matches();
+            let Some(won) = card_counts.get_mut(start..end) else {
+                bail!(invalid_input!(
+                    "card {} wins copies of {} cards, but only {} come after it",
+                    card.id,
+                    end - start,
+                    cards.len() - start
+                ));
+            };
+            for c in won {
+                *c = c
+                    .checked_add(cnt)
+                    .ok_or_else(|| {
+                        invalid_input!("too many copies of cards after card {}", card.id)
+                    })?;
+            }
+        }
+
+        let ans = card_counts
+            .iter()
+            .try_fold(0usize, |acc, &c| acc.checked_add(c))
+            .ok_or_else(|| invalid_input!("too many cards in total"))?;
+        Ok(ans.into())
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        Some(generate_cards(gen).1)
+    }
 }
 
-struct ScratchCard {
-    #[allow(dead_code)]
+// Writes cards with a random number of matches each, which are returned along
+// with the input.
+fn generate_cards(gen: &mut InputGenerator) -> (Vec<usize>, String) {
+    let cards = gen.size().max(1);
+    let list = |xs: &[usize]| {
+        let xs: Vec<String> = xs.iter().map(|x| format!("{:2}", x)).collect();
+        xs.join(" ")
+    };
+
+    let mut planted = Vec::new();
+    let lines: Vec<String> = (1..=cards)
+        .map(|id| {
+            let mut nums: Vec<usize> = (1..100).collect();
+            nums.shuffle(gen);
+            let winning = &nums[..5];
+            // Cards never win copies of cards past the end of the table
+            let matches = gen.gen_range(0..=(cards - id).min(5));
+            planted.push(matches);
+            let mut have = winning[..matches].to_vec();
+            have.extend_from_slice(&nums[5..13 - matches]);
+            have.shuffle(gen);
+            format!("Card {:3}: {} | {}", id, list(winning), list(&have))
+        })
+        .collect();
+    (planted, lines.join("\n"))
+}
+
+pub struct ScratchCard {
     id: usize,
     winning_numbers: Vec<usize>,
     numbers: Vec<usize>,
@@ -37,11 +98,10 @@ impl ScratchCard {
         a.intersection(&b).count()
     }
 
-    fn points(&self) -> usize {
-        let count = self.num_matches();
-        match count {
-            0 => 0,
-            _ => 1 << (count - 1),
+    fn points(&self) -> Option<usize> {
+        match self.num_matches() {
+            0 => Some(0),
+            count => 1usize.checked_shl(count as u32 - 1),
         }
     }
 }
@@ -79,11 +139,48 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "13")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "13")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "30")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "30")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let input = "Card 1: 1 2 | 1 2\nCard 2: 3 | 4";
+        let err = Solver::problem2(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "card 1 wins copies of 2 cards, but only 1 come after it"
+        );
+
+        let nums: Vec<String> = (1..=65).map(|x| x.to_string()).collect();
+        let input = format!("Card 7: {} | {}", nums.join(" "), nums.join(" "));
+        let err = Solver::problem1(&input).unwrap_err();
+        assert_eq!(err.to_string(), "card 7 is worth too many points");
+    }
+
+    #[test]
+    fn generate_test() {
+        for seed in 0..24 {
+            let (planted, input) = generate_cards(&mut InputGenerator::new(seed, 12));
+            let cards = Solver::parse(&input).unwrap();
+            let matches: Vec<usize> = cards.iter().map(|c| c.num_matches()).collect();
+            assert_eq!(matches, planted);
+
+            let points: usize = planted.iter().map(|&m| (1 << m) >> 1).sum();
+            assert_eq!(Solver::part1(&cards).unwrap(), Answer::from(points));
+            // Each card adds a copy of the next ones per copy of itself
+            let mut copies = vec![1; planted.len()];
+            for (i, &m) in planted.iter().enumerate() {
+                for j in i + 1..=i + m {
+                    copies[j] += copies[i];
+                }
+            }
+            let total: usize = copies.iter().sum();
+            assert_eq!(Solver::part2(&cards).unwrap(), Answer::from(total));
+        }
     }
 }
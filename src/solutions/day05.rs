@@ -2,30 +2,128 @@ use crate::solutions::prelude::*;
 
 use std::cmp;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let (seeds, maps) = parse!(input);
-    let seed_ranges: Vec<Range> = seeds
-        .iter()
-        .copied()
-        .map(|s| Range::new(s, s + 1).unwrap())
-        .collect();
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = (Vec<usize>, Vec<Map>);
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let (seeds, maps): Self::Parsed = parse!(input);
+        for m in &maps {
+            for (i, r) in m.ranges.iter().enumerate() {
+                if r.len == 0 {
+                    bail!(invalid_input!("{} map: range {} is empty", m.name, i + 1));
+                }
+                if r.src_start.checked_add(r.len).is_none() || r.dst_start.checked_add(r.len).is_none() {
+                    bail!(invalid_input!(
+                        "{} map: range {} runs past the largest number",
+                        m.name,
+                        i + 1
+                    ));
+                }
+            }
+        }
+        Ok((seeds, maps))
+    }
+
+    fn part1((seeds, maps): &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let seed_ranges = seeds
+            .iter()
+            .map(|&s| Range::new(s, s.checked_add(1)?))
+            .collect::<Option<Vec<Range>>>()
+            .ok_or_else(|| invalid_input!("seed {} is too big", usize::MAX))?;
 
-    Ok(min_location(seed_ranges, &maps).to_string())
+        Ok(min_location(seed_ranges, maps)?.into())
+    }
+
+    fn part2((seeds, maps): &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        Ok(min_location(seed_ranges(seeds)?, maps)?.into())
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        const CATEGORIES: &[&str] = &[
+            "seed",
+            "soil",
+            "fertilizer",
+            "water",
+            "light",
+            "temperature",
+            "humidity",
+            "location",
+        ];
+        // Small inputs get small numbers too, so that their seeds can be
+        // tried one by one
+        let max: usize = 1 << (gen.size() + 12).min(32);
+
+        let seeds: Vec<String> = (0..gen.gen_range(1..=5))
+            .flat_map(|_| [gen.gen_range(0..max), gen.gen_range(1..max >> 4)])
+            .map(|x| x.to_string())
+            .collect();
+        let mut blocks = vec![format!("seeds: {}", seeds.join(" "))];
+
+        let ranges = (gen.size() / CATEGORIES.len()).max(1);
+        for names in CATEGORIES.windows(2) {
+            // Sources are cut into ranges that do not overlap, destinations
+            // can be anywhere
+            let mut cuts = rand::seq::index::sample(gen, max, ranges * 2).into_vec();
+            cuts.sort_unstable();
+            let mut lines = vec![format!("{}-to-{} map:", names[0], names[1])];
+            for c in cuts.chunks_exact(2) {
+                let dst = gen.gen_range(0..max);
+                lines.push(format!("{} {} {}", dst, c[0], c[1] - c[0]));
+            }
+            blocks.push(lines.join("\n"));
+        }
+        Some(blocks.join("\n\n"))
+    }
+
+    fn reference((seeds, maps): &Self::Parsed, part: usize) -> Result<Option<Answer>, anyhow::Error> {
+        let seeds: Vec<usize> = match part {
+            1 => seeds.clone(),
+            _ => seed_ranges(seeds)?
+                .into_iter()
+                .flat_map(|r| r.start..r.end)
+                .collect(),
+        };
+        let location = |seed| maps.iter().fold(seed, |x, m| m.apply(x));
+        let ans = seeds
+            .into_iter()
+            .map(location)
+            .min()
+            .ok_or_else(|| invalid_input!("no seeds"))?;
+        Ok(Some(ans.into()))
+    }
 }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let (seeds, maps) = parse!(input);
-    let seed_ranges: Vec<Range> = seeds
+// Reads the seeds as pairs of a start and a length.
+fn seed_ranges(seeds: &[usize]) -> Result<Vec<Range>, anyhow::Error> {
+    if !seeds.len().is_multiple_of(2) {
+        bail!(invalid_input!("seeds are not in pairs, there are {} of them", seeds.len()));
+    }
+    seeds
         .chunks_exact(2)
-        .map(|xs| Range::new(xs[0], xs[0] + xs[1]).unwrap())
-        .collect();
-
-    Ok(min_location(seed_ranges, &maps).to_string())
+        .enumerate()
+        .map(|(i, xs)| {
+            let end = xs[0]
+                .checked_add(xs[1])
+                .ok_or_else(|| {
+                    invalid_input!("seed range {} runs past the largest number", i + 1)
+                })?;
+            let range = Range::new(xs[0], end)
+                .ok_or_else(|| invalid_input!("seed range {} is empty", i + 1))?;
+            Ok(range)
+        })
+        .collect()
 }
 
-fn min_location(seeds: Vec<Range>, maps: &[Map]) -> usize {
+fn min_location(seeds: Vec<Range>, maps: &[Map]) -> Result<usize, anyhow::Error> {
     let mapped_ranges = maps.iter().fold(seeds, |acc, m| m.apply_ranges(acc));
-    mapped_ranges.iter().map(|r| r.start).min().unwrap()
+    let min = mapped_ranges
+        .iter()
+        .map(|r| r.start)
+        .min()
+        .ok_or_else(|| invalid_input!("no seeds"))?;
+    Ok(min)
 }
 
 // A range from [start, end)
@@ -55,10 +153,12 @@ impl Range {
         )
     }
 
-    fn translate(&self, amt: isize) -> Self {
+    // Moves a range that starts at or after `from` so that `from` lands on
+    // `to`.
+    fn translate(&self, from: usize, to: usize) -> Self {
         Range {
-            start: (self.start as isize + amt) as usize,
-            end: (self.end as isize + amt) as usize,
+            start: self.start - from + to,
+            end: self.end - from + to,
         }
     }
 }
@@ -74,22 +174,26 @@ impl MapRange {
     // Returns the overlap with the mapping applied and the residual before and
     // after the overlap
     fn apply_range(&self, r: Range) -> Option<(Range, Option<Range>, Option<Range>)> {
-        let (b, o, a) = r.split(&Range::new(self.src_start, self.src_start + self.len).unwrap());
+        let (b, o, a) = r.split(&Range::new(self.src_start, self.src_start + self.len)?);
         let overlap = o?;
-        Some((
-            overlap.translate(self.dst_start as isize - self.src_start as isize),
-            b,
-            a,
-        ))
+        Some((overlap.translate(self.src_start, self.dst_start), b, a))
     }
 }
 
 #[derive(Clone, Debug)]
-struct Map {
+pub struct Map {
+    name: String,
     ranges: Vec<MapRange>,
 }
 
 impl Map {
+    fn apply(&self, x: usize) -> usize {
+        self.ranges
+            .iter()
+            .find(|r| (r.src_start..r.src_start + r.len).contains(&x))
+            .map_or(x, |r| r.dst_start + (x - r.src_start))
+    }
+
     fn apply_ranges(&self, r: Vec<Range>) -> Vec<Range> {
         let mut to_process = r;
         let mut tmp = Vec::new();
@@ -139,13 +243,15 @@ mod parser {
                 }
             });
 
-        let map_header = ws_line(tuple((
+        let map_header = ws_line(terminated(
             take_while(|c: char| !c.is_whitespace()),
-            space1,
-            tag("map:"),
-        )));
+            tuple((space1, tag("map:"))),
+        ));
 
-        let map = preceded(map_header, many1(ws_line(range))).map(|ranges| Map { ranges });
+        let map = tuple((map_header, many1(ws_line(range)))).map(|(name, ranges)| Map {
+            name: name.to_owned(),
+            ranges,
+        });
         let maps = separated_list1(multispace1, map);
         let parser = separated_pair(ws_line(seeds), multispace1, maps);
         ws_all_consuming(parser)(input)
@@ -192,11 +298,41 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "35")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "35")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "46")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "46")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let map = "seed-to-soil map:\n50 98 2\n52 50 0";
+        let err = Solver::parse(&format!("seeds: 1\n\n{}", map)).unwrap_err();
+        assert_eq!(err.to_string(), "seed-to-soil map: range 2 is empty");
+
+        let map = "soil-to-x map:\n18446744073709551615 0 2";
+        let err = Solver::parse(&format!("seeds: 1\n\n{}", map)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "soil-to-x map: range 1 runs past the largest number"
+        );
+
+        let map = "seed-to-soil map:\n50 98 2";
+        let err = Solver::problem2(&format!("seeds: 1 2 3\n\n{}", map)).unwrap_err();
+        assert_eq!(err.to_string(), "seeds are not in pairs, there are 3 of them");
+        let err = Solver::problem2(&format!("seeds: 1 2 3 0\n\n{}", map)).unwrap_err();
+        assert_eq!(err.to_string(), "seed range 2 is empty");
+        let input = format!("seeds: 18446744073709551615\n\n{}", map);
+        let err = Solver::problem1(&input).unwrap_err();
+        assert_eq!(err.to_string(), "seed 18446744073709551615 is too big");
+    }
+
+    #[test]
+    fn reference_test() {
+        let parsed = Solver::parse(EXAMPLE_INPUT).unwrap();
+        assert_eq!(Solver::reference(&parsed, 1).unwrap().unwrap(), "35");
+        assert_eq!(Solver::reference(&parsed, 2).unwrap().unwrap(), "46");
     }
 }
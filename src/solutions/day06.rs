@@ -1,29 +1,79 @@
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let races = parse!(input);
-    let ans: usize = races.iter().map(|r| r.num_winning_waits()).product();
-    Ok(ans.to_string())
-}
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = Vec<Race>;
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Ok(parse!(input))
+    }
+
+    fn part1(races: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let ans = races
+            .iter()
+            .try_fold(1usize, |acc, r| acc.checked_mul(r.num_winning_waits()))
+            .ok_or_else(|| invalid_input!("too many ways to win to multiply"))?;
+        Ok(ans.into())
+    }
+
+    fn part2(race_components: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        Ok(joined(race_components)?.num_winning_waits().into())
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        // Holding the button for half the race goes furthest
+        let best = |t: u64| (t / 2) * (t - t / 2);
+        let join = |xs: &[u64]| xs.iter().map(|x| x.to_string()).collect::<String>();
+        let list = |xs: &[u64]| xs.iter().map(|x| format!("{:>6}", x)).collect::<String>();
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let race_components = parse!(input);
+        let races = (gen.size() / 5).clamp(1, 4);
+        loop {
+            let times: Vec<u64> = (0..races).map(|_| gen.gen_range(7..100)).collect();
+            let records: Vec<u64> = times.iter().map(|&t| gen.gen_range(0..best(t))).collect();
 
-    let t_string: String = race_components.iter().map(|x| x.time.to_string()).collect();
-    let d_string: String = race_components
-        .iter()
-        .map(|x| x.record_distance.to_string())
-        .collect();
-    let race = Race {
-        time: usize::from_str_radix(&t_string, 10).unwrap(),
-        record_distance: usize::from_str_radix(&d_string, 10).unwrap(),
-    };
+            // The long race of part 2 must be winnable too, and small enough
+            // for its distance to be exact as a float
+            let time: u64 = join(&times).parse().unwrap();
+            let record: u64 = join(&records).parse().unwrap();
+            if record < best(time) && record < 1 << 53 {
+                return Some(format!(
+                    "Time:    {}\nDistance:{}",
+                    list(&times),
+                    list(&records)
+                ));
+            }
+        }
+    }
 
-    Ok(race.num_winning_waits().to_string())
+    fn reference(races: &Self::Parsed, part: usize) -> Result<Option<Answer>, anyhow::Error> {
+        let ans: usize = match part {
+            1 => races
+                .iter()
+                .try_fold(1usize, |acc, r| acc.checked_mul(r.num_winning_waits_slow()))
+                .ok_or_else(|| invalid_input!("too many ways to win to multiply"))?,
+            _ => joined(races)?.num_winning_waits_slow(),
+        };
+        Ok(Some(ans.into()))
+    }
+}
+
+// The single long race part 2 reads the races as.
+fn joined(races: &[Race]) -> Result<Race, anyhow::Error> {
+    let t_string: String = races.iter().map(|x| x.time.to_string()).collect();
+    let d_string: String = races.iter().map(|x| x.record_distance.to_string()).collect();
+    Ok(Race {
+        time: t_string
+            .parse()
+            .map_err(|_| invalid_input!("time {} of the joined race is too long", t_string))?,
+        record_distance: d_string
+            .parse()
+            .map_err(|_| invalid_input!("record {} of the joined race is too long", d_string))?,
+    })
 }
 
 #[derive(Clone, Copy, Debug)]
-struct Race {
+pub struct Race {
     time: usize,
     record_distance: usize,
 }
@@ -33,14 +83,28 @@ impl Race {
         // solution from https://www.wolframalpha.com/input?i=solve+for+w+in+w+*+%28t+-+w%29+%3D+d
         let t = self.time as f64;
         let d = self.record_distance as f64;
-        let shared = (t * t - 4.0 * d).sqrt();
+        let discriminant = t * t - 4.0 * d;
+        if discriminant < 0.0 {
+            return 0;
+        }
+        let shared = discriminant.sqrt();
         let low_root = 0.5 * (t - shared);
         let high_root = 0.5 * (t + shared);
 
-        let low = low_root.ceil() as usize + if low_root.fract() == 0.0 { 1 } else { 0 };
-        let high = high_root.floor() as usize - if high_root.fract() == 0.0 { 1 } else { 0 };
+        // Waits strictly between the roots beat the record
+        let low = low_root.floor() + 1.0;
+        let high = high_root.ceil() - 1.0;
+        if high < low {
+            return 0;
+        }
+        (high - low + 1.0) as usize
+    }
 
-        std::cmp::max(high - low + 1, 0)
+    // Tries every wait instead.
+    fn num_winning_waits_slow(&self) -> usize {
+        (0..=self.time)
+            .filter(|&w| w as u128 * (self.time - w) as u128 > self.record_distance as u128)
+            .count()
     }
 }
 
@@ -57,7 +121,7 @@ mod parser {
         let parser = tuple((ws_line(times), ws_line(distances))).map(|(times, distances)| {
             times
                 .into_iter()
-                .zip(distances.into_iter())
+                .zip(distances)
                 .map(|(t, d)| Race {
                     time: t,
                     record_distance: d,
@@ -71,6 +135,7 @@ mod parser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::generate;
 
     const EXAMPLE_INPUT: &str = "
         Time:      7  15   30
@@ -79,11 +144,57 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "288")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "288")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "71503")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "71503")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        assert_eq!(Solver::problem1("Time: 0 7\nDistance: 0 100").unwrap(), "0");
+
+        let input = "Time: 9999999999 9999999999\nDistance: 1 1";
+        let err = Solver::problem2(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "time 99999999999999999999 of the joined race is too long"
+        );
+
+        let input = "Time: 4294967296 4294967296 4294967296\nDistance: 1 1 1";
+        let err = Solver::problem1(input).unwrap_err();
+        assert_eq!(err.to_string(), "too many ways to win to multiply");
+    }
+
+    #[test]
+    fn reference_test() {
+        let races = Solver::parse(EXAMPLE_INPUT).unwrap();
+        assert_eq!(Solver::reference(&races, 1).unwrap().unwrap(), "288");
+        assert_eq!(Solver::reference(&races, 2).unwrap().unwrap(), "71503");
+
+        let input = format!("Time:{}\nDistance:{}", " 100".repeat(10), " 0".repeat(10));
+        let races = Solver::parse(&input).unwrap();
+        let err = Solver::reference(&races, 1).unwrap_err();
+        assert_eq!(err.to_string(), "too many ways to win to multiply");
+    }
+
+    #[test]
+    fn generate_test() {
+        for input in generate::samples::<Solver>() {
+            let races = Solver::parse(&input).unwrap();
+            let slow = Solver::reference(&races, 1).unwrap().unwrap();
+            assert_eq!(Solver::part1(&races).unwrap(), slow, "{}", input);
+
+            // The winning waits of the long race are the ones between `low`
+            // and `time - low`, and waiting one less than `low` loses
+            let race = joined(&races).unwrap();
+            let ways = Solver::part2(&races).unwrap().as_int().unwrap() as usize;
+            assert!(ways >= 1 && (race.time + 1 - ways).is_multiple_of(2), "{}", input);
+            let low = (race.time + 1 - ways) / 2;
+            assert!(low * (race.time - low) > race.record_distance, "{}", input);
+            assert!(low == 0 || (low - 1) * (race.time - low + 1) <= race.record_distance);
+        }
     }
 }
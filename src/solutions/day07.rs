@@ -1,36 +1,75 @@
+use ahash::AHashSet;
+
 use crate::solutions::prelude::*;
 
 const JACK: u8 = 11;
 const JOKER: u8 = 1;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let mut hands = parse!(input);
-    hands.sort_by_cached_key(|x| (x.typ(), x.cards));
-    let ans: usize = hands.iter().enumerate().map(|(i, h)| (i + 1) * h.bet).sum();
-    Ok(ans.to_string())
-}
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = Vec<Hand>;
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Ok(parse!(input))
+    }
+
+    fn part1(hands: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut hands = hands.clone();
+        hands.sort_by_cached_key(|x| (x.typ(), x.cards));
+        Ok(total_winnings(&hands)?.into())
+    }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let mut hands = parse!(input);
+    fn part2(hands: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut hands = hands.clone();
 
-    // Replace all jacks with jokers
-    for h in hands.iter_mut() {
-        for card in h.cards.iter_mut() {
-            if *card == JACK {
-                *card = JOKER
+        // Replace all jacks with jokers
+        for h in hands.iter_mut() {
+            for card in h.cards.iter_mut() {
+                if *card == JACK {
+                    *card = JOKER
+                }
             }
         }
+
+        hands.sort_by_cached_key(|x| (x.typ(), x.cards));
+        Ok(total_winnings(&hands)?.into())
     }
 
-    hands.sort_by_cached_key(|x| (x.typ(), x.cards));
-    let ans: usize = hands.iter().enumerate().map(|(i, h)| (i + 1) * h.bet).sum();
-    Ok(ans.to_string())
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        const CARDS: &[u8] = b"23456789TJQKA";
+
+        let mut seen = AHashSet::new();
+        let mut lines = Vec::new();
+        while lines.len() < gen.size().max(1) {
+            let hand: String = (0..5)
+                .map(|_| *CARDS.choose(gen).unwrap() as char)
+                .collect();
+            if seen.insert(hand.clone()) {
+                lines.push(format!("{} {}", hand, gen.gen_range(1..=1000)));
+            }
+        }
+        Some(lines.join("\n"))
+    }
 }
 
 type Card = u8;
 
+// Sums the bets of the hands weighted by their rank, weakest first.
+fn total_winnings(ranked: &[Hand]) -> Result<usize, anyhow::Error> {
+    ranked.iter().enumerate().try_fold(0usize, |acc, (i, h)| {
+        let winnings = (i + 1)
+            .checked_mul(h.bet)
+            .and_then(|x| acc.checked_add(x))
+            .ok_or_else(|| {
+                invalid_input!("winnings of the hand with a bet of {} are too big", h.bet)
+            })?;
+        Ok(winnings)
+    })
+}
+
 #[derive(Clone, Debug)]
-struct Hand {
+pub struct Hand {
     cards: [Card; 5],
     bet: usize,
 }
@@ -84,7 +123,7 @@ mod parser {
     fn five_cards(input: &str) -> IResult<&str, [Card; 5]> {
         let card = |input| {
             alt((
-                one_of("23456789").map(|c| c.to_digit(10).unwrap() as u8),
+                one_of("23456789").map(|c| c as u8 - b'0'),
                 value(10, tag("T")),
                 value(11, tag("J")),
                 value(12, tag("Q")),
@@ -113,11 +152,21 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "6440")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "6440")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "5905")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "5905")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let input = "32T3K 765\nT55J5 18446744073709551615";
+        let err = Solver::problem1(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "winnings of the hand with a bet of 18446744073709551615 are too big"
+        );
     }
 }
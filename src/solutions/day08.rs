@@ -1,35 +1,121 @@
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 
 use crate::{solutions::prelude::*, utils::lcm};
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let (dir, node_descs) = parse!(input);
-    let nodes = build_nodes(&node_descs)?;
+pub struct Solver;
 
-    let start = nodes
-        .iter()
-        .position(|x| x.id == "AAA")
-        .ok_or_else(|| anyhow!("no starting node found"))?;
-    let ans = moves_until_end(start, &nodes, &dir, |x| x == "ZZZ");
+impl Solution for Solver {
+    type Parsed = (Vec<Direction>, Vec<Node>);
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let (dir, node_descs) = parse!(input);
+        Ok((dir, build_nodes(&node_descs)?))
+    }
+
+    fn part1((dir, nodes): &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let start = nodes
+            .iter()
+            .position(|x| x.id == "AAA")
+            .ok_or_else(|| invalid_input!("no starting node found"))?;
+        let ans = moves_until_end(start, nodes, dir, |x| x == "ZZZ")?;
+
+        Ok(ans.into())
+    }
+
+    fn part2((dir, nodes): &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let starts = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.id.ends_with("A"));
+        let mut cycles_lcm = None;
+        for (s, _) in starts {
+            let len = moves_until_end(s, nodes, dir, |id| id.ends_with("Z"))? as u64;
+            cycles_lcm = Some(match cycles_lcm {
+                None => len,
+                Some(acc) => lcm(acc, len)
+                    .ok_or_else(|| invalid_input!("the ghosts take too many moves to line up"))?,
+            });
+        }
+        let cycles_lcm = cycles_lcm.ok_or_else(|| invalid_input!("no starting nodes found"))?;
+
+        Ok(cycles_lcm.into())
+    }
 
-    Ok(ans.to_string())
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        Some(generate_map(gen).1)
+    }
 }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let (dir, node_descs) = parse!(input);
-    let nodes = build_nodes(&node_descs)?;
+// Each ghost leaves its start for a loop of `len * p` nodes that ends at its
+// end node, where `len` is the number of directions and `p` a prime of its
+// own. Every node of a loop is always left by the same direction, so the
+// ghosts reach their ends every `len * p` moves and the least common multiple
+// answers part 2. The first ghost goes from AAA to ZZZ. Returns the answers
+// this plants along with the input.
+fn generate_map(gen: &mut InputGenerator) -> ([usize; 2], String) {
+    let mut primes = vec![3, 5, 7, 11, 13];
+    primes.shuffle(gen);
+    primes.truncate(gen.gen_range(2..=4));
+    let len = (gen.size() / primes.iter().sum::<usize>()).max(1);
+    let dirs: Vec<char> = (0..len)
+        .map(|_| if gen.gen() { 'L' } else { 'R' })
+        .collect();
 
-    let starts = nodes
+    let mut names = AHashSet::from(["AAA".to_owned(), "ZZZ".to_owned()]);
+    let mut name = |gen: &mut InputGenerator, last: std::ops::RangeInclusive<u8>| loop {
+        let name: String = [
+            gen.gen_range(b'A'..=b'Z'),
+            gen.gen_range(b'A'..=b'Z'),
+            gen.gen_range(last.clone()),
+        ]
+        .iter()
+        .map(|&c| c as char)
+        .collect();
+        if names.insert(name.clone()) {
+            return name;
+        }
+    };
+
+    // Each ghost's start followed by its loop
+    let paths: Vec<Vec<String>> = primes
         .iter()
         .enumerate()
-        .filter(|(_, x)| x.id.ends_with("A"));
-    let cycle_lengths =
-        starts.map(|(s, _)| moves_until_end(s, &nodes, &dir, |id| id.ends_with("Z")) as u64);
-    let cycles_lcm = cycle_lengths
-        .reduce(lcm)
-        .ok_or_else(|| anyhow!("no starting nodes found"))?;
-
-    Ok(cycles_lcm.to_string())
+        .map(|(i, p)| {
+            let loop_len = len * p;
+            let mut path = vec![match i {
+                0 => "AAA".to_owned(),
+                _ => name(gen, b'A'..=b'A'),
+            }];
+            for _ in 1..loop_len {
+                path.push(name(gen, b'B'..=b'Y'));
+            }
+            path.push(match i {
+                0 => "ZZZ".to_owned(),
+                _ => name(gen, b'Z'..=b'Z'),
+            });
+            path
+        })
+        .collect();
+    let all: Vec<&String> = paths.iter().flatten().collect();
+
+    let mut lines = Vec::new();
+    for path in &paths {
+        for (t, node) in path.iter().enumerate() {
+            // The end goes back to the first node of the loop
+            let next = path.get(t + 1).unwrap_or(&path[1]);
+            let other = all.choose(gen).unwrap();
+            let (left, right) = match dirs[t % len] {
+                'L' => (next, *other),
+                _ => (*other, next),
+            };
+            lines.push(format!("{} = ({}, {})", node, left, right));
+        }
+    }
+    lines.shuffle(gen);
+
+    let planted = [len * primes[0], len * primes.iter().product::<usize>()];
+    let dirs: String = dirs.into_iter().collect();
+    (planted, format!("{}\n\n{}", dirs, lines.join("\n")))
 }
 
 fn build_nodes(descs: &[NodeDesc]) -> Result<Vec<Node>, anyhow::Error> {
@@ -46,13 +132,13 @@ fn build_nodes(descs: &[NodeDesc]) -> Result<Vec<Node>, anyhow::Error> {
             let right_id = d.right.as_str();
 
             Ok(Node {
-                id: &d.id,
+                id: d.id.clone(),
                 left: *m
                     .get(left_id)
-                    .ok_or_else(|| anyhow!("node `{}` not declared", left_id))?,
+                    .ok_or_else(|| invalid_input!("node `{}` not declared", left_id))?,
                 right: *m
                     .get(right_id)
-                    .ok_or_else(|| anyhow!("node `{}` not declared", right_id))?,
+                    .ok_or_else(|| invalid_input!("node `{}` not declared", right_id))?,
             })
         })
         .collect()
@@ -63,22 +149,26 @@ fn moves_until_end(
     nodes: &[Node],
     directions: &[Direction],
     predicate: impl Fn(&str) -> bool,
-) -> usize {
+) -> Result<usize, anyhow::Error> {
+    // After visiting every node at every position in the directions, the
+    // walk only repeats itself
+    let limit = nodes.len() * directions.len();
     let mut cur = start;
-    let mut count = 0;
-    for &d in directions.iter().cycle() {
+    for (count, &d) in directions.iter().cycle().enumerate() {
         if predicate(&nodes[cur].id) {
+            return Ok(count);
+        }
+        if count > limit {
             break;
         }
         cur = nodes[cur].get(d);
-        count += 1;
     }
 
-    count
+    bail!(no_solution!("the path from node {} never reaches an end node", nodes[start].id))
 }
 
 #[derive(Clone, Copy, Debug)]
-enum Direction {
+pub enum Direction {
     Left,
     Right,
 }
@@ -91,13 +181,13 @@ struct NodeDesc {
 }
 
 #[derive(Clone, Debug)]
-struct Node<'a> {
-    id: &'a str,
+pub struct Node {
+    id: String,
     left: usize,
     right: usize,
 }
 
-impl<'a> Node<'a> {
+impl Node {
     fn get(&self, d: Direction) -> usize {
         match d {
             Direction::Left => self.left,
@@ -162,11 +252,39 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "6")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "6")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT2).unwrap(), "6")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT2).unwrap(), "6")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let input = "L\n\nAAA = (BBB, BBB)\nBBB = (AAA, AAA)\nZZZ = (ZZZ, ZZZ)";
+        let err = Solver::problem1(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the path from node AAA never reaches an end node"
+        );
+
+        let err = Solver::parse("L\n\nAAA = (BBB, ZZZ)\nZZZ = (ZZZ, ZZZ)").unwrap_err();
+        assert_eq!(err.to_string(), "node `BBB` not declared");
+
+        let err = Solver::problem2("L\n\nBBB = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)").unwrap_err();
+        assert_eq!(err.to_string(), "no starting nodes found");
+
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(u64::MAX, 2), None);
+    }
+
+    #[test]
+    fn generate_test() {
+        for seed in 0..24 {
+            let (planted, input) = generate_map(&mut InputGenerator::new(seed, 30));
+            assert_eq!(Solver::problem1(&input).unwrap(), Answer::from(planted[0]));
+            assert_eq!(Solver::problem2(&input).unwrap(), Answer::from(planted[1]));
+        }
     }
 }
@@ -1,51 +1,85 @@
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let hists = parse!(input);
-    let ans: i64 = hists
-        .into_iter()
-        .flat_map(|x| DerivativeIterator::new(x))
-        .sum();
-    Ok(ans.to_string())
-}
-
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let mut hists = parse!(input);
-    hists.iter_mut().for_each(|x| x.reverse());
-    let ans: i64 = hists
-        .into_iter()
-        .flat_map(|x| DerivativeIterator::new(x))
-        .sum();
-    Ok(ans.to_string())
-}
+pub struct Solver;
 
-struct DerivativeIterator {
-    nums: Vec<i64>,
-}
+impl Solution for Solver {
+    type Parsed = Vec<Vec<i64>>;
 
-impl DerivativeIterator {
-    fn new(nums: Vec<i64>) -> Self {
-        Self { nums }
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Ok(parse!(input))
     }
-}
 
-impl Iterator for DerivativeIterator {
-    type Item = i64;
+    fn part1(hists: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut ans: i64 = 0;
+        for (i, h) in hists.iter().enumerate() {
+            ans = ans
+                .checked_add(next_value(i + 1, h)?)
+                .ok_or_else(|| invalid_input!("sum of the next values is too big"))?;
+        }
+        Ok(ans.into())
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.nums.iter().all(|&x| x == 0) {
-            return None;
+    fn part2(hists: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut ans: i64 = 0;
+        for (i, h) in hists.iter().enumerate() {
+            let reversed: Vec<i64> = h.iter().rev().copied().collect();
+            ans = ans
+                .checked_add(next_value(i + 1, &reversed)?)
+                .ok_or_else(|| invalid_input!("sum of the previous values is too big"))?;
         }
+        Ok(ans.into())
+    }
 
-        let ret = self.nums.last().copied();
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        Some(generate_histories(gen).1)
+    }
+}
 
-        for i in 0..self.nums.len() - 1 {
-            self.nums[i] = self.nums[i + 1] - self.nums[i];
-        }
-        self.nums.pop();
+// Writes histories that follow polynomials, returned with the sums of the
+// values the polynomials take just after and just before each history.
+fn generate_histories(gen: &mut InputGenerator) -> ([i64; 2], String) {
+    // Also right for a negative `n`, as the running product is always a
+    // binomial itself
+    fn binomial(n: i64, k: i64) -> i64 {
+        (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+    }
 
-        ret
+    let mut planted = [0, 0];
+    let lines: Vec<String> = (0..gen.size().max(1))
+        .map(|_| {
+            // Sums of binomials have integer values, and the differences
+            // reach zero before the numbers run out
+            let len: i64 = gen.gen_range(5..=21);
+            let degree = gen.gen_range(0..=(len - 2).min(6));
+            let coeffs: Vec<i64> = (0..=degree).map(|_| gen.gen_range(-9..=9)).collect();
+            let value = |x| (0..).zip(&coeffs).map(|(k, c)| c * binomial(x, k)).sum::<i64>();
+            planted[0] += value(len);
+            planted[1] += value(-1);
+            let values: Vec<String> = (0..len).map(|x| value(x).to_string()).collect();
+            values.join(" ")
+        })
+        .collect();
+    (planted, lines.join("\n"))
+}
+
+// Extends a history by one value, by summing the last value of it and of
+// every row of differences down to the row of zeros.
+fn next_value(line: usize, history: &[i64]) -> Result<i64, anyhow::Error> {
+    let overflow = || invalid_input!("differences of line {} are too big", line);
+    let mut nums = history.to_vec();
+    let mut ans: i64 = 0;
+    while nums.iter().any(|&x| x != 0) {
+        if nums.len() == 1 {
+            bail!(invalid_input!("differences of line {} never reach a row of zeros", line));
+        }
+        ans = ans.checked_add(nums[nums.len() - 1]).ok_or_else(overflow)?;
+        nums = nums
+            .windows(2)
+            .map(|w| w[1].checked_sub(w[0]))
+            .collect::<Option<_>>()
+            .ok_or_else(overflow)?;
     }
+    Ok(ans)
 }
 
 mod parser {
@@ -69,11 +103,33 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "114")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "114")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "2")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "2")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::problem1("0 0 0\n1 2 4").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "differences of line 2 never reach a row of zeros"
+        );
+
+        let input = "3 3\n-9223372036854775808 9223372036854775807";
+        let err = Solver::problem2(input).unwrap_err();
+        assert_eq!(err.to_string(), "differences of line 2 are too big");
+    }
+
+    #[test]
+    fn generate_test() {
+        for seed in 0..24 {
+            let (planted, input) = generate_histories(&mut InputGenerator::new(seed, 12));
+            assert_eq!(Solver::problem1(&input).unwrap(), Answer::from(planted[0]));
+            assert_eq!(Solver::problem2(&input).unwrap(), Answer::from(planted[1]));
+        }
     }
 }
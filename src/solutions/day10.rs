@@ -5,41 +5,276 @@ use ahash::AHashSet;
 use crate::grid::{Direction, Grid, Point};
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
+pub struct Solver;
 
-    let (start, start_dirs) = find_start(&grid)?;
-    if start_dirs.len() != 2 {
-        bail!("start must connect to 2 points");
+impl Solution for Solver {
+    type Parsed = Grid<Cell>;
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Grid::try_new(parse!(input))
     }
 
-    let mut w1 = Walker::new(&grid, start, start_dirs[0]);
-    let mut w2 = Walker::new(&grid, start, start_dirs[1]);
-    w1.step()?;
-    w2.step()?;
+    fn part1(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let (start, start_dirs) = find_start(grid)?;
+        if start_dirs.len() != 2 {
+            bail!(invalid_input!("start must connect to 2 points"));
+        }
 
-    let mut count = 1;
-    while w1.loc != w2.loc {
+        let mut w1 = Walker::new(grid, start, start_dirs[0]);
+        let mut w2 = Walker::new(grid, start, start_dirs[1]);
         w1.step()?;
         w2.step()?;
-        count += 1;
+
+        let mut count = 1;
+        while w1.loc != w2.loc {
+            w1.step()?;
+            w2.step()?;
+            count += 1;
+        }
+
+        Ok(count.into())
+    }
+
+    fn part2(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let (_, inside) = enclosed(grid)?;
+        Ok(inside.len().into())
+    }
+
+    fn render(grid: &Self::Parsed, part: usize) -> Result<Option<Canvas>, anyhow::Error> {
+        let (loop_points, inside) = enclosed(grid)?;
+
+        let mut canvas = Canvas::new(grid, |c| match c {
+            Cell::Ground => Color::gray(32),
+            Cell::Start => Color::rgb(220, 40, 40),
+            _ => Color::gray(96),
+        });
+        canvas.overlay("loop", Color::rgb(240, 200, 60), 255, loop_points);
+        if part == 2 {
+            canvas.overlay("inside", Color::rgb(60, 180, 90), 255, inside);
+        }
+        Ok(Some(canvas))
+    }
+
+    fn animate(grid: &Self::Parsed, part: usize) -> Result<Option<Frames<'_>>, anyhow::Error> {
+        // Tracing the loop first means the walkers below cannot get lost.
+        let (loop_points, inside) = enclosed(grid)?;
+        let (start, start_dirs) = find_start(grid)?;
+
+        // Part 1 walks both ways around the loop until the walkers meet, part
+        // 2 walks all the way around and then shows what is inside.
+        let mut walkers = vec![Walker::new(grid, start, start_dirs[0])];
+        if part == 1 {
+            walkers.push(Walker::new(grid, start, start_dirs[1]));
+        }
+        let steps_per_frame = (loop_points.len() / 200).max(1);
+
+        let mut walked = AHashSet::from([start]);
+        let mut steps = 0;
+        let mut walking = true;
+        let mut showed_inside = false;
+        let frames = std::iter::from_fn(move || {
+            if !walking {
+                if part == 1 || showed_inside {
+                    return None;
+                }
+                showed_inside = true;
+                let caption = format!("{} tiles inside the loop", inside.len());
+                return Some(draw_loop(caption, grid, &loop_points, &walked, &[], &inside));
+            }
+
+            for _ in 0..steps_per_frame {
+                for w in &mut walkers {
+                    w.step().ok()?;
+                    walked.insert(w.loc);
+                }
+                steps += 1;
+
+                walking = match walkers.as_slice() {
+                    [w1, w2] => w1.loc != w2.loc,
+                    [w] => w.loc != start,
+                    _ => false,
+                };
+                if !walking {
+                    break;
+                }
+            }
+
+            let caption = if !walking && part == 1 {
+                format!("the walkers meet {} steps from the start", steps)
+            } else {
+                format!("step {}", steps)
+            };
+            let at: Vec<Point> = walkers.iter().map(|w| w.loc).collect();
+            let none = AHashSet::new();
+            Some(draw_loop(caption, grid, &loop_points, &walked, &at, &none))
+        });
+        Ok(Some(Box::new(frames)))
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        Some(generate_loop(gen).1)
+    }
+}
+
+// Grows a random tree over blocks of 2x2 tiles and walks around it, which
+// gives a loop through every tile of the tree's blocks. The blocks left out
+// of the tree are filled with junk pipes, and since the loop only ever runs
+// between neighbouring tiles none of them end up inside. Returns the answers
+// this plants along with the input.
+fn generate_loop(gen: &mut InputGenerator) -> ([usize; 2], String) {
+    const N: u8 = 1;
+    const E: u8 = 2;
+    const S: u8 = 4;
+    const W: u8 = 8;
+
+    // One tile of margin all around, so the outside touches the edge
+    let n = gen.size().max(4);
+    let blocks = (n - 2) / 2;
+
+    let mut in_tree = vec![vec![false; blocks]; blocks];
+    let mut tree_edges = Vec::new();
+    let first = (gen.gen_range(0..blocks), gen.gen_range(0..blocks));
+    in_tree[first.1][first.0] = true;
+    let mut tree = vec![first];
+    let mut frontier = vec![first];
+    let target = (blocks * blocks * 3 / 5).max(1);
+    while tree.len() < target && !frontier.is_empty() {
+        let i = gen.gen_range(0..frontier.len());
+        let (x, y) = frontier[i];
+        let neighbours: Vec<(usize, usize)> = [(0, 1), (2, 1), (1, 0), (1, 2)]
+            .iter()
+            .filter_map(|&(dx, dy)| {
+                let nx = (x + dx).checked_sub(1)?;
+                let ny = (y + dy).checked_sub(1)?;
+                (nx < blocks && ny < blocks && !in_tree[ny][nx]).then_some((nx, ny))
+            })
+            .collect();
+        let Some(&next) = neighbours.choose(gen) else {
+            frontier.swap_remove(i);
+            continue;
+        };
+        in_tree[next.1][next.0] = true;
+        tree_edges.push(((x, y), next));
+        tree.push(next);
+        frontier.push(next);
+    }
+
+    // The openings of each tile
+    let mut tiles = vec![vec![0u8; n]; n];
+    let link = |tiles: &mut Vec<Vec<u8>>, (x, y): (usize, usize), dir: u8, on: bool| {
+        let (other, back) = match dir {
+            N => ((x, y - 1), S),
+            E => ((x + 1, y), W),
+            S => ((x, y + 1), N),
+            _ => ((x - 1, y), E),
+        };
+        if on {
+            tiles[y][x] |= dir;
+            tiles[other.1][other.0] |= back;
+        } else {
+            tiles[y][x] &= !dir;
+            tiles[other.1][other.0] &= !back;
+        }
+    };
+    let corner = |(bx, by): (usize, usize), dx: usize, dy: usize| (1 + 2 * bx + dx, 1 + 2 * by + dy);
+
+    // Every block starts as a loop of its own
+    for &b in &tree {
+        link(&mut tiles, corner(b, 0, 0), E, true);
+        link(&mut tiles, corner(b, 1, 0), S, true);
+        link(&mut tiles, corner(b, 1, 1), W, true);
+        link(&mut tiles, corner(b, 0, 1), N, true);
+    }
+    // and the loops of neighbouring blocks in the tree are joined
+    for (a, b) in tree_edges {
+        let (a, b) = if (a.1, a.0) < (b.1, b.0) { (a, b) } else { (b, a) };
+        if a.1 == b.1 {
+            link(&mut tiles, corner(a, 1, 0), S, false);
+            link(&mut tiles, corner(b, 0, 0), S, false);
+            link(&mut tiles, corner(a, 1, 0), E, true);
+            link(&mut tiles, corner(a, 1, 1), E, true);
+        } else {
+            link(&mut tiles, corner(a, 0, 1), E, false);
+            link(&mut tiles, corner(b, 0, 0), E, false);
+            link(&mut tiles, corner(a, 0, 1), S, true);
+            link(&mut tiles, corner(a, 1, 1), S, true);
+        }
+    }
+
+    let loop_tiles: Vec<(usize, usize)> = (0..n)
+        .flat_map(|y| (0..n).map(move |x| (x, y)))
+        .filter(|&(x, y)| tiles[y][x] != 0)
+        .collect();
+    let start = *loop_tiles.choose(gen).unwrap();
+
+    let mut rows: Vec<Vec<char>> = tiles
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&t| match t {
+                    0 => *b"|-LJ7F....".choose(gen).unwrap() as char,
+                    _ if t == N | S => '|',
+                    _ if t == E | W => '-',
+                    _ if t == N | E => 'L',
+                    _ if t == N | W => 'J',
+                    _ if t == S | W => '7',
+                    _ => 'F',
+                })
+                .collect()
+        })
+        .collect();
+    rows[start.1][start.0] = 'S';
+    // Junk next to the start must not look connected to it
+    for (x, y) in [
+        (start.0 - 1, start.1),
+        (start.0 + 1, start.1),
+        (start.0, start.1 - 1),
+        (start.0, start.1 + 1),
+    ] {
+        if tiles[y][x] == 0 {
+            rows[y][x] = '.';
+        }
     }
 
-    Ok(count.to_string())
+    let rows: Vec<String> = rows.into_iter().map(|r| r.into_iter().collect()).collect();
+    ([loop_tiles.len() / 2, 0], rows.join("\n"))
 }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
+// Draws the walked part of the loop as pipes and the rest of it as dots.
+fn draw_loop(
+    caption: String,
+    grid: &Grid<Cell>,
+    loop_points: &AHashSet<Point>,
+    walked: &AHashSet<Point>,
+    walkers: &[Point],
+    inside: &AHashSet<Point>,
+) -> Frame {
+    Frame::grid(caption, grid, |p, c| {
+        if walkers.contains(&p) {
+            '@'
+        } else if walked.contains(&p) {
+            c.pipe()
+        } else if loop_points.contains(&p) {
+            '·'
+        } else if inside.contains(&p) {
+            'I'
+        } else {
+            ' '
+        }
+    })
+}
 
-    let (start, start_dirs) = find_start(&grid)?;
+// Finds the points of the loop and the points it encloses.
+fn enclosed(grid: &Grid<Cell>) -> anyhow::Result<(AHashSet<Point>, AHashSet<Point>)> {
+    let (start, start_dirs) = find_start(grid)?;
     if start_dirs.len() != 2 {
-        bail!("start must connect to 2 points");
+        bail!(invalid_input!("start must connect to 2 points"));
     }
 
     let mut loop_points = AHashSet::new();
     let mut left_side = Vec::new();
     let mut right_side = Vec::new();
-    let mut w = Walker::new(&grid, start, start_dirs[0]);
+    let mut w = Walker::new(grid, start, start_dirs[0]);
 
     loop {
         let prev_d = w.dir;
@@ -55,7 +290,7 @@ pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
         }
     }
 
-    let eval_side = |xs: Vec<Option<Point>>| -> Option<usize> {
+    let eval_side = |xs: Vec<Option<Point>>| -> Option<AHashSet<Point>> {
         let set: Option<AHashSet<Point>> = xs
             .into_iter()
             .filter(|p| match p {
@@ -63,24 +298,24 @@ pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
                 None => true,
             })
             .collect();
-        set.and_then(|s| fill(&s, &loop_points))
+        set.and_then(|s| fill(grid, &s, &loop_points))
     };
 
     let left_ans = eval_side(left_side);
     let right_ans = eval_side(right_side);
 
-    let ans = left_ans
+    let inside = left_ans
         .xor(right_ans)
-        .ok_or_else(|| anyhow!("one and only one side may be inside the loop"))?;
+        .ok_or_else(|| invalid_input!("one and only one side may be inside the loop"))?;
 
-    Ok(ans.to_string())
+    Ok((loop_points, inside))
 }
 
 fn find_start(grid: &Grid<Cell>) -> anyhow::Result<(Point, Vec<Direction>)> {
     let start = grid
         .iter_points()
         .find(|p| grid.get(*p) == Some(&Cell::Start))
-        .ok_or(anyhow!("no start in grid"))?;
+        .ok_or(invalid_input!("no start in grid"))?;
 
     let dirs = Direction::iter().filter(|&d| {
         start
@@ -117,7 +352,7 @@ impl<'a> Walker<'a> {
         self.loc = self
             .loc
             .next(self.dir)
-            .ok_or_else(|| anyhow!("walked to invalid point"))?;
+            .ok_or_else(|| invalid_input!("walked to invalid point"))?;
 
         // determine next direction
         self.dir = if self.loc == self.start_loc {
@@ -126,43 +361,54 @@ impl<'a> Walker<'a> {
             let cell = self
                 .grid
                 .get(self.loc)
-                .ok_or_else(|| anyhow!("walked off map"))?;
-
-            cell.directions()
-                .iter()
+                .ok_or_else(|| invalid_input!("walked off map"))?;
+
+            // A pipe that does not lead back the way we came would send the
+            // walk around in some other circle forever
+            let dirs = cell.directions();
+            if !dirs.contains(&self.dir.opposite()) {
+                bail!(invalid_input!(
+                    "pipe at ({}, {}) does not connect to the one before it",
+                    self.loc.x,
+                    self.loc.y
+                ));
+            }
+            dirs.iter()
                 .copied()
                 .find(|&d| d != self.dir.opposite())
-                .ok_or_else(|| anyhow!("point has no next direction"))?
+                .ok_or_else(|| internal!("point has no next direction"))?
         };
 
         Ok(())
     }
 }
 
-fn fill(start: &AHashSet<Point>, bounds: &AHashSet<Point>) -> Option<usize> {
+fn fill(
+    grid: &Grid<Cell>,
+    start: &AHashSet<Point>,
+    bounds: &AHashSet<Point>,
+) -> Option<AHashSet<Point>> {
     let mut frontier: VecDeque<Point> = start.iter().copied().collect();
     let mut seen: AHashSet<Point> = start.iter().copied().collect();
 
     while let Some(p) = frontier.pop_front() {
         for d in Direction::iter() {
-            let Some(n) = p.next(d) else {
+            let Some(n) = p.next(d).filter(|&n| grid.get(n).is_some()) else {
                 // if we move off edge, we are not within the loop
                 return None;
             };
 
-            if !bounds.contains(&n) {
-                if seen.insert(n) {
-                    frontier.push_back(n);
-                }
+            if !bounds.contains(&n) && seen.insert(n) {
+                frontier.push_back(n);
             }
         }
     }
 
-    Some(seen.len())
+    Some(seen)
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Cell {
+pub enum Cell {
     NS,
     EW,
     NE,
@@ -186,6 +432,19 @@ impl Cell {
             Self::Start => &[],
         }
     }
+
+    fn pipe(&self) -> char {
+        match self {
+            Self::NS => '│',
+            Self::EW => '─',
+            Self::NE => '└',
+            Self::NW => '┘',
+            Self::SW => '┐',
+            Self::SE => '┌',
+            Self::Ground => ' ',
+            Self::Start => 'S',
+        }
+    }
 }
 
 impl TryFrom<char> for Cell {
@@ -210,10 +469,10 @@ mod parser {
     use super::*;
     use crate::parser::prelude::*;
 
-    pub fn parse(input: &str) -> IResult<&str, Grid<Cell>> {
-        let cell = one_of("|-LJ7F.S").map(|x| Cell::try_from(x).unwrap());
+    pub fn parse(input: &str) -> IResult<&str, Vec<Vec<Cell>>> {
+        let cell = map_res(anychar, Cell::try_from);
         let row = many1(cell);
-        let graph = many1(ws_line(row)).map(Grid::new);
+        let graph = many1(ws_line(row));
         ws_all_consuming(graph)(input)
     }
 }
@@ -232,7 +491,7 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "8")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "8")
     }
 
     #[test]
@@ -248,6 +507,62 @@ mod tests {
         7-L-JL7||F7|L7F-7F7|
         L.L7LFJ|||||FJL7||LJ
         L7JLJL-JLJLJL--JLJ.L";
-        assert_eq!(problem2(input).unwrap(), "10")
+        assert_eq!(Solver::problem2(input).unwrap(), "10")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::problem1("S-7\n|.-\nL-J").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "pipe at (2, 1) does not connect to the one before it"
+        );
+
+        let err = Solver::parse("S-7\n|.|\nL-").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 3 of the grid has 2 cells, but row 1 has 3"
+        );
+
+        assert_eq!(Solver::problem2("...\n.S7\n.LJ").unwrap(), "0");
+    }
+
+    #[test]
+    fn render_test() {
+        let grid = Solver::parse(EXAMPLE_INPUT).unwrap();
+        let count = |part, color| {
+            let canvas = Solver::render(&grid, part).unwrap().unwrap();
+            canvas.pixels().into_iter().filter(|&c| c == color).count()
+        };
+
+        assert_eq!(count(1, Color::rgb(240, 200, 60)), 16);
+        assert_eq!(count(1, Color::rgb(60, 180, 90)), 0);
+        assert_eq!(count(2, Color::rgb(60, 180, 90)), 1);
+    }
+
+    #[test]
+    fn animate_test() {
+        let grid = Solver::parse(EXAMPLE_INPUT).unwrap();
+
+        let frames: Vec<Frame> = Solver::animate(&grid, 1).unwrap().unwrap().collect();
+        assert_eq!(frames.len(), 8);
+        assert_eq!(frames[0].caption, "step 1");
+        assert_eq!(frames[0].lines[2], "S@ ··");
+        assert_eq!(frames[0].lines[3], "@····");
+        assert_eq!(frames[7].caption, "the walkers meet 8 steps from the start");
+
+        let frames: Vec<Frame> = Solver::animate(&grid, 2).unwrap().unwrap().collect();
+        assert_eq!(frames.len(), 17);
+        assert_eq!(frames[16].caption, "1 tiles inside the loop");
+        assert_eq!(frames[16].lines[2], "S┘I└┐");
+    }
+
+    #[test]
+    fn generate_test() {
+        for seed in 0..24 {
+            let (planted, input) = generate_loop(&mut InputGenerator::new(seed, 12));
+            assert_eq!(Solver::problem1(&input).unwrap(), Answer::from(planted[0]));
+            assert_eq!(Solver::problem2(&input).unwrap(), Answer::from(planted[1]));
+        }
     }
 }
@@ -1,23 +1,72 @@
-use crate::grid::Point;
+use crate::grid::{Grid, Point};
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let galaxies = parse!(input);
-    let expanded = expand(&galaxies, 2);
-    let ans = sum_of_distances(&expanded);
-    Ok(ans.to_string())
-}
+pub struct Solver;
+
+const PART1_FACTOR: Param<usize> = Param::new("part1-factor", 2, "how many times larger empty space gets in part 1");
+const PART2_FACTOR: Param<usize> = Param::new(
+    "part2-factor",
+    1_000_000,
+    "how many times larger empty space gets in part 2",
+);
+
+impl Solution for Solver {
+    type Parsed = Vec<Point>;
+
+    const PARAMS: &'static [&'static dyn ParamInfo] = &[&PART1_FACTOR, &PART2_FACTOR];
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let galaxies = parse!(input);
-    let expanded = expand(&galaxies, 1_000_000);
-    let ans = sum_of_distances(&expanded);
-    Ok(ans.to_string())
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let grid = Grid::try_new(parse!(input))?;
+        Ok(grid.iter_points().filter(|&l| grid.get(l) == Some(&true)).collect())
+    }
+
+    fn part1(galaxies: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let expanded = expand(galaxies, PART1_FACTOR.get())?;
+        let ans = sum_of_distances(&expanded)?;
+        Ok(ans.into())
+    }
+
+    fn part2(galaxies: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let expanded = expand(galaxies, PART2_FACTOR.get())?;
+        let ans = sum_of_distances(&expanded)?;
+        Ok(ans.into())
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        let n = gen.size().max(2);
+        // Some rows and columns are left empty to expand
+        let empty_rows: Vec<bool> = (0..n).map(|_| gen.gen_bool(0.15)).collect();
+        let empty_cols: Vec<bool> = (0..n).map(|_| gen.gen_bool(0.15)).collect();
+
+        let mut rows: Vec<Vec<char>> = (0..n)
+            .map(|y| {
+                (0..n)
+                    .map(|x| {
+                        let empty = empty_rows[y] || empty_cols[x];
+                        if !empty && gen.gen_bool(0.1) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        // Distances need at least two galaxies
+        rows[0][0] = '#';
+        rows[n - 1][n - 1] = '#';
+
+        let rows: Vec<String> = rows.into_iter().map(|r| r.into_iter().collect()).collect();
+        Some(rows.join("\n"))
+    }
 }
 
-fn expand(locs: &[Point], factor: usize) -> Vec<Point> {
-    assert!(factor != 0);
+fn expand(locs: &[Point], factor: usize) -> Result<Vec<Point>, anyhow::Error> {
+    if factor == 0 {
+        bail!(invalid_input!("the expansion factor must be at least 1"));
+    }
     let f = factor - 1;
+    let too_big = || invalid_input!("the universe expands too far for a factor of {}", factor);
 
     let mut points = locs.to_vec();
 
@@ -26,9 +75,13 @@ fn expand(locs: &[Point], factor: usize) -> Vec<Point> {
     let mut last = 0;
     let mut expansion = 0;
     for p in points.iter_mut() {
-        expansion += (p.x - last).saturating_sub(1) * f;
+        expansion = (p.x - last)
+            .saturating_sub(1)
+            .checked_mul(f)
+            .and_then(|x| x.checked_add(expansion))
+            .ok_or_else(too_big)?;
         last = p.x;
-        p.x += expansion;
+        p.x = p.x.checked_add(expansion).ok_or_else(too_big)?;
     }
 
     // expand y axis
@@ -36,42 +89,49 @@ fn expand(locs: &[Point], factor: usize) -> Vec<Point> {
     let mut last = 0;
     let mut expansion = 0;
     for p in points.iter_mut() {
-        expansion += (p.y - last).saturating_sub(1) * f;
+        expansion = (p.y - last)
+            .saturating_sub(1)
+            .checked_mul(f)
+            .and_then(|x| x.checked_add(expansion))
+            .ok_or_else(too_big)?;
         last = p.y;
-        p.y += expansion;
+        p.y = p.y.checked_add(expansion).ok_or_else(too_big)?;
     }
 
-    points
+    Ok(points)
 }
 
-fn sum_of_distances(points: &[Point]) -> usize {
-    let pairs = points
+fn sum_of_distances(points: &[Point]) -> Result<usize, anyhow::Error> {
+    let mut pairs = points
         .iter()
         .enumerate()
         .flat_map(|(i, a)| std::iter::repeat(a).zip(&points[i + 1..]));
 
-    pairs
-        .map(|(a, b)| a.x.abs_diff(b.x) + a.y.abs_diff(b.y))
-        .sum()
+    let sum = pairs
+        .try_fold(0usize, |sum, (a, b)| {
+            a.x.abs_diff(b.x)
+                .checked_add(a.y.abs_diff(b.y))
+                .and_then(|d| sum.checked_add(d))
+        })
+        .ok_or_else(|| invalid_input!("sum of the distances is too big"))?;
+    Ok(sum)
 }
 
 mod parser {
-    use super::*;
-    use crate::grid::Grid;
     use crate::parser::prelude::*;
 
-    pub fn parse(input: &str) -> IResult<&str, Vec<Point>> {
+    pub fn parse(input: &str) -> IResult<&str, Vec<Vec<bool>>> {
         let cell = alt((value(true, char('#')), value(false, char('.'))));
         let row = many1(cell);
-        let grid = many1(ws_line(row)).map(|g| Grid::new(g));
-        let galaxies = grid.map(|g| g.iter_points().filter(|&l| *g.get(l).unwrap()).collect());
-        ws_all_consuming(galaxies)(input)
+        ws_all_consuming(many1(ws_line(row)))(input)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::generate;
+    use crate::params;
 
     const EXAMPLE_INPUT: &str = "
     ...#......
@@ -87,11 +147,55 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "374")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "374")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "82000210")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "82000210")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::parse("#..\n.#").unwrap_err();
+        assert_eq!(err.to_string(), "row 2 of the grid has 2 cells, but row 1 has 3");
+
+        let args = ["part2-factor=18446744073709551615".to_owned()];
+        let overrides = params::Overrides::parse(Solver::PARAMS, &args).unwrap();
+        params::with(&overrides, || {
+            let err = Solver::problem2("#..#").unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "the universe expands too far for a factor of 18446744073709551615"
+            );
+        });
+    }
+
+    #[test]
+    fn factor_test() {
+        let args = ["part1-factor=10".to_owned(), "part2-factor=100".to_owned()];
+        let overrides = params::Overrides::parse(Solver::PARAMS, &args).unwrap();
+        params::with(&overrides, || {
+            assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "1030");
+            assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "8410");
+        });
+    }
+
+    #[test]
+    fn generate_test() {
+        // Each empty row or column between two galaxies adds `factor - 1` to
+        // their distance, so the sum grows linearly with the factor
+        let args = ["part1-factor=1".to_owned()];
+        let overrides = params::Overrides::parse(Solver::PARAMS, &args).unwrap();
+        for input in generate::samples::<Solver>() {
+            let base = params::with(&overrides, || Solver::problem1(&input))
+                .unwrap()
+                .as_int()
+                .unwrap();
+            let one = Solver::problem1(&input).unwrap().as_int().unwrap();
+            let two = Solver::problem2(&input).unwrap().as_int().unwrap();
+            let (f1, f2) = (PART1_FACTOR.get() as i128, PART2_FACTOR.get() as i128);
+            assert_eq!((two - base) * (f1 - 1), (one - base) * (f2 - 1), "{}", input);
+        }
     }
 }
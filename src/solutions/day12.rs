@@ -1,29 +1,106 @@
+use std::collections::HashMap;
+
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let rows = parse!(input);
-    let ans: usize = rows.iter().map(num_arrangements).sum();
-    Ok(ans.to_string())
+pub struct Solver;
+
+const COPIES: Param<usize> = Param::new("copies", 5, "how many times rows are unfolded in part 2");
+
+impl Solution for Solver {
+    type Parsed = Vec<Row>;
+
+    const PARAMS: &'static [&'static dyn ParamInfo] = &[&COPIES];
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let rows: Vec<Row> = parse!(input);
+        for (i, r) in rows.iter().enumerate() {
+            if r.groups.contains(&0) {
+                bail!(invalid_input!("row {} has a group of 0 damaged springs", i + 1));
+            }
+        }
+        Ok(rows)
+    }
+
+    fn part1(rows: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        Ok(total_arrangements(rows)?.into())
+    }
+
+    fn part2(rows: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        Ok(total_arrangements(&unfold(rows)?)?.into())
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        let lines: Vec<String> = (0..gen.size().max(1))
+            .map(|_| {
+                // Lay the groups out, then hide some of the springs, so every
+                // row has at least one arrangement
+                let groups: Vec<usize> = (0..gen.gen_range(1..=5))
+                    .map(|_| gen.gen_range(1..=4))
+                    .collect();
+                let mut springs = String::new();
+                for (i, &g) in groups.iter().enumerate() {
+                    let gap = gen.gen_range(usize::from(i > 0)..=3);
+                    springs.push_str(&".".repeat(gap));
+                    springs.push_str(&"#".repeat(g));
+                }
+                let tail = gen.gen_range(0..=2);
+                springs.push_str(&".".repeat(tail));
+
+                let cells: String = springs
+                    .chars()
+                    .map(|c| if gen.gen_bool(0.5) { '?' } else { c })
+                    .collect();
+                let groups: Vec<String> = groups.iter().map(|g| g.to_string()).collect();
+                format!("{} {}", cells, groups.join(","))
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    fn reference(rows: &Self::Parsed, part: usize) -> Result<Option<Answer>, anyhow::Error> {
+        let rows = match part {
+            1 => rows.clone(),
+            _ => unfold(rows)?,
+        };
+        let ans: usize = rows.iter().map(num_arrangements_slow).sum();
+        Ok(Some(ans.into()))
+    }
 }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let mut rows = parse!(input);
+fn unfold(rows: &[Row]) -> Result<Vec<Row>, anyhow::Error> {
+    let copies = COPIES.get();
+    if copies == 0 {
+        bail!(invalid_input!("rows must be unfolded into at least 1 copy"));
+    }
+    let mut rows = rows.to_vec();
 
-    for r in rows.iter_mut() {
+    for (i, r) in rows.iter_mut().enumerate() {
+        if (r.cells.len() + 1).checked_mul(copies).is_none() {
+            bail!(invalid_input!("row {} is too long to unfold {} times", i + 1, copies));
+        }
         let cell_len = r.cells.len();
         let group_len = r.groups.len();
-        for _ in 0..4 {
+        for _ in 1..copies {
             r.cells.push(Cell::Unknown);
             r.cells.extend_from_within(..cell_len);
             r.groups.extend_from_within(..group_len);
         }
     }
+    Ok(rows)
+}
 
-    let ans: usize = rows.iter().map(num_arrangements).sum();
-    Ok(ans.to_string())
+fn total_arrangements(rows: &[Row]) -> Result<usize, anyhow::Error> {
+    let mut ans: usize = 0;
+    for (i, r) in rows.iter().enumerate() {
+        ans = num_arrangements(r)
+            .and_then(|n| ans.checked_add(n))
+            .ok_or_else(|| invalid_input!("too many arrangements, at row {}", i + 1))?;
+    }
+    Ok(ans)
 }
 
-fn num_arrangements(r: &Row) -> usize {
+// None if there are too many arrangements to count in a usize.
+fn num_arrangements(r: &Row) -> Option<usize> {
     let mut memo = vec![None; r.cells.len() * (r.groups.len() + 1)];
     num_arrangement_rec(r, 0, 0, &mut memo)
 }
@@ -33,22 +110,22 @@ fn num_arrangement_rec(
     mut pos: usize,
     group_index: usize,
     memo: &mut Vec<Option<usize>>,
-) -> usize {
+) -> Option<usize> {
     while r.cells.get(pos) == Some(&Cell::Operational) {
         pos += 1;
     }
 
     let Some(&c) = r.cells.get(pos) else {
-        return if group_index == r.groups.len() { 1 } else { 0 };
+        return Some(if group_index == r.groups.len() { 1 } else { 0 });
     };
 
     let memo_index = pos * (r.groups.len() + 1) + group_index;
     if let Some(v) = memo[memo_index] {
-        return v;
+        return Some(v);
     }
 
     let assume_operational = if c == Cell::Unknown {
-        num_arrangement_rec(r, pos + 1, group_index, memo)
+        num_arrangement_rec(r, pos + 1, group_index, memo)?
     } else {
         0
     };
@@ -57,7 +134,7 @@ fn num_arrangement_rec(
         let Some(&group_size) = r.groups.get(group_index) else {
             break 'block 0;
         };
-        let Some(next_springs) = r.cells.get(pos..pos + group_size) else {
+        let Some(next_springs) = r.cells.get(pos..pos.saturating_add(group_size)) else {
             break 'block 0;
         };
 
@@ -66,20 +143,50 @@ fn num_arrangement_rec(
             if r.cells.get(pos) == Some(&Cell::Damaged) {
                 0
             } else {
-                num_arrangement_rec(r, pos + 1, group_index + 1, memo)
+                num_arrangement_rec(r, pos + 1, group_index + 1, memo)?
             }
         } else {
             0
         }
     };
 
-    let ret = assume_operational + assume_damaged;
+    let ret = assume_operational.checked_add(assume_damaged)?;
     memo[memo_index] = Some(ret);
-    ret
+    Some(ret)
+}
+
+// Walks the springs one at a time, counting the ways to get to each number of
+// finished groups with a run of damaged springs of each length going.
+fn num_arrangements_slow(r: &Row) -> usize {
+    let mut ways = HashMap::from([((0, 0), 1)]);
+    for &cell in &r.cells {
+        let mut next = HashMap::new();
+        for (&(done, run), &n) in &ways {
+            if cell != Cell::Damaged {
+                if run == 0 {
+                    *next.entry((done, 0)).or_default() += n;
+                } else if r.groups.get(done) == Some(&run) {
+                    *next.entry((done + 1, 0)).or_default() += n;
+                }
+            }
+            if cell != Cell::Operational && r.groups.get(done).is_some_and(|&g| run < g) {
+                *next.entry((done, run + 1)).or_default() += n;
+            }
+        }
+        ways = next;
+    }
+
+    ways.iter()
+        .filter(|&(&(done, run), _)| {
+            (done, run) == (r.groups.len(), 0)
+                || (done + 1 == r.groups.len() && r.groups[done] == run)
+        })
+        .map(|(_, &n)| n)
+        .sum()
 }
 
 #[derive(Clone, Debug)]
-struct Row {
+pub struct Row {
     cells: Vec<Cell>,
     groups: Vec<usize>,
 }
@@ -125,11 +232,32 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "21")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "21")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "525152")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "525152")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::parse("#.# 1,1\n??? 1,0").unwrap_err();
+        assert_eq!(err.to_string(), "row 2 has a group of 0 damaged springs");
+
+        assert_eq!(Solver::problem1("#?# 18446744073709551615").unwrap(), "0");
+
+        let input = "?".repeat(200) + " 1";
+        assert_eq!(Solver::problem1(&input).unwrap(), "200");
+        let input = ["?".repeat(300), "1,".repeat(59) + "1"].join(" ");
+        let err = Solver::problem1(&input).unwrap_err();
+        assert_eq!(err.to_string(), "too many arrangements, at row 1");
+    }
+
+    #[test]
+    fn reference_test() {
+        let rows = Solver::parse(EXAMPLE_INPUT).unwrap();
+        assert_eq!(Solver::reference(&rows, 1).unwrap().unwrap(), "21");
+        assert_eq!(Solver::reference(&rows, 2).unwrap().unwrap(), "525152");
     }
 }
@@ -1,32 +1,114 @@
 use crate::grid::{Grid, Point};
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let grids = parse!(input);
-    let ans: usize = grids
-        .iter()
-        .map(|g| {
-            let cg = CompressedGrid::try_from(g).unwrap();
-            find_mirror(&cg.rows, 0) * 100 + find_mirror(&cg.columns, 0)
-        })
-        .sum();
-    Ok(ans.to_string())
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = Vec<CompressedGrid>;
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let patterns: Vec<Vec<Vec<bool>>> = parse!(input);
+        let mut grids = Vec::new();
+        for (i, cells) in patterns.into_iter().enumerate() {
+            let grid = Grid::try_new(cells)
+                .map_err(|e| invalid_input!("pattern {}: {}", i + 1, e))?;
+            let grid = CompressedGrid::try_from(&grid)
+                .map_err(|e| invalid_input!("pattern {}: {}", i + 1, e))?;
+            grids.push(grid);
+        }
+        Ok(grids)
+    }
+
+    fn part1(grids: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let ans: usize = grids
+            .iter()
+            .map(|cg| find_mirror(&cg.rows, 0) * 100 + find_mirror(&cg.columns, 0))
+            .sum();
+        Ok(ans.into())
+    }
+
+    fn part2(grids: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let ans: usize = grids
+            .iter()
+            .map(|cg| find_mirror(&cg.rows, 1) * 100 + find_mirror(&cg.columns, 1))
+            .sum();
+        Ok(ans.into())
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        let patterns: Vec<String> = (0..(gen.size() / 12).max(1))
+            .map(|_| {
+                let (grid, _) = generate_pattern(gen);
+                let rows: Vec<String> = grid
+                    .cells
+                    .iter()
+                    .map(|r| r.iter().map(|&c| if c { '#' } else { '.' }).collect())
+                    .collect();
+                rows.join("\n")
+            })
+            .collect();
+        Some(patterns.join("\n\n"))
+    }
 }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let grids = parse!(input);
-    let ans: usize = grids
-        .iter()
-        .map(|g| {
-            let cg = CompressedGrid::try_from(g).unwrap();
-            find_mirror(&cg.rows, 1) * 100 + find_mirror(&cg.columns, 1)
-        })
-        .sum();
-    Ok(ans.to_string())
+// Makes a pattern that mirrors between two rows and, but for one smudge,
+// between two columns. The smudge is in a row the row mirror does not reach,
+// so it leaves that mirror alone. Returns the pattern with the summaries of
+// the mirrors planted for part 1 and 2.
+fn generate_pattern(gen: &mut InputGenerator) -> (Grid<bool>, [usize; 2]) {
+    loop {
+        let cols = gen.gen_range(5..=15);
+        let rows = gen.gen_range(5..=15);
+        let row_mirror = gen.gen_range(1..rows);
+        let col_mirror = gen.gen_range(1..cols);
+        if row_mirror * 2 == rows {
+            continue;
+        }
+
+        let mut cells: Vec<Vec<bool>> = (0..rows)
+            .map(|_| (0..cols).map(|_| gen.gen()).collect())
+            .collect();
+        for y in row_mirror..rows.min(row_mirror * 2) {
+            cells[y] = cells[row_mirror * 2 - 1 - y].clone();
+        }
+        for row in cells.iter_mut() {
+            for x in col_mirror..cols.min(col_mirror * 2) {
+                row[x] = row[col_mirror * 2 - 1 - x];
+            }
+        }
+
+        let reach = row_mirror.min(rows - row_mirror);
+        let y = match row_mirror * 2 < rows {
+            true => gen.gen_range(row_mirror + reach..rows),
+            false => gen.gen_range(0..row_mirror - reach),
+        };
+        let x = gen.gen_range(col_mirror.saturating_sub(cols - col_mirror)..cols.min(col_mirror * 2));
+        cells[y][x] = !cells[y][x];
+
+        let mut grid = Grid::new(cells);
+        let mut planted = [row_mirror * 100, col_mirror];
+        if gen.gen() {
+            planted = [row_mirror, col_mirror * 100];
+            grid = Grid::new(
+                (0..cols)
+                    .map(|x| (0..rows).map(|y| grid.cells[y][x]).collect())
+                    .collect(),
+            );
+        }
+
+        // Random cells can mirror in more places, which would be ambiguous
+        let cg = CompressedGrid::try_from(&grid).unwrap();
+        let count = |lines: &[u32], diff| mirrors(lines, diff).count();
+        if count(&cg.rows, 0) + count(&cg.columns, 0) == 1
+            && count(&cg.rows, 1) + count(&cg.columns, 1) == 1
+        {
+            return (grid, planted);
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
-struct CompressedGrid {
+pub struct CompressedGrid {
     rows: Vec<u32>,
     columns: Vec<u32>,
 }
@@ -37,7 +119,11 @@ impl TryFrom<&Grid<bool>> for CompressedGrid {
     fn try_from(grid: &Grid<bool>) -> Result<Self, Self::Error> {
         let (col_len, row_len) = grid.size();
         if col_len > 32 || row_len > 32 {
-            bail!("grid too big to be compressed");
+            bail!(invalid_input!(
+                "{}x{} grid is too big to be compressed, the most is 32x32",
+                col_len,
+                row_len
+            ));
         }
 
         let rows = grid
@@ -59,8 +145,12 @@ impl TryFrom<&Grid<bool>> for CompressedGrid {
 }
 
 fn find_mirror(lines: &[u32], target_diff: u32) -> usize {
-    (1..lines.len())
-        .find(|&mirror| {
+    mirrors(lines, target_diff).next().unwrap_or(0)
+}
+
+// Every place the lines mirror with exactly `target_diff` cells different.
+fn mirrors(lines: &[u32], target_diff: u32) -> impl Iterator<Item = usize> + '_ {
+    (1..lines.len()).filter(move |&mirror| {
             let diffs: u32 = lines[..mirror]
                 .iter()
                 .rev()
@@ -69,19 +159,17 @@ fn find_mirror(lines: &[u32], target_diff: u32) -> usize {
                 .sum();
             diffs == target_diff
         })
-        .unwrap_or(0)
 }
 
 mod parser {
     use nom::character::complete::multispace1;
 
-    use super::*;
     use crate::parser::prelude::*;
 
-    pub fn parse(input: &str) -> IResult<&str, Vec<Grid<bool>>> {
+    pub fn parse(input: &str) -> IResult<&str, Vec<Vec<Vec<bool>>>> {
         let cell = alt((value(false, char('.')), value(true, char('#'))));
         let row = ws_line(many1(cell));
-        let grid = many1(row).map(|rows| Grid::new(rows));
+        let grid = many1(row);
         let parser = separated_list1(multispace1, grid);
         ws_all_consuming(parser)(input)
     }
@@ -111,11 +199,37 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "405")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "405")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "400")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "400")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::parse("#.#\n.#.\n\n##\n#\n##").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "pattern 2: row 2 of the grid has 1 cells, but row 1 has 2"
+        );
+
+        let err = Solver::parse(&format!("##\n##\n\n{}", "#".repeat(33))).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "pattern 2: 33x1 grid is too big to be compressed, the most is 32x32"
+        );
+    }
+
+    #[test]
+    fn generate_test() {
+        // Each part finds the mirror planted for it and no other
+        for seed in 0..24 {
+            let (grid, planted) = generate_pattern(&mut InputGenerator::new(seed, 12));
+            let patterns = vec![CompressedGrid::try_from(&grid).unwrap()];
+            assert_eq!(Solver::part1(&patterns).unwrap(), Answer::from(planted[0]));
+            assert_eq!(Solver::part2(&patterns).unwrap(), Answer::from(planted[1]));
+        }
     }
 }
@@ -7,47 +7,187 @@ use ahash::AHashMap;
 use crate::grid::{Grid, Point};
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
+pub struct Solver;
 
-    let mut rg = RotatingGrid::from(&grid);
-    rg.slide_up(0);
+const CYCLES: Param<usize> = Param::new("cycles", 1_000_000_000, "spin cycles to run in part 2");
 
-    Ok(rg.score().to_string())
-}
+// The reference solution runs every cycle, which takes too long for the
+// default number of them.
+const MAX_REFERENCE_CYCLES: usize = 100_000;
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
+impl Solution for Solver {
+    type Parsed = RotatingGrid;
 
-    let mut rg = RotatingGrid::from(&grid);
-    let mut history = Vec::new();
-    let mut seen_locations = AHashMap::new();
+    const PARAMS: &'static [&'static dyn ParamInfo] = &[&CYCLES];
 
-    let mut i: usize = 0;
-    let (repeat_start, repeat_len) = 'l: loop {
-        let c = Rc::new(rg.round_rocks.clone());
-        history.push(c.clone());
-        match seen_locations.entry(c) {
-            Entry::Occupied(e) => {
-                break 'l (*e.get(), i - *e.get());
-            }
-            Entry::Vacant(e) => {
-                e.insert(i);
-            }
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Ok(RotatingGrid::from(&Grid::try_new(parse!(input))?))
+    }
+
+    fn part1(rg: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut rg = rg.clone();
+        rg.slide_up(0);
+
+        Ok(rg.score().into())
+    }
+
+    fn part2(rg: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut rg = rg.clone();
+        let mut history = Vec::new();
+        let mut seen_locations = AHashMap::new();
+
+        let mut i: usize = 0;
+        let (repeat_start, repeat_len) = 'l: loop {
+            let c = Rc::new(rg.round_rocks.clone());
+            history.push(c.clone());
+            match seen_locations.entry(c) {
+                Entry::Occupied(e) => {
+                    break 'l (*e.get(), i - *e.get());
+                }
+                Entry::Vacant(e) => {
+                    e.insert(i);
+                }
+            };
+
+            rg.cycle();
+
+            i += 1;
+        };
+        trace::count("cycles", i as u64);
+        trace::count("period", repeat_len as u64);
+
+        rg.round_rocks = (*history[same_cycle(CYCLES.get(), repeat_start, repeat_len)]).clone();
+
+        Ok(rg.score().into())
+    }
+
+    fn animate(rg: &Self::Parsed, part: usize) -> Result<Option<Frames<'_>>, anyhow::Error> {
+        let draw = |caption: String, rg: &RotatingGrid| {
+            Frame::new(caption, format!("{:?}", rg).lines().map(String::from).collect())
         };
 
-        rg.cycle();
+        let mut rg = rg.clone();
+        if part == 1 {
+            let before = draw(format!("load {}", rg.score()), &rg);
+            rg.slide_up(0);
+            let after = draw(format!("tilted north, load {}", rg.score()), &rg);
+            return Ok(Some(Box::new([before, after].into_iter())));
+        }
+
+        // One frame per spin cycle until the rocks repeat a position.
+        let cycles = CYCLES.get();
+        let mut seen = AHashMap::new();
+        let mut i: usize = 0;
+        let mut done = false;
+        let frames = std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+
+            let caption = match seen.entry(rg.round_rocks.clone()) {
+                Entry::Occupied(e) => {
+                    done = true;
+                    let start = *e.get();
+                    format!(
+                        "cycle {} repeats cycle {}, so cycle {} looks like cycle {}",
+                        i,
+                        start,
+                        cycles,
+                        same_cycle(cycles, start, i - start)
+                    )
+                }
+                Entry::Vacant(e) => {
+                    e.insert(i);
+                    format!("cycle {}, load {}", i, rg.score())
+                }
+            };
+            let frame = draw(caption, &rg);
+
+            rg.cycle();
+            i += 1;
+            Some(frame)
+        });
+        Ok(Some(Box::new(frames)))
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        let n = gen.size().max(1);
+        let rows: Vec<String> = (0..n)
+            .map(|_| {
+                (0..n)
+                    .map(|_| match gen.gen_range(0..20) {
+                        0..=2 => '#',
+                        3..=7 => 'O',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+        Some(rows.join("\n"))
+    }
+
+    fn reference(rg: &Self::Parsed, part: usize) -> Result<Option<Answer>, anyhow::Error> {
+        let mut grid: Vec<Vec<u8>> = format!("{:?}", rg)
+            .lines()
+            .map(|l| l.bytes().collect())
+            .collect();
+        if part == 1 {
+            roll(&mut grid, (0, -1));
+            return Ok(Some(load(&grid).into()));
+        }
+
+        let cycles = CYCLES.get();
+        if cycles > MAX_REFERENCE_CYCLES {
+            bail!(invalid_input!(
+                "the reference runs every cycle, set cycles to at most {}",
+                MAX_REFERENCE_CYCLES
+            ));
+        }
+        for _ in 0..cycles {
+            for dir in [(0, -1), (-1, 0), (0, 1), (1, 0)] {
+                roll(&mut grid, dir);
+            }
+        }
+        Ok(Some(load(&grid).into()))
+    }
+}
 
-        i += 1;
-    };
+// Moves round rocks one step at a time in a direction until none can move.
+fn roll(grid: &mut [Vec<u8>], (dx, dy): (isize, isize)) {
+    let mut moved = true;
+    while moved {
+        moved = false;
+        for y in 0..grid.len() {
+            for x in 0..grid[y].len() {
+                let (nx, ny) = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+                if grid[y][x] == b'O' && grid.get(ny).and_then(|r| r.get(nx)) == Some(&b'.') {
+                    grid[y][x] = b'.';
+                    grid[ny][nx] = b'O';
+                    moved = true;
+                }
+            }
+        }
+    }
+}
 
-    rg.round_rocks = (*history[repeat_start + (1_000_000_000 - repeat_start) % repeat_len]).clone();
+fn load(grid: &[Vec<u8>]) -> usize {
+    (0..grid.len())
+        .map(|y| (grid.len() - y) * grid[y].iter().filter(|&&c| c == b'O').count())
+        .sum()
+}
 
-    Ok(rg.score().to_string())
+// The first cycle that leaves the rocks where they are after `cycles` cycles,
+// when they repeat every `len` cycles from `start` on.
+fn same_cycle(cycles: usize, start: usize, len: usize) -> usize {
+    if cycles < start {
+        cycles
+    } else {
+        start + (cycles - start) % len
+    }
 }
 
 #[derive(Clone)]
-struct RotatingGrid {
+pub struct RotatingGrid {
     cols: usize,
     rows: usize,
     round_rocks: Vec<Point>,
@@ -72,7 +212,7 @@ impl RotatingGrid {
         let mut r = rows;
         for i in 1..square_rock_rotations.len() {
             square_rock_rotations[i] = square_rock_rotations[i - 1].clone();
-            rotate_clockwise(c, &mut square_rock_rotations[i]);
+            rotate_clockwise(r, &mut square_rock_rotations[i]);
             square_rock_rotations[i].sort_unstable();
             std::mem::swap(&mut c, &mut r);
         }
@@ -91,7 +231,7 @@ impl RotatingGrid {
 
         for i in 0..4 {
             self.slide_up(i);
-            rotate_clockwise(cols, &mut self.round_rocks);
+            rotate_clockwise(rows, &mut self.round_rocks);
             std::mem::swap(&mut cols, &mut rows);
         }
     }
@@ -106,17 +246,16 @@ impl RotatingGrid {
         let mut next_row = 0;
 
         loop {
-            let next = match (round_rocks.peek(), square_rocks.peek()) {
-                (Some(round), Some(square)) => {
-                    if **round <= **square {
-                        Rock::Round(round_rocks.next().unwrap())
-                    } else {
-                        Rock::Square(square_rocks.next().unwrap())
-                    }
-                }
-                (Some(_), None) => Rock::Round(round_rocks.next().unwrap()),
-                (None, Some(_)) => Rock::Square(square_rocks.next().unwrap()),
-                (None, None) => break,
+            let round_first = match (round_rocks.peek(), square_rocks.peek()) {
+                (Some(round), Some(square)) => **round <= **square,
+                (round, _) => round.is_some(),
+            };
+            let next = match round_first {
+                true => round_rocks.next().map(Rock::Round),
+                false => square_rocks.next().map(Rock::Square),
+            };
+            let Some(next) = next else {
+                break;
             };
 
             let p = next.point();
@@ -138,7 +277,7 @@ impl RotatingGrid {
     }
 
     fn score(&self) -> usize {
-        self.round_rocks.iter().map(|p| (self.rows - p.y)).sum()
+        self.round_rocks.iter().map(|p| self.rows - p.y).sum()
     }
 }
 
@@ -184,9 +323,9 @@ impl From<&Grid<Cell>> for RotatingGrid {
     }
 }
 
-fn rotate_clockwise(cols: usize, points: &mut [Point]) {
+fn rotate_clockwise(rows: usize, points: &mut [Point]) {
     for p in points.iter_mut() {
-        *p = Point::new(cols - p.y - 1, p.x);
+        *p = Point::new(rows - p.y - 1, p.x);
     }
 }
 
@@ -215,21 +354,21 @@ mod parser {
     use super::*;
     use crate::parser::prelude::*;
 
-    pub fn parse(input: &str) -> IResult<&str, Grid<Cell>> {
+    pub fn parse(input: &str) -> IResult<&str, Vec<Vec<Cell>>> {
         let cell = alt((
             value(Cell::Empty, char('.')),
             value(Cell::Square, char('#')),
             value(Cell::Round, char('O')),
         ));
         let row = ws_line(many1(cell));
-        let grid = many1(row).map(|rows| Grid::new(rows));
-        ws_all_consuming(grid)(input)
+        ws_all_consuming(many1(row))(input)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::params;
 
     const EXAMPLE_INPUT: &str = "
     O....#....
@@ -245,11 +384,82 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "136")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "136")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "64")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "64")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::parse("O.#\n.O\n#..").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 2 of the grid has 2 cells, but row 1 has 3"
+        );
+
+        // Platforms need not be square
+        let overrides = params::Overrides::parse(Solver::PARAMS, &["cycles=1000".to_owned()]).unwrap();
+        for input in ["O.#O.\n.O..#", "O.\n#O\n.O\nO.\n..", "#O..O.O#"] {
+            let rg = Solver::parse(input).unwrap();
+            let reference = |part| Solver::reference(&rg, part).unwrap().unwrap();
+            assert_eq!(Solver::part1(&rg).unwrap(), reference(1), "{}", input);
+            params::with(&overrides, || {
+                assert_eq!(Solver::part2(&rg).unwrap(), reference(2), "{}", input);
+            });
+        }
+    }
+
+    #[test]
+    fn cycles_test() {
+        // The rocks repeat every 7 cycles from cycle 3 on
+        assert_eq!(same_cycle(1_000_000_000, 3, 7), 6);
+        assert_eq!(same_cycle(2, 3, 7), 2);
+
+        let overrides = params::Overrides::parse(Solver::PARAMS, &["cycles=13".to_owned()]).unwrap();
+        params::with(&overrides, || {
+            assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "64")
+        });
+    }
+
+    #[test]
+    fn reference_test() {
+        let rg = Solver::parse(EXAMPLE_INPUT).unwrap();
+        assert_eq!(Solver::reference(&rg, 1).unwrap().unwrap(), "136");
+        let err = Solver::reference(&rg, 2).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the reference runs every cycle, set cycles to at most 100000"
+        );
+
+        // Past cycle 10 the fast solution skips the cycles the reference runs
+        for cycles in ["cycles=0", "cycles=2", "cycles=13", "cycles=1000"] {
+            let overrides = params::Overrides::parse(Solver::PARAMS, &[cycles.to_owned()]).unwrap();
+            params::with(&overrides, || {
+                let fast = Solver::part2(&rg).unwrap();
+                assert_eq!(Solver::reference(&rg, 2).unwrap().unwrap(), fast);
+            });
+        }
+    }
+
+    #[test]
+    fn animate_test() {
+        let rg = Solver::parse(EXAMPLE_INPUT).unwrap();
+
+        let frames: Vec<Frame> = Solver::animate(&rg, 1).unwrap().unwrap().collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].lines[1], "O.OO#....#");
+        assert_eq!(frames[1].lines[1], "OO..#....#");
+        assert_eq!(frames[1].caption, "tilted north, load 136");
+
+        let frames: Vec<Frame> = Solver::animate(&rg, 2).unwrap().unwrap().collect();
+        assert_eq!(frames.len(), 11);
+        assert_eq!(
+            frames[10].caption,
+            "cycle 10 repeats cycle 3, so cycle 1000000000 looks like cycle 6"
+        );
+        assert_eq!(frames[6].caption, "cycle 6, load 64");
     }
 }
@@ -2,33 +2,73 @@ use std::num::Wrapping;
 
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let instructions = input.trim().split(",");
-    let ans: usize = instructions.map(|x| hash(x) as usize).sum();
-    Ok(ans.to_string())
-}
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = Vec<Step>;
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let steps: Vec<Step> = parse!(input);
+        for (i, step) in steps.iter().enumerate() {
+            if let Instruction::Add(l) = &step.instruction {
+                if !(1..=9).contains(&l.length) {
+                    bail!(invalid_input!(
+                        "step {}: focal length {} is not between 1 and 9",
+                        i + 1,
+                        l.length
+                    ));
+                }
+            }
+        }
+        Ok(steps)
+    }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let instructions = parse!(input);
+    fn part1(steps: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let ans: usize = steps.iter().map(|x| hash(&x.text) as usize).sum();
+        Ok(ans.into())
+    }
 
-    let mut map = ElfHashMap::default();
+    fn part2(steps: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let mut map = ElfHashMap::default();
 
-    for inst in instructions.iter() {
-        match inst {
-            Instruction::Add(l) => map.add(l.clone()),
-            Instruction::Remove(id) => map.remove(id),
+        for step in steps.iter() {
+            match &step.instruction {
+                Instruction::Add(l) => map.add(l.clone()),
+                Instruction::Remove(id) => map.remove(id),
+            }
         }
-    }
 
-    let ans: usize = map
-        .buckets
-        .iter()
-        .enumerate()
-        .flat_map(|(i, b)| b.iter().enumerate().map(move |(j, l)| (i, j, l)))
-        .map(|(i, j, l)| (i + 1) * (j + 1) * l.length)
-        .sum();
+        let ans: usize = map
+            .buckets
+            .iter()
+            .enumerate()
+            .flat_map(|(i, b)| b.iter().enumerate().map(move |(j, l)| (i, j, l)))
+            .map(|(i, j, l)| (i + 1) * (j + 1) * l.length)
+            .sum();
+
+        Ok(ans.into())
+    }
 
-    Ok(ans.to_string())
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        // Labels are reused so lenses get replaced and removed
+        let labels: Vec<String> = (0..(gen.size() / 2).max(2))
+            .map(|_| {
+                let len = gen.gen_range(2..=6);
+                gen.word(len)
+            })
+            .collect();
+        let steps: Vec<String> = (0..gen.size().max(1) * 10)
+            .map(|_| {
+                let label = labels.choose(gen).unwrap();
+                if gen.gen_bool(0.7) {
+                    format!("{}={}", label, gen.gen_range(1..=9))
+                } else {
+                    format!("{}-", label)
+                }
+            })
+            .collect();
+        Some(steps.join(","))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -84,11 +124,18 @@ enum Instruction {
     Remove(String),
 }
 
+// A comma separated step along with its original text, which part 1 hashes.
+pub struct Step {
+    text: String,
+    instruction: Instruction,
+}
+
 mod parser {
     use super::*;
     use crate::parser::prelude::*;
+    use nom::combinator::consumed;
 
-    pub fn parse(input: &str) -> IResult<&str, Vec<Instruction>> {
+    pub fn parse(input: &str) -> IResult<&str, Vec<Step>> {
         let add_inst = separated_pair(alpha1, char('='), uint).map(|(id, length)| {
             Instruction::Add(Lens {
                 id: id.to_string(),
@@ -98,7 +145,11 @@ mod parser {
         let rem_inst =
             terminated(alpha1, char('-')).map(|id: &str| Instruction::Remove(id.to_string()));
         let inst = alt((add_inst, rem_inst));
-        let parser = separated_list1(char(','), inst);
+        let step = consumed(inst).map(|(text, instruction): (&str, _)| Step {
+            text: text.to_owned(),
+            instruction,
+        });
+        let parser = separated_list1(char(','), step);
         ws_all_consuming(parser)(input)
     }
 }
@@ -111,11 +162,28 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "1320")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "1320")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "145")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "145")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::problem1("rn=1,cm?").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to parse input at line 1, column 5: unexpected `,cm?`"
+        );
+
+        let err = Solver::problem1("rn=1,cm=18446744073709551615").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "step 2: focal length 18446744073709551615 is not between 1 and 9"
+        );
+        let err = Solver::problem1("rn=0").unwrap_err();
+        assert_eq!(err.to_string(), "step 1: focal length 0 is not between 1 and 9");
     }
 }
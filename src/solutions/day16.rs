@@ -1,62 +1,186 @@
-use std::collections::VecDeque;
-
+use ahash::AHashMap;
 use arrayvec::ArrayVec;
 
 use crate::grid::{Direction, Grid, Point};
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
-    let ans = num_energized(&grid, Point::new(0, 0), Direction::Right);
-    Ok(ans.to_string())
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = Grid<Cell>;
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Grid::try_new(parse!(input))
+    }
+
+    fn part1(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let ans = num_energized(grid, Point::new(0, 0), Direction::Right);
+        Ok(ans.into())
+    }
+
+    fn part2(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let ans = edge_starts(grid)
+            .map(|(p, d)| num_energized(grid, p, d))
+            .max()
+            .ok_or_else(|| internal!("size zero grid?"))?;
+
+        Ok(ans.into())
+    }
+
+    fn render(grid: &Self::Parsed, part: usize) -> Result<Option<Canvas>, anyhow::Error> {
+        let (start, dir) = part_start(grid, part)?;
+        let seen = energize(grid, start, dir);
+        let energized = grid.iter_points().filter(|&p| seen.get(p).is_some_and(|x| x.0 > 0));
+
+        let mut canvas = Canvas::new(grid, |c| match c {
+            Cell::Empty => Color::gray(24),
+            _ => Color::gray(200),
+        });
+        canvas
+            .overlay("energized", Color::rgb(255, 150, 30), 160, energized)
+            .overlay("start", Color::rgb(220, 40, 40), 255, [start]);
+        Ok(Some(canvas))
+    }
+
+    fn animate(grid: &Self::Parsed, part: usize) -> Result<Option<Frames<'_>>, anyhow::Error> {
+        let (start, dir) = part_start(grid, part)?;
+        let mut beam = Beam::new(grid, start, dir);
+        let mut step = 0;
+        let mut done = false;
+
+        let frames = std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            done = !beam.step();
+            step += 1;
+
+            let heads: AHashMap<Point, Direction> = beam.front.iter().copied().collect();
+            let energized = beam.seen.cells.iter().flatten().filter(|x| x.0 > 0).count();
+            let caption = format!("step {}, {} tiles energized", step, energized);
+            Some(Frame::grid(caption, grid, |p, c| {
+                if let Some(d) = heads.get(&p) {
+                    return match d {
+                        Direction::Up => 'v',
+                        Direction::Down => '^',
+                        Direction::Left => '<',
+                        Direction::Right => '>',
+                    };
+                }
+                match c {
+                    Cell::Empty if beam.seen.get(p).is_some_and(|x| x.0 > 0) => '#',
+                    Cell::Empty => '.',
+                    Cell::Slash => '/',
+                    Cell::BackSlash => '\\',
+                    Cell::VerticalSplit => '|',
+                    Cell::HorizontalSplit => '-',
+                }
+            }))
+        });
+        Ok(Some(Box::new(frames)))
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        let n = gen.size().max(1);
+        let rows: Vec<String> = (0..n)
+            .map(|_| {
+                (0..n)
+                    .map(|_| match gen.gen_range(0..40) {
+                        0..=1 => '/',
+                        2..=3 => '\\',
+                        4..=5 => '|',
+                        6..=7 => '-',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+        Some(rows.join("\n"))
+    }
+}
+
+// Where the beam of a part enters the grid. Part 2 uses the start that
+// energizes the most tiles.
+fn part_start(grid: &Grid<Cell>, part: usize) -> anyhow::Result<(Point, Direction)> {
+    if part == 1 {
+        return Ok((Point::new(0, 0), Direction::Right));
+    }
+    let start = edge_starts(grid)
+        .max_by_key(|&(p, d)| num_energized(grid, p, d))
+        .ok_or_else(|| internal!("size zero grid?"))?;
+    Ok(start)
 }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
+// Every point on the edge with the direction that enters the grid from there.
+fn edge_starts(grid: &Grid<Cell>) -> impl Iterator<Item = (Point, Direction)> {
     let (cols, rows) = grid.size();
 
     let left_starts = (0..rows).map(|r| (Point::new(0, r), Direction::Right));
-    let right_starts = (0..rows).map(|r| (Point::new(cols - 1, r), Direction::Left));
+    let right_starts = (0..rows).map(move |r| (Point::new(cols - 1, r), Direction::Left));
     let top_starts = (0..cols).map(|c| (Point::new(c, 0), Direction::Up));
-    let bottom_starts = (0..cols).map(|c| (Point::new(c, rows - 1), Direction::Down));
-    let starts = left_starts
+    let bottom_starts = (0..cols).map(move |c| (Point::new(c, rows - 1), Direction::Down));
+    left_starts
         .chain(right_starts)
         .chain(top_starts)
-        .chain(bottom_starts);
+        .chain(bottom_starts)
+}
 
-    let ans = starts
-        .map(|(p, d)| num_energized(&grid, p, d))
-        .max()
-        .ok_or_else(|| anyhow!("size zero grid?"))?;
+fn num_energized(grid: &Grid<Cell>, start: Point, start_dir: Direction) -> usize {
+    energize(grid, start, start_dir)
+        .cells
+        .iter()
+        .flat_map(|x| x.iter())
+        .filter(|x| x.0 > 0)
+        .count()
+}
 
-    Ok(ans.to_string())
+// The directions the beam passed through each point in.
+fn energize(grid: &Grid<Cell>, start: Point, start_dir: Direction) -> Grid<DirectionSet> {
+    let mut beam = Beam::new(grid, start, start_dir);
+    while beam.step() {}
+    beam.seen
 }
 
-fn num_energized(grid: &Grid<Cell>, start: Point, start_dir: Direction) -> usize {
-    let (cols, rows) = grid.size();
-    let mut seen = Grid::new(vec![vec![DirectionSet::default(); cols]; rows]);
-    let mut frontier = VecDeque::new();
-    frontier.push_back((start, start_dir));
+// A beam spreading through the grid one tile per step.
+struct Beam<'a> {
+    grid: &'a Grid<Cell>,
+    seen: Grid<DirectionSet>,
+    // Where the beam goes next and in which direction
+    front: Vec<(Point, Direction)>,
+    next: Vec<(Point, Direction)>,
+}
 
-    while let Some((p, d)) = frontier.pop_front() {
-        if !seen.get_mut(p).map(|x| x.set(d)).unwrap_or(false) {
-            continue;
+impl<'a> Beam<'a> {
+    fn new(grid: &'a Grid<Cell>, start: Point, start_dir: Direction) -> Self {
+        let (cols, rows) = grid.size();
+        Self {
+            grid,
+            seen: Grid::new(vec![vec![DirectionSet::default(); cols]; rows]),
+            front: vec![(start, start_dir)],
+            next: Vec::new(),
         }
+    }
 
-        for new_d in grid.get(p).map(|x| x.refract(d)).unwrap_or(ArrayVec::new()) {
-            let Some(new_p) = p.next(new_d) else {
+    // Returns false once the beam stopped spreading.
+    fn step(&mut self) -> bool {
+        for &(p, d) in &self.front {
+            if !self.seen.get_mut(p).map(|x| x.set(d)).unwrap_or(false) {
                 continue;
-            };
+            }
+
+            for new_d in self.grid.get(p).map(|x| x.refract(d)).unwrap_or_default() {
+                let Some(new_p) = p.next(new_d) else {
+                    continue;
+                };
 
-            frontier.push_back((new_p, new_d));
+                self.next.push((new_p, new_d));
+            }
         }
-    }
 
-    seen.cells
-        .iter()
-        .flat_map(|x| x.iter())
-        .filter(|x| x.0 > 0)
-        .count()
+        std::mem::swap(&mut self.front, &mut self.next);
+        self.next.clear();
+        !self.front.is_empty()
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -79,7 +203,7 @@ impl DirectionSet {
 }
 
 #[derive(Clone, Copy, Debug)]
-enum Cell {
+pub enum Cell {
     Empty,
     Slash,
     BackSlash,
@@ -123,7 +247,7 @@ mod parser {
     use super::*;
     use crate::parser::prelude::*;
 
-    pub fn parse(input: &str) -> IResult<&str, Grid<Cell>> {
+    pub fn parse(input: &str) -> IResult<&str, Vec<Vec<Cell>>> {
         let cell = alt((
             value(Cell::Empty, char('.')),
             value(Cell::Slash, char('/')),
@@ -132,8 +256,7 @@ mod parser {
             value(Cell::HorizontalSplit, char('-')),
         ));
         let row = ws_line(many1(cell));
-        let grid = many1(row).map(|d| Grid::new(d));
-        ws_all_consuming(grid)(input)
+        ws_all_consuming(many1(row))(input)
     }
 }
 
@@ -156,11 +279,33 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "46")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "46")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "51")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "51")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::parse(".|.\n\\..-").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 2 of the grid has 4 cells, but row 1 has 3"
+        );
+    }
+
+    #[test]
+    fn animate_test() {
+        let grid = Solver::parse(EXAMPLE_INPUT).unwrap();
+        let frames: Vec<Frame> = Solver::animate(&grid, 1).unwrap().unwrap().collect();
+
+        assert_eq!(frames[0].caption, "step 1, 1 tiles energized");
+        assert_eq!(frames[0].lines[0], "#>...\\....");
+        // The splitter sends the beam down only, up leaves the grid
+        assert_eq!(frames[1].lines[0], "#|...\\....");
+        assert_eq!(frames[1].lines[1], "|v-.\\.....");
+        assert!(frames.last().unwrap().caption.ends_with(", 46 tiles energized"));
     }
 }
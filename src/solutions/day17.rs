@@ -3,17 +3,55 @@ use std::collections::VecDeque;
 use crate::grid::{Direction, Grid, Point};
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
-    Ok(solve(&grid, 0, 3).to_string())
-}
+pub struct Solver;
+
+impl Solution for Solver {
+    type Parsed = Grid<u8>;
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
-    Ok(solve(&grid, 4, 10).to_string())
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Grid::try_new(parse!(input))
+    }
+
+    fn part1(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        Ok(solve(grid, 0, 3, |_, _| ())?.0.into())
+    }
+
+    fn part2(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        Ok(solve(grid, 4, 10, |_, _| ())?.0.into())
+    }
+
+    fn render(grid: &Self::Parsed, part: usize) -> Result<Option<Canvas>, anyhow::Error> {
+        let (_, path) = if part == 1 {
+            best_path(grid, 0, 3)?
+        } else {
+            best_path(grid, 4, 10)?
+        };
+
+        // Cooler blocks are darker
+        let mut canvas = Canvas::new(grid, |&heat| Color::rgb(heat * 25, heat * 12, 40));
+        canvas.overlay("path", Color::WHITE, 255, path);
+        Ok(Some(canvas))
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        // Ultra crucibles need room to reach the corner
+        let n = gen.size().max(5);
+        let rows: Vec<String> = (0..n)
+            .map(|_| (0..n).map(|_| gen.gen_range(b'1'..=b'9') as char).collect())
+            .collect();
+        Some(rows.join("\n"))
+    }
 }
 
-fn solve(grid: &Grid<u8>, min_dir: u8, max_dir: u8) -> usize {
+// Returns the least heat lost on the way to the bottom right corner and the
+// state that got there. `reached` is called with every state the first time
+// it is reached and the state it was reached from.
+fn solve(
+    grid: &Grid<u8>,
+    min_dir: u8,
+    max_dir: u8,
+    mut reached: impl FnMut(&WalkState, &WalkState),
+) -> Result<(usize, WalkState), anyhow::Error> {
     let (cols, rows) = grid.size();
     let target_point = Point::new(cols - 1, rows - 1);
 
@@ -27,10 +65,16 @@ fn solve(grid: &Grid<u8>, min_dir: u8, max_dir: u8) -> usize {
     };
 
     for state in start.iter_next(0, 1) {
-        frontier.push(0, state);
+        if let Some(s_index) = state.as_int(cols, rows, max_dir) {
+            seen[s_index] = true;
+        }
+        reached(&state, &start);
+        frontier.push(0, state)?;
     }
 
+    let mut pops = 0;
     while let Some((cost, state)) = frontier.pop() {
+        pops += 1;
         let Some(&additional_cost) = grid.get(state.loc) else {
             continue;
         };
@@ -38,7 +82,8 @@ fn solve(grid: &Grid<u8>, min_dir: u8, max_dir: u8) -> usize {
         let new_cost = cost + additional_cost as usize;
 
         if state.loc == target_point && state.straight_count >= min_dir {
-            return new_cost;
+            trace::count("pops", pops);
+            return Ok((new_cost, state));
         }
 
         for next_state in state.iter_next(min_dir, max_dir) {
@@ -47,12 +92,41 @@ fn solve(grid: &Grid<u8>, min_dir: u8, max_dir: u8) -> usize {
             };
             if !seen[s_index] {
                 seen[s_index] = true;
-                frontier.push(new_cost, next_state);
+                reached(&next_state, &state);
+                frontier.push(new_cost, next_state)?;
             }
         }
     }
 
-    unreachable!("no solution");
+    bail!(no_solution!("no path reaches the bottom right corner"));
+}
+
+// Like `solve` but also returns the blocks passed through, starting with the
+// top left corner.
+fn best_path(
+    grid: &Grid<u8>,
+    min_dir: u8,
+    max_dir: u8,
+) -> Result<(usize, Vec<Point>), anyhow::Error> {
+    let (cols, rows) = grid.size();
+    let mut came_from = vec![None; WalkState::max_int_state(cols, rows, max_dir)];
+    let (cost, end) = solve(grid, min_dir, max_dir, |state, from| {
+        if let Some(i) = state.as_int(cols, rows, max_dir) {
+            came_from[i] = Some(*from);
+        }
+    })?;
+
+    let mut path = vec![end.loc];
+    let mut state = end;
+    while let Some(from) = state
+        .as_int(cols, rows, max_dir)
+        .and_then(|i| came_from[i])
+    {
+        path.push(from.loc);
+        state = from;
+    }
+    path.reverse();
+    Ok((cost, path))
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -108,14 +182,19 @@ struct RollingPriorityQueue<V> {
 }
 
 impl<V> RollingPriorityQueue<V> {
-    fn push(&mut self, k: usize, v: V) {
-        let i = k
-            .checked_sub(self.min_key)
-            .expect("RollingPriorityQueue: priority must be same or greater than minimum");
+    fn push(&mut self, k: usize, v: V) -> Result<(), anyhow::Error> {
+        let i = k.checked_sub(self.min_key).ok_or_else(|| {
+            internal!(
+                "priority {} is below the minimum {} of the queue",
+                k,
+                self.min_key
+            )
+        })?;
         if i >= self.queue.len() {
             self.queue.resize_with(i + 1, Vec::new);
         }
         self.queue[i].push(v);
+        Ok(())
     }
 
     fn pop(&mut self) -> Option<(usize, V)> {
@@ -140,20 +219,19 @@ impl<V> Default for RollingPriorityQueue<V> {
 }
 
 mod parser {
-    use super::*;
     use crate::parser::prelude::*;
 
-    pub fn parse(input: &str) -> IResult<&str, Grid<u8>> {
-        let cell = one_of("0123456789").map(|x| x.to_digit(10).unwrap() as u8);
+    pub fn parse(input: &str) -> IResult<&str, Vec<Vec<u8>>> {
+        let cell = one_of("0123456789").map(|x| x as u8 - b'0');
         let row = ws_line(many1(cell));
-        let grid = many1(row).map(|d| Grid::new(d));
-        ws_all_consuming(grid)(input)
+        ws_all_consuming(many1(row))(input)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::{self, ErrorKind};
 
     const EXAMPLE_INPUT: &str = "
         2413432311323
@@ -173,11 +251,53 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "102")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "102")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "94")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "94")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        // Ultra crucibles cannot turn before the corner
+        assert_eq!(Solver::problem1("123\n456").unwrap(), "11");
+        let err = Solver::problem2("123\n456").unwrap_err();
+        assert_eq!(err.to_string(), "no path reaches the bottom right corner");
+
+        let err = Solver::parse("123\n4567").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 2 of the grid has 4 cells, but row 1 has 3"
+        );
+    }
+
+    #[test]
+    fn queue_test() {
+        let mut queue = RollingPriorityQueue::default();
+        queue.push(5, 'a').unwrap();
+        queue.push(2, 'b').unwrap();
+        assert_eq!(queue.pop(), Some((2, 'b')));
+        assert_eq!(queue.pop(), Some((5, 'a')));
+
+        let err = queue.push(3, 'c').unwrap_err();
+        assert_eq!(err.to_string(), "priority 3 is below the minimum 5 of the queue");
+        assert_eq!(error::kind(&err), ErrorKind::Internal);
+    }
+
+    #[test]
+    fn path_test() {
+        let grid = Solver::parse(EXAMPLE_INPUT).unwrap();
+        let (cost, path) = best_path(&grid, 0, 3).unwrap();
+        assert_eq!(path.first(), Some(&Point::new(0, 0)));
+        assert_eq!(path.last(), Some(&Point::new(12, 12)));
+
+        // Each step moves to a neighbour and the heat lost adds up
+        for w in path.windows(2) {
+            assert!(w[0].iter_adjacent().any(|p| p == w[1]));
+        }
+        let heat: usize = path[1..].iter().map(|&p| *grid.get(p).unwrap() as usize).sum();
+        assert_eq!(heat, cost);
     }
 }
@@ -1,101 +1,292 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let (workflows, ratings) = parse!(input);
-    let workflows: AHashMap<String, Workflow> =
-        workflows.into_iter().map(|w| (w.name.clone(), w)).collect();
+pub struct Solver;
 
-    fn filter_rating(workflows: &AHashMap<String, Workflow>, rating: &Rating) -> bool {
-        let mut cur = &Target::Workflow("in".into());
-        while let Target::Workflow(id) = cur {
-            cur = workflows.get(id.as_ref()).unwrap().eval(rating)
-        }
+const MIN_RATING: Param<u32> = Param::new("min-rating", 1, "lowest rating considered in part 2");
+const MAX_RATING: Param<u32> = Param::new("max-rating", 4000, "highest rating considered in part 2");
+
+impl Solution for Solver {
+    type Parsed = (AHashMap<String, Workflow>, Vec<Rating>);
+
+    const PARAMS: &'static [&'static dyn ParamInfo] = &[&MIN_RATING, &MAX_RATING];
 
-        cur == &Target::Accept
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let (workflows, ratings) = parse!(input);
+        let workflows = workflows.into_iter().map(|w| (w.name.clone(), w)).collect();
+        check_workflows(&workflows)?;
+        Ok((workflows, ratings))
     }
 
-    let ans: usize = ratings
-        .iter()
-        .filter(|r| filter_rating(&workflows, r))
-        .map(|r| r.sum() as usize)
-        .sum();
+    fn part1((workflows, ratings): &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        fn filter_rating(
+            workflows: &AHashMap<String, Workflow>,
+            rating: &Rating,
+        ) -> Result<bool, anyhow::Error> {
+            let mut cur = &Target::Workflow("in".into());
+            while let Target::Workflow(id) = cur {
+                cur = get_workflow(workflows, id)?.eval(rating)
+            }
 
-    Ok(ans.to_string())
-}
+            Ok(cur == &Target::Accept)
+        }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let (workflows, _) = parse!(input);
-    let workflows: AHashMap<String, Workflow> =
-        workflows.into_iter().map(|w| (w.name.clone(), w)).collect();
-
-    fn rec(workflows: &AHashMap<String, Workflow>, cur: &str) -> Vec<RatingRange> {
-        const DEFAULT_RATING_RANGE: RatingRange = RatingRange {
-            x: Range::new(1, 4000),
-            m: Range::new(1, 4000),
-            a: Range::new(1, 4000),
-            s: Range::new(1, 4000),
-        };
+        let mut ans: u64 = 0;
+        for r in ratings {
+            if filter_rating(workflows, r)? {
+                ans += r.sum();
+            }
+        }
 
-        let mut ret = Vec::new();
-        let workflow = workflows.get(cur).unwrap();
-        for (i, rule) in workflow.rules.iter().enumerate() {
-            match &rule.target {
+        Ok(ans.into())
+    }
+
+    fn part2((workflows, _): &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        fn rec(
+            workflows: &AHashMap<String, Workflow>,
+            cur: &str,
+            full: &RatingRange,
+        ) -> Result<Vec<RatingRange>, anyhow::Error> {
+            let mut ret = Vec::new();
+            let workflow = get_workflow(workflows, cur)?;
+            for (i, rule) in workflow.rules.iter().enumerate() {
+                match &rule.target {
+                    Target::Accept => {
+                        if let Some(r) = full.apply_rules([rule], &workflow.rules[..i]) {
+                            ret.push(r);
+                        }
+                    }
+                    Target::Reject => (), // skip
+                    Target::Workflow(id) => {
+                        let res = rec(workflows, id, full)?
+                            .into_iter()
+                            .filter_map(|r| r.apply_rules([rule], &workflow.rules[..i]));
+                        ret.extend(res);
+                    }
+                }
+            }
+
+            match &workflow.default_target {
                 Target::Accept => {
-                    if let Some(r) = DEFAULT_RATING_RANGE.apply_rules([rule], &workflow.rules[..i])
-                    {
-                        ret.push(r);
+                    if let Some(r) = full.apply_rules([], &workflow.rules) {
+                        ret.push(r)
                     }
                 }
                 Target::Reject => (), // skip
-                Target::Workflow(id) => {
-                    let res = rec(workflows, &id)
+                Target::Workflow(id) => ret.extend(
+                    rec(workflows, id, full)?
                         .into_iter()
-                        .filter_map(|r| r.apply_rules([rule], &workflow.rules[..i]));
-                    ret.extend(res);
+                        .filter_map(|r| r.apply_rules([], &workflow.rules)),
+                ),
+            }
+
+            Ok(ret)
+        }
+
+        let range = Range::new(MIN_RATING.get(), MAX_RATING.get());
+        if !range.is_valid() {
+            bail!(invalid_input!(
+                "min-rating {} is above max-rating {}",
+                range.start,
+                range.end
+            ));
+        }
+        let full = RatingRange {
+            x: range,
+            m: range,
+            a: range,
+            s: range,
+        };
+        let ranges = rec(workflows, "in", &full)?;
+        let ans = ranges
+            .iter()
+            .try_fold(0u64, |ans, r| ans.checked_add(r.num_ratings()?))
+            .ok_or_else(|| invalid_input!("too many accepted ratings to count"))?;
+
+        Ok(ans.into())
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        Some(generate_system(gen))
+    }
+
+    fn reference((workflows, ratings): &Self::Parsed, part: usize) -> Result<Option<Answer>, anyhow::Error> {
+        if part == 1 {
+            let mut ans = 0;
+            for r in ratings {
+                if accepts(workflows, r)? {
+                    ans += r.sum();
                 }
             }
+            return Ok(Some(ans.into()));
+        }
+
+        let (min, max) = (MIN_RATING.get(), MAX_RATING.get());
+        if min > max {
+            bail!(invalid_input!("min-rating {} is above max-rating {}", min, max));
+        }
+
+        // Ratings between two neighbouring thresholds of a category all go
+        // the same way, so the first of them stands in for the rest
+        let mut starts: [Vec<u32>; 4] = Default::default();
+        for s in starts.iter_mut() {
+            s.push(min);
+        }
+        for rule in workflows.values().flat_map(|w| &w.rules) {
+            let start = match rule.op {
+                Ordering::Less => rule.value,
+                _ => rule.value.saturating_add(1),
+            };
+            if min < start && start <= max {
+                starts[rule.category as usize].push(start);
+            }
         }
+        let spans = starts.map(|mut s| {
+            s.sort_unstable();
+            s.dedup();
+            let ends = s.iter().skip(1).map(|&x| x as u64).chain([max as u64 + 1]);
+            s.iter()
+                .zip(ends)
+                .map(|(&start, end)| (start, end - start as u64))
+                .collect::<Vec<_>>()
+        });
 
-        match &workflow.default_target {
-            Target::Accept => {
-                if let Some(r) = DEFAULT_RATING_RANGE.apply_rules([], &workflow.rules) {
-                    ret.push(r)
+        let mut ans: u64 = 0;
+        for &(x, xn) in &spans[0] {
+            for &(m, mn) in &spans[1] {
+                for &(a, an) in &spans[2] {
+                    for &(s, sn) in &spans[3] {
+                        if accepts(workflows, &Rating { x, m, a, s })? {
+                            ans += xn * mn * an * sn;
+                        }
+                    }
                 }
             }
-            Target::Reject => (), // skip
-            Target::Workflow(id) => ret.extend(
-                rec(workflows, &id)
-                    .into_iter()
-                    .filter_map(|r| r.apply_rules([], &workflow.rules)),
-            ),
         }
+        Ok(Some(ans.into()))
+    }
+}
+
+// Checks that the workflows from `in` end in accept or reject for every part,
+// by sending each workflow only to declared ones that do not lead back to it.
+fn check_workflows(workflows: &AHashMap<String, Workflow>) -> Result<(), anyhow::Error> {
+    fn visit<'a>(
+        workflows: &'a AHashMap<String, Workflow>,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut AHashSet<&'a str>,
+    ) -> Result<(), anyhow::Error> {
+        if done.contains(name) {
+            return Ok(());
+        }
+        if path.contains(&name) {
+            bail!(invalid_input!("workflow `{}` leads back to itself", name));
+        }
+        let workflow = get_workflow(workflows, name)?;
+
+        path.push(name);
+        let targets = workflow.rules.iter().map(|r| &r.target);
+        for target in targets.chain([&workflow.default_target]) {
+            if let Target::Workflow(next) = target {
+                visit(workflows, next, path, done)?;
+            }
+        }
+        path.pop();
+        done.insert(name);
+        Ok(())
+    }
 
-        ret
+    let mut done = AHashSet::new();
+    visit(workflows, "in", &mut Vec::new(), &mut done)?;
+    for name in workflows.keys() {
+        visit(workflows, name, &mut Vec::new(), &mut done)?;
     }
+    Ok(())
+}
 
-    let ranges = rec(&workflows, "in");
-    let ans: u64 = ranges.iter().map(|r| r.num_ratings()).sum();
+fn get_workflow<'a>(
+    workflows: &'a AHashMap<String, Workflow>,
+    name: &str,
+) -> Result<&'a Workflow, anyhow::Error> {
+    let workflow = workflows
+        .get(name)
+        .ok_or_else(|| invalid_input!("workflow `{}` not declared", name))?;
+    Ok(workflow)
+}
 
-    Ok(ans.to_string())
+// Follows a part through the workflows one rule at a time.
+fn accepts(workflows: &AHashMap<String, Workflow>, rating: &Rating) -> Result<bool, anyhow::Error> {
+    let mut name = "in";
+    loop {
+        let workflow = get_workflow(workflows, name)?;
+        let matching = workflow.rules.iter().find(|r| match r.op {
+            Ordering::Less => rating.get(r.category) < r.value,
+            _ => rating.get(r.category) > r.value,
+        });
+        match matching.map_or(&workflow.default_target, |r| &r.target) {
+            Target::Accept => return Ok(true),
+            Target::Reject => return Ok(false),
+            Target::Workflow(next) => name = next,
+        }
+    }
+}
+
+// Workflows form a tree from `in`, so every part ends up accepted or
+// rejected.
+fn generate_system(gen: &mut InputGenerator) -> String {
+    let count = (gen.size() / 2).max(1);
+    let mut names = gen.words(count - 1, 3, &[]).into_iter();
+
+    let mut workflows = Vec::new();
+    let mut todo = vec!["in".to_owned()];
+    while let Some(name) = todo.pop() {
+        let mut target = |gen: &mut InputGenerator| match names.len() > 0 && gen.gen_bool(0.6) {
+            true => {
+                let child = names.next().unwrap();
+                todo.push(child.clone());
+                child
+            }
+            false => ["A", "R"].choose(gen).unwrap().to_string(),
+        };
+
+        let mut rules: Vec<String> = (0..gen.gen_range(1..=3))
+            .map(|_| {
+                let category = *b"xmas".choose(gen).unwrap() as char;
+                let op = *b"<>".choose(gen).unwrap() as char;
+                let value = gen.gen_range(1..4000);
+                format!("{}{}{}:{}", category, op, value, target(gen))
+            })
+            .collect();
+        rules.push(target(gen));
+        workflows.push(format!("{}{{{}}}", name, rules.join(",")));
+    }
+    workflows.shuffle(gen);
+
+    let ratings: Vec<String> = (0..count)
+        .map(|_| {
+            let [x, m, a, s] = [(); 4].map(|_| gen.gen_range(1..=4000));
+            format!("{{x={},m={},a={},s={}}}", x, m, a, s)
+        })
+        .collect();
+    format!("{}\n\n{}", workflows.join("\n"), ratings.join("\n"))
 }
 
 #[derive(Clone, Debug)]
-struct Workflow {
+pub struct Workflow {
     name: String,
     rules: Vec<Rule>,
     default_target: Target<'static>,
 }
 
 impl Workflow {
-    fn eval(&self, rating: &Rating) -> &Target {
+    fn eval(&self, rating: &Rating) -> &Target<'_> {
         self.rules
             .iter()
-            .filter_map(|r| (r.eval(&rating)).then_some(&r.target))
+            .filter_map(|r| (r.eval(rating)).then_some(&r.target))
             .next()
             .unwrap_or(&self.default_target)
     }
@@ -144,8 +335,8 @@ impl Range {
         Self { start, end }
     }
 
-    fn len(&self) -> u32 {
-        self.end - self.start + 1
+    fn len(&self) -> u64 {
+        (self.end - self.start) as u64 + 1
     }
 
     fn is_valid(&self) -> bool {
@@ -185,8 +376,12 @@ struct RatingRange {
 }
 
 impl RatingRange {
-    fn num_ratings(&self) -> u64 {
-        self.x.len() as u64 * self.m.len() as u64 * self.a.len() as u64 * self.s.len() as u64
+    fn num_ratings(&self) -> Option<u64> {
+        self.x
+            .len()
+            .checked_mul(self.m.len())?
+            .checked_mul(self.a.len())?
+            .checked_mul(self.s.len())
     }
 
     fn apply_rule(&mut self, rule: &Rule, opposite: bool) -> bool {
@@ -195,10 +390,16 @@ impl RatingRange {
             true => (rule.op.reverse(), 0),
             false => (rule.op, 1),
         };
+        // No rating is above u32::MAX or below 0
         let new_r = match op {
-            Ordering::Greater => range.subset_with_start(rule.value + offset),
-            Ordering::Less => range.subset_with_end(rule.value - offset),
-            _ => panic!("bad op"),
+            Ordering::Less => rule
+                .value
+                .checked_sub(offset)
+                .and_then(|end| range.subset_with_end(end)),
+            _ => rule
+                .value
+                .checked_add(offset)
+                .and_then(|start| range.subset_with_start(start)),
         };
 
         let Some(new_r) = new_r else {
@@ -251,7 +452,7 @@ enum Category {
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-struct Rating {
+pub struct Rating {
     x: u32,
     m: u32,
     a: u32,
@@ -277,8 +478,8 @@ impl Rating {
         }
     }
 
-    fn sum(&self) -> u32 {
-        self.x + self.m + self.a + self.s
+    fn sum(&self) -> u64 {
+        self.x as u64 + self.m as u64 + self.a as u64 + self.s as u64
     }
 }
 
@@ -302,7 +503,7 @@ mod parser {
             value(Ordering::Greater, char('>')),
             value(Ordering::Less, char('<')),
         ));
-        let target = move |input| id.map(|s| Target::from(s)).parse(input);
+        let target = move |input| id.map(Target::from).parse(input);
         let rule = tuple((category, op, uint, preceded(char(':'), target))).map(
             |(category, op, value, target)| Rule {
                 category,
@@ -341,6 +542,7 @@ mod parser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::params;
 
     const EXAMPLE_INPUT: &str = "
         px{a<2006:qkq,m>2090:A,rfg}
@@ -364,11 +566,44 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "19114")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "19114")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "167409079868000")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "167409079868000")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::parse("in{x<5:ab,R}\n\n{x=1,m=2,a=3,s=4}").unwrap_err();
+        assert_eq!(err.to_string(), "workflow `ab` not declared");
+
+        let err = Solver::parse("ab{x<5:A,R}\n\n{x=1,m=2,a=3,s=4}").unwrap_err();
+        assert_eq!(err.to_string(), "workflow `in` not declared");
+
+        let input = "in{x<5:ab,R}\nab{m>3:in,A}\n\n{x=1,m=2,a=3,s=4}";
+        let err = Solver::parse(input).unwrap_err();
+        assert_eq!(err.to_string(), "workflow `in` leads back to itself");
+
+        let input = "in{x<0:R,s>4294967295:R,A}\n\n{x=4294967295,m=4294967295,a=1,s=1}";
+        assert_eq!(Solver::problem1(input).unwrap(), "8589934592");
+
+        let args = ["min-rating=0".to_owned(), "max-rating=4294967295".to_owned()];
+        let overrides = params::Overrides::parse(Solver::PARAMS, &args).unwrap();
+        params::with(&overrides, || {
+            let err = Solver::problem2(input).unwrap_err();
+            assert_eq!(err.to_string(), "too many accepted ratings to count");
+        });
+    }
+
+    #[test]
+    fn reference_test() {
+        let parsed = Solver::parse(EXAMPLE_INPUT).unwrap();
+        assert_eq!(Solver::reference(&parsed, 1).unwrap().unwrap(), "19114");
+        assert_eq!(
+            Solver::reference(&parsed, 2).unwrap().unwrap(),
+            "167409079868000"
+        );
     }
 }
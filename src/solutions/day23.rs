@@ -4,16 +4,148 @@ use crate::grid::{Direction, Grid, Point};
 use crate::solutions::prelude::*;
 use crate::utils::IdAssigner;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
-    let graph = build_graph(&grid, true)?;
-    longest_path(&graph).map(|x| x.to_string())
+pub struct Solver;
+
+const MAX_INTERSECTIONS: Param<usize> = Param::new(
+    "max-intersections",
+    64,
+    "most intersections to search for the longest hike, it takes exponential time",
+);
+
+impl Solution for Solver {
+    type Parsed = Grid<Cell>;
+
+    const PARAMS: &'static [&'static dyn ParamInfo] = &[&MAX_INTERSECTIONS];
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        Grid::try_new(parse!(input))
+    }
+
+    fn part1(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let graph = build_graph(grid, true)?;
+        longest_path(&graph).map(Answer::from)
+    }
+
+    fn part2(grid: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let graph = build_graph(grid, false)?;
+        longest_path(&graph).map(Answer::from)
+    }
+
+    fn render(grid: &Self::Parsed, part: usize) -> Result<Option<Canvas>, anyhow::Error> {
+        let slippery = part == 1;
+        let graph = build_graph(grid, slippery)?;
+        let route = longest_route(&graph).ok_or_else(|| no_solution!("no solution"))?;
+
+        // Walk each edge of the route again to find the tiles in between. When
+        // two trails join the same intersections the route took the longer.
+        let mut hike = vec![graph.points[graph.start_id]];
+        for w in route.windows(2) {
+            let (from, to) = (graph.points[w[0]], graph.points[w[1]]);
+            let trail = Direction::iter()
+                .filter_map(|d| {
+                    let mut trail = Vec::new();
+                    let (end, _) = walk_straight(grid, from, d, slippery, |p| trail.push(p))?;
+                    (end == to).then_some(trail)
+                })
+                .max_by_key(|trail| trail.len())
+                .ok_or_else(|| internal!("no trail from {:?} to {:?}", from, to))?;
+            hike.extend(trail);
+        }
+
+        let mut canvas = Canvas::new(grid, |c| match c {
+            Cell::Empty => Color::rgb(200, 180, 140),
+            Cell::Slope(_) => Color::rgb(140, 200, 230),
+            Cell::Wall => Color::rgb(30, 70, 40),
+        });
+        canvas
+            .overlay("hike", Color::rgb(220, 40, 40), 255, hike)
+            .overlay("intersections", Color::rgb(250, 230, 80), 255, graph.points);
+        Ok(Some(canvas))
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        Some(generate_trails(gen))
+    }
 }
 
-pub fn problem2(input: &str) -> Result<String, anyhow::Error> {
-    let grid = parse!(input);
-    let graph = build_graph(&grid, false)?;
-    longest_path(&graph).map(|x| x.to_string())
+// Lays out a square lattice of intersections with a trail to each right and
+// lower neighbour. Slopes next to the intersections only let the trails be
+// walked right and down, like the real map. Trails take a detour into the
+// block above or to the left of them, horizontal ones in the lower left of
+// the block and vertical ones in the upper right, so they never touch.
+fn generate_trails(gen: &mut InputGenerator) -> String {
+    const GAP: usize = 10;
+
+    let junctions = ((gen.size().max(GAP) - 5) / GAP + 1).clamp(2, 6);
+    let xs: Vec<usize> = (0..junctions).map(|i| 1 + i * GAP).collect();
+    let ys: Vec<usize> = (0..junctions).map(|j| 2 + j * GAP).collect();
+    let (cols, rows) = (xs[junctions - 1] + 2, ys[junctions - 1] + 3);
+
+    let mut cells = vec![vec!['#'; cols]; rows];
+    let mut dig = |x: usize, y: usize| cells[y][x] = '.';
+    // Start and end
+    for y in 0..ys[0] {
+        dig(xs[0], y);
+    }
+    for y in ys[junctions - 1]..rows {
+        dig(xs[junctions - 1], y);
+    }
+
+    let mut slopes = Vec::new();
+    for j in 0..junctions {
+        for i in 0..junctions {
+            if i + 1 < junctions {
+                let (x0, x1, y) = (xs[i], xs[i + 1], ys[j]);
+                slopes.extend([(x0 + 1, y, '>'), (x1 - 1, y, '>')]);
+                let detour = (j > 0 && gen.gen_bool(0.7)).then(|| {
+                    let from = gen.gen_range(x0 + 2..x0 + GAP / 2 - 2);
+                    let to = gen.gen_range(from + 2..x0 + GAP / 2);
+                    (from, to, gen.gen_range(1..GAP / 2))
+                });
+                for x in x0..=x1 {
+                    match detour {
+                        Some((from, to, up)) if (from..=to).contains(&x) => {
+                            if x == from || x == to {
+                                (y - up..y).for_each(|y| dig(x, y));
+                            }
+                            dig(x, y - up);
+                            if x == from || x == to {
+                                dig(x, y);
+                            }
+                        }
+                        _ => dig(x, y),
+                    }
+                }
+            }
+            if j + 1 < junctions {
+                let (y0, y1, x) = (ys[j], ys[j + 1], xs[i]);
+                slopes.extend([(x, y0 + 1, 'v'), (x, y1 - 1, 'v')]);
+                let detour = (i > 0 && gen.gen_bool(0.7)).then(|| {
+                    let from = gen.gen_range(y0 + 2..y0 + GAP / 2 - 2);
+                    let to = gen.gen_range(from + 2..y0 + GAP / 2);
+                    (from, to, gen.gen_range(1..GAP / 2))
+                });
+                for y in y0..=y1 {
+                    match detour {
+                        Some((from, to, left)) if (from..=to).contains(&y) => {
+                            if y == from || y == to {
+                                (x - left..x).for_each(|x| dig(x, y));
+                                dig(x, y);
+                            }
+                            dig(x - left, y);
+                        }
+                        _ => dig(x, y),
+                    }
+                }
+            }
+        }
+    }
+    for (x, y, slope) in slopes {
+        cells[y][x] = slope;
+    }
+
+    let rows: Vec<String> = cells.into_iter().map(|r| r.into_iter().collect()).collect();
+    rows.join("\n")
 }
 
 fn longest_path(graph: &Graph) -> anyhow::Result<usize> {
@@ -38,12 +170,40 @@ fn longest_path(graph: &Graph) -> anyhow::Result<usize> {
         max_steps
     }
 
-    rec(
+    let steps = rec(
         graph,
         graph.start_id,
         vec![false; graph.nodes.len()].as_mut_slice(),
     )
-    .ok_or_else(|| anyhow!("no solution"))
+    .ok_or_else(|| no_solution!("no solution"))?;
+    Ok(steps)
+}
+
+// Like `longest_path` but returns the nodes of the route. Slower, so it is only
+// used for drawing.
+fn longest_route(graph: &Graph) -> Option<Vec<usize>> {
+    fn rec(graph: &Graph, cur: usize, steps: usize, path: &mut Vec<usize>, best: &mut (usize, Vec<usize>)) {
+        if path.contains(&cur) {
+            return;
+        }
+        path.push(cur);
+
+        if cur == graph.end_id {
+            if steps >= best.0 {
+                *best = (steps, path.clone());
+            }
+        } else {
+            for edge in &graph.nodes[cur].edges {
+                rec(graph, edge.to, steps + edge.distance, path, best);
+            }
+        }
+
+        path.pop();
+    }
+
+    let mut best = (0, Vec::new());
+    rec(graph, graph.start_id, 0, &mut Vec::new(), &mut best);
+    (!best.1.is_empty()).then_some(best.1)
 }
 
 fn build_graph(grid: &Grid<Cell>, slippery: bool) -> anyhow::Result<Graph> {
@@ -51,11 +211,11 @@ fn build_graph(grid: &Grid<Cell>, slippery: bool) -> anyhow::Result<Graph> {
     let start = (0..cols)
         .map(|c| Point::new(c, 0))
         .find(|p| grid.get(*p) == Some(&Cell::Empty))
-        .ok_or_else(|| anyhow!("cannot find start"))?;
+        .ok_or_else(|| invalid_input!("cannot find start"))?;
     let end = (0..cols)
         .map(|c| Point::new(c, rows - 1))
         .find(|p| grid.get(*p) == Some(&Cell::Empty))
-        .ok_or_else(|| anyhow!("cannot find end"))?;
+        .ok_or_else(|| invalid_input!("cannot find end"))?;
 
     let mut frontier = vec![start];
     let mut nodes = Vec::new();
@@ -72,7 +232,7 @@ fn build_graph(grid: &Grid<Cell>, slippery: bool) -> anyhow::Result<Graph> {
 
         let edges = Direction::iter()
             .filter_map(|d| {
-                let (to, distance) = walk_straight(grid, intersection, d, slippery)?;
+                let (to, distance) = walk_straight(grid, intersection, d, slippery, |_| ())?;
 
                 if seen_intersections.insert(to) {
                     frontier.push(to);
@@ -91,19 +251,35 @@ fn build_graph(grid: &Grid<Cell>, slippery: bool) -> anyhow::Result<Graph> {
         nodes[id] = Node { edges }
     }
 
+    if nodes.len() > MAX_INTERSECTIONS.get() {
+        bail!(invalid_input!(
+            "the trails meet at {} intersections, more than max-intersections {}",
+            nodes.len(),
+            MAX_INTERSECTIONS.get()
+        ));
+    }
+
+    let mut points = vec![start; id_assigner.next_id];
+    for p in seen_intersections {
+        points[id_assigner.lookup_or_assign(p)] = p;
+    }
+
     Ok(Graph {
         nodes,
+        points,
         start_id,
         end_id,
     })
 }
 
-// Walk until reaching another intersection.
+// Walk until reaching another intersection. Every point stepped on is passed
+// to `visit`, including the intersection reached.
 fn walk_straight(
     grid: &Grid<Cell>,
     start: Point,
     dir: Direction,
     slippery: bool,
+    mut visit: impl FnMut(Point),
 ) -> Option<(Point, usize)> {
     let (_, rows) = grid.size();
 
@@ -123,17 +299,19 @@ fn walk_straight(
     }
 
     loop {
+        visit(cur);
+
         if cur.y == 0 || cur.y == rows - 1 {
             // We have reached a start/end node.
             return Some((cur, steps));
         }
 
         let mut next_places = Direction::iter()
-            .map(|d| (d, cur.next(d).unwrap()))
-            .filter(|(dir, p)| match grid.get(*p).unwrap() {
-                Cell::Empty => true,
-                Cell::Slope(d) => d == dir || !slippery,
-                Cell::Wall => false,
+            .filter_map(|d| Some((d, cur.next(d)?)))
+            .filter(|(dir, p)| match grid.get(*p) {
+                Some(Cell::Empty) => true,
+                Some(Cell::Slope(d)) => d == dir || !slippery,
+                Some(Cell::Wall) | None => false,
             })
             .filter(|(_, p)| p != &last);
 
@@ -156,6 +334,8 @@ fn walk_straight(
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct Graph {
     nodes: Vec<Node>,
+    // Where each node is on the grid
+    points: Vec<Point>,
 
     start_id: usize,
     end_id: usize,
@@ -173,7 +353,7 @@ struct Edge {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Cell {
+pub enum Cell {
     Empty,
     Slope(Direction),
     Wall,
@@ -183,7 +363,7 @@ mod parser {
     use super::*;
     use crate::parser::prelude::*;
 
-    pub fn parse(input: &str) -> IResult<&str, Grid<Cell>> {
+    pub fn parse(input: &str) -> IResult<&str, Vec<Vec<Cell>>> {
         let cell = alt((
             value(Cell::Empty, char('.')),
             value(Cell::Wall, char('#')),
@@ -193,16 +373,14 @@ mod parser {
             value(Cell::Slope(Direction::Down), char('^')),
         ));
         let row = ws_line(many1(cell));
-        let grid = many1(row).map(|d| Grid::new(d));
-        ws_all_consuming(grid)(input)
+        ws_all_consuming(many1(row))(input)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use nom::Finish;
-
     use super::*;
+    use crate::params;
 
     const EXAMPLE_INPUT: &str = "
         #.#####################
@@ -232,18 +410,59 @@ mod tests {
 
     #[test]
     fn walk_straight_test() {
-        let grid = parser::parse(EXAMPLE_INPUT).finish().unwrap().1;
-        let res = walk_straight(&grid, Point::new(1, 0), Direction::Up, true);
+        let grid = Solver::parse(EXAMPLE_INPUT).unwrap();
+        let mut trail = Vec::new();
+        let res = walk_straight(&grid, Point::new(1, 0), Direction::Up, true, |p| {
+            trail.push(p)
+        });
         assert_eq!(res, Some((Point::new(3, 5), 15)));
+        assert_eq!(trail.len(), 15);
+        assert_eq!(trail.last(), Some(&Point::new(3, 5)));
     }
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "94")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "94")
     }
 
     #[test]
     fn problem2_test() {
-        assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "154")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "154")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        // Trails may run along the sides of the map
+        assert_eq!(Solver::problem2(".##\n..#\n#..").unwrap(), "3");
+
+        let err = Solver::parse("#.#\n#.\n#.#").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 2 of the grid has 2 cells, but row 1 has 3"
+        );
+
+        let args = ["max-intersections=8".to_owned()];
+        let overrides = params::Overrides::parse(Solver::PARAMS, &args).unwrap();
+        params::with(&overrides, || {
+            let err = Solver::problem2(EXAMPLE_INPUT).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "the trails meet at 9 intersections, more than max-intersections 8"
+            );
+        });
+    }
+
+    #[test]
+    fn render_test() {
+        let grid = Solver::parse(EXAMPLE_INPUT).unwrap();
+        for (part, steps) in [(1, 94), (2, 154)] {
+            let canvas = Solver::render(&grid, part).unwrap().unwrap();
+            let hike = Color::rgb(220, 40, 40);
+            let on_hike = canvas.pixels().into_iter().filter(|&c| c == hike).count();
+            // The start and every step, less the intersections drawn over it
+            let graph = build_graph(&grid, part == 1).unwrap();
+            let route = longest_route(&graph).unwrap();
+            assert_eq!(on_hike, steps + 1 - route.len());
+        }
     }
 }
@@ -1,15 +1,88 @@
 use crate::solutions::prelude::*;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let vectors = parse!(input);
+pub struct Solver;
+
+const AREA_MIN: Param<f64> = Param::new(
+    "area-min",
+    200000000000000.0,
+    "lowest X and Y of the test area in part 1",
+);
+const AREA_MAX: Param<f64> = Param::new(
+    "area-max",
+    400000000000000.0,
+    "highest X and Y of the test area in part 1",
+);
+
+impl Solution for Solver {
+    type Parsed = Vec<Vector3>;
+
+    const PARAMS: &'static [&'static dyn ParamInfo] = &[&AREA_MIN, &AREA_MAX];
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let vectors: Vec<Vector3> = parse!(input);
+        // Paths are compared by their slopes, which vertical ones have none of
+        if let Some(i) = vectors.iter().position(|v| v.velocity.x == 0) {
+            bail!(invalid_input!("hailstone {}: X velocity is 0", i + 1));
+        }
+        Ok(vectors)
+    }
 
-    let ans = count_overlaps_test_area(&vectors, 200000000000000.0, 400000000000000.0);
+    fn part1(vectors: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let ans = count_overlaps_test_area(vectors, AREA_MIN.get(), AREA_MAX.get());
 
-    Ok(ans.to_string())
+        Ok(ans.into())
+    }
+
+    fn part2(_vectors: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        bail!(Error::NotImplemented)
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        Some(generate_hail(gen).1)
+    }
 }
 
-pub fn problem2(_input: &str) -> Result<String, anyhow::Error> {
-    bail!("not yet implemented")
+// Throws a rock first and then places each hailstone where the rock will hit
+// it at a time of its own, so a rock that hits every hailstone exists. Returns
+// the rock along with the input.
+fn generate_hail(gen: &mut InputGenerator) -> (Vector3, String) {
+    let rock: [isize; 3] = [(); 3].map(|_| gen.gen_range(150_000_000_000_000..=350_000_000_000_000));
+    let rock_velocity: [isize; 3] = [(); 3].map(|_| gen.gen_range(-300..=300));
+
+    let mut times = ahash::AHashSet::new();
+    let mut lines = Vec::new();
+    while lines.len() < gen.size().max(3) {
+        let t = gen.gen_range(100_000_000_000..=500_000_000_000);
+        let velocity = [(); 3].map(|_| {
+            let v = gen.gen_range(-300..300);
+            // Moving along every axis keeps the paths from being vertical
+            if v >= 0 {
+                v + 1
+            } else {
+                v
+            }
+        });
+        let location: Vec<isize> = (0..3)
+            .map(|i| rock[i] + (rock_velocity[i] - velocity[i]) * t)
+            .collect();
+        if location.iter().any(|&x| x < 0) || velocity == rock_velocity || !times.insert(t) {
+            continue;
+        }
+        lines.push(format!(
+            "{}, {}, {} @ {}, {}, {}",
+            location[0], location[1], location[2], velocity[0], velocity[1], velocity[2]
+        ));
+    }
+    let point3 = |p: [isize; 3]| Point3 {
+        x: p[0],
+        y: p[1],
+        z: p[2],
+    };
+    let rock = Vector3 {
+        location: point3(rock),
+        velocity: point3(rock_velocity),
+    };
+    (rock, lines.join("\n"))
 }
 
 fn count_overlaps_test_area(vectors: &[Vector3], min_pos: f64, max_pos: f64) -> usize {
@@ -37,7 +110,7 @@ struct Point3 {
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-struct Vector3 {
+pub struct Vector3 {
     location: Point3,
     velocity: Point3,
 }
@@ -98,6 +171,8 @@ mod tests {
     use nom::Finish;
 
     use super::*;
+    use crate::generate::InputGenerator;
+    use crate::params;
 
     const EXAMPLE_INPUT: &str = "
         19, 13, 30 @ -2,  1, -2
@@ -111,10 +186,62 @@ mod tests {
     fn problem1_test() {
         let vecs = parser::parse(EXAMPLE_INPUT).finish().unwrap().1;
         assert_eq!(count_overlaps_test_area(&vecs, 7.0, 27.0), 2);
+
+        let args = ["area-min=7".to_owned(), "area-max=27".to_owned()];
+        let overrides = params::Overrides::parse(Solver::PARAMS, &args).unwrap();
+        params::with(&overrides, || {
+            assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "2")
+        });
     }
 
     #[test]
     fn problem2_test() {
-        //assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "")
+        //assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), "")
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::parse("19, 13, 30 @ -2, 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to parse input at line 1, column 19: unexpected end of input"
+        );
+
+        let err = Solver::parse("19, 13, 30 @ -2, 1, -2\n18, 19, 22 @ 0, -1, -2").unwrap_err();
+        assert_eq!(err.to_string(), "hailstone 2: X velocity is 0");
+    }
+
+    #[test]
+    fn generate_test() {
+        for seed in 0..24 {
+            let mut gen = InputGenerator::new(seed, [5, 12, 30][seed as usize % 3]);
+            let (rock, input) = generate_hail(&mut gen);
+            let stones = Solver::parse(&input).unwrap();
+            assert_eq!(stones.len(), gen.size().max(3));
+
+            let mut times = ahash::AHashSet::new();
+            for stone in stones {
+                let axes = |v: Vector3| {
+                    let (l, d) = (v.location, v.velocity);
+                    [(l.x, d.x), (l.y, d.y), (l.z, d.z)]
+                };
+                let pairs = axes(rock).into_iter().zip(axes(stone));
+                // Along an axis where both move alike they must start level,
+                // otherwise the gap closes at one time for every axis
+                let mut hit = None;
+                for ((rock_at, rock_speed), (at, speed)) in pairs {
+                    let (gap, closing) = (at - rock_at, rock_speed - speed);
+                    if closing == 0 {
+                        assert_eq!(gap, 0, "seed {}: {:?}", seed, stone);
+                        continue;
+                    }
+                    assert_eq!(gap % closing, 0, "seed {}: {:?}", seed, stone);
+                    let t = gap / closing;
+                    assert_eq!(*hit.get_or_insert(t), t, "seed {}: {:?}", seed, stone);
+                }
+                let t = hit.unwrap_or_else(|| panic!("seed {}: rock rides along {:?}", seed, stone));
+                assert!(t > 0 && times.insert(t), "seed {}: time {}", seed, t);
+            }
+        }
     }
 }
@@ -1,32 +1,260 @@
+use ahash::{AHashMap, AHashSet};
 use rand::prelude::*;
 
 use crate::solutions::prelude::*;
+use crate::utils::IdAssigner;
 
-pub fn problem1(input: &str) -> Result<String, anyhow::Error> {
-    let graph = parse!(input);
-    loop {
-        let ((a, b), conns) = karger(&graph);
-        if conns <= 3 {
-            return Ok((a * b).to_string());
+pub struct Solver;
+
+const MAX_TRIALS: Param<usize> = Param::new(
+    "max-trials",
+    2000,
+    "random cuts to try before deciding no three wires split the components",
+);
+
+impl Solution for Solver {
+    type Parsed = Graph;
+
+    const PARAMS: &'static [&'static dyn ParamInfo] = &[&MAX_TRIALS];
+
+    fn parse(input: &str) -> Result<Self::Parsed, anyhow::Error> {
+        let lines: Vec<(&str, Vec<&str>)> = parse!(input);
+        let mut id_assigner = IdAssigner::default();
+        let mut edges = Vec::new();
+        for (a, bs) in lines {
+            for b in bs {
+                if a == b {
+                    bail!(invalid_input!("component {} is wired to itself", a));
+                }
+                edges.push((
+                    id_assigner.lookup_or_assign(a),
+                    id_assigner.lookup_or_assign(b),
+                ));
+            }
         }
+        let graph = Graph {
+            edges,
+            num_nodes: id_assigner.next_id,
+        };
+
+        let groups = num_groups(&graph);
+        if groups > 1 {
+            bail!(invalid_input!(
+                "the components fall apart into {} groups without cutting any wires",
+                groups
+            ));
+        }
+        Ok(graph)
+    }
+
+    fn part1(graph: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        let max_trials = max_trials()?;
+        for trials in 1..=max_trials {
+            let ((a, b), conns) = {
+                let _span = trace::span("karger");
+                karger(graph)
+            };
+            if conns <= 3 {
+                trace::count("trials", trials as u64);
+                return Ok((a * b).into());
+            }
+        }
+        bail!(no_solution!("no cut of at most 3 wires found in {} trials", max_trials));
+    }
+
+    fn part2(_graph: &Self::Parsed) -> Result<Answer, anyhow::Error> {
+        Ok(Answer::None)
+    }
+
+    fn animate(graph: &Self::Parsed, part: usize) -> Result<Option<Frames<'_>>, anyhow::Error> {
+        if part != 1 {
+            return Ok(None);
+        }
+
+        let max_trials = max_trials()?;
+        let merges_per_frame = (graph.num_nodes / 20).max(1);
+        let mut contraction = Contraction::new(graph);
+        let mut trial = 1;
+        let mut found = false;
+        let frames = std::iter::from_fn(move || {
+            if found {
+                return None;
+            }
+
+            let mut finished = false;
+            for _ in 0..merges_per_frame {
+                if !contraction.step() {
+                    finished = true;
+                    break;
+                }
+            }
+
+            let ((a, b), conns) = contraction.cut();
+            let caption = if !finished {
+                format!(
+                    "trial {}: {} groups, {} edges between them",
+                    trial, contraction.remaining_nodes, conns
+                )
+            } else if conns <= 3 {
+                found = true;
+                format!(
+                    "trial {}: cut {} edges into groups of {} and {}, {} * {} = {}",
+                    trial,
+                    conns,
+                    a,
+                    b,
+                    a,
+                    b,
+                    a * b
+                )
+            } else if trial >= max_trials {
+                found = true;
+                format!("trial {}: cut {} edges, giving up", trial, conns)
+            } else {
+                format!("trial {}: cut {} edges, trying again", trial, conns)
+            };
+            let frame = Frame::new(caption, contraction.draw());
+
+            if finished && !found {
+                contraction = Contraction::new(graph);
+                trial += 1;
+            }
+            Some(frame)
+        });
+        Ok(Some(Box::new(frames)))
+    }
+
+    fn generate(gen: &mut InputGenerator) -> Option<String> {
+        Some(generate_wiring(gen).1)
     }
 }
 
-pub fn problem2(_input: &str) -> Result<String, anyhow::Error> {
-    Ok("Push the button".to_owned())
+fn max_trials() -> Result<usize, anyhow::Error> {
+    match MAX_TRIALS.get() {
+        0 => bail!(invalid_input!("at least 1 trial is needed to find a cut")),
+        trials => Ok(trials),
+    }
+}
+
+// Two groups of components joined by three wires. Inside a group every
+// component is wired in a ring and to others at random until it has five
+// wires, so cutting any other three wires leaves the groups whole. Returns the
+// answer this plants along with the input.
+fn generate_wiring(gen: &mut InputGenerator) -> (usize, String) {
+    let n = gen.size().max(12);
+    let names = gen.words(n, 3, &[]);
+    let split = gen.gen_range(6..=n - 6);
+
+    let mut wires = AHashSet::new();
+    let mut degrees = vec![0; n];
+    let wire = |wires: &mut AHashSet<_>, degrees: &mut [usize], a: usize, b: usize| {
+        if a != b && !wires.contains(&(b, a)) && wires.insert((a, b)) {
+            degrees[a] += 1;
+            degrees[b] += 1;
+        }
+    };
+    for group in [0..split, split..n] {
+        let len = group.len();
+        for a in group.clone() {
+            let next = group.start + (a - group.start + 1) % len;
+            wire(&mut wires, &mut degrees, a, next);
+        }
+        for a in group.clone() {
+            while degrees[a] < 5 {
+                let b = gen.gen_range(group.clone());
+                wire(&mut wires, &mut degrees, a, b);
+            }
+        }
+    }
+    let left = rand::seq::index::sample(gen, split, 3);
+    let right = rand::seq::index::sample(gen, n - split, 3);
+    for (a, b) in left.iter().zip(right.iter()) {
+        wire(&mut wires, &mut degrees, a, split + b);
+    }
+
+    // Each wire is listed under one of its ends
+    let mut by_node: AHashMap<usize, Vec<&str>> = AHashMap::new();
+    let mut wires: Vec<(usize, usize)> = wires.into_iter().collect();
+    wires.sort_unstable();
+    for (a, b) in wires {
+        let (a, b) = if gen.gen() { (a, b) } else { (b, a) };
+        by_node.entry(a).or_default().push(&names[b]);
+    }
+    let mut lines: Vec<String> = by_node
+        .into_iter()
+        .map(|(a, bs)| format!("{}: {}", names[a], bs.join(" ")))
+        .collect();
+    lines.sort_unstable();
+    lines.shuffle(gen);
+    (split * (n - split), lines.join("\n"))
 }
 
 // Randomly determines a cut of the graph. Returns the size of two subgraphs
 // and the number of connections between them.
 fn karger(graph: &Graph) -> ((usize, usize), usize) {
-    let mut edges = graph.edges.clone();
-    let mut remaining_nodes = graph.num_nodes;
-    let mut assignments = vec![None; graph.num_nodes];
-    let mut next_id = 0;
-
-    while remaining_nodes > 2 {
-        let i = thread_rng().gen_range(0..edges.len());
-        let (e1, e2) = edges[i];
+    let mut contraction = Contraction::new(graph);
+    while contraction.step() {}
+    contraction.cut()
+}
+
+// The number of groups of components wired together.
+fn num_groups(graph: &Graph) -> usize {
+    let mut neighbours = vec![Vec::new(); graph.num_nodes];
+    for &(a, b) in &graph.edges {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+    }
+
+    let mut seen = vec![false; graph.num_nodes];
+    let mut groups = 0;
+    for start in 0..graph.num_nodes {
+        if seen[start] {
+            continue;
+        }
+        groups += 1;
+        seen[start] = true;
+        let mut frontier = vec![start];
+        while let Some(n) = frontier.pop() {
+            for &m in &neighbours[n] {
+                if !seen[m] {
+                    seen[m] = true;
+                    frontier.push(m);
+                }
+            }
+        }
+    }
+    groups
+}
+
+// Merges the ends of random edges into groups until two groups are left.
+struct Contraction {
+    edges: Vec<(usize, usize)>,
+    remaining_nodes: usize,
+    // The group of each node, None until it is merged with another
+    assignments: Vec<Option<usize>>,
+    next_id: usize,
+}
+
+impl Contraction {
+    fn new(graph: &Graph) -> Self {
+        Self {
+            edges: graph.edges.clone(),
+            remaining_nodes: graph.num_nodes,
+            assignments: vec![None; graph.num_nodes],
+            next_id: 0,
+        }
+    }
+
+    // Merges the ends of a random edge. Returns false once two groups are
+    // left, or no edges are if the components are not all wired together.
+    fn step(&mut self) -> bool {
+        if self.remaining_nodes <= 2 || self.edges.is_empty() {
+            return false;
+        }
+
+        let assignments = &mut self.assignments;
+        let i = thread_rng().gen_range(0..self.edges.len());
+        let (e1, e2) = self.edges[i];
         match (assignments[e1], assignments[e2]) {
             (Some(a), Some(b)) => {
                 for assignment in assignments.iter_mut() {
@@ -38,67 +266,84 @@ fn karger(graph: &Graph) -> ((usize, usize), usize) {
             (Some(a), None) => assignments[e2] = Some(a),
             (None, Some(b)) => assignments[e1] = Some(b),
             (None, None) => {
-                assignments[e1] = Some(next_id);
-                assignments[e2] = Some(next_id);
-                next_id += 1;
+                assignments[e1] = Some(self.next_id);
+                assignments[e2] = Some(self.next_id);
+                self.next_id += 1;
             }
         };
 
-        edges.retain(|x| {
+        self.edges.retain(|x| {
             let assign_a = assignments[x.0];
             let assign_b = assignments[x.1];
 
             assign_a.zip(assign_b).map(|(a, b)| a != b).unwrap_or(true)
         });
 
-        remaining_nodes -= 1;
+        self.remaining_nodes -= 1;
+        true
+    }
+
+    // The size of the group of the first node, the size of the rest and the
+    // number of edges left between groups.
+    fn cut(&self) -> ((usize, usize), usize) {
+        let a_count = self
+            .assignments
+            .iter()
+            .filter(|&&x| x == self.assignments[0])
+            .count();
+        let b_count = self.assignments.len() - a_count;
+        ((a_count, b_count), self.edges.len())
     }
 
-    let a_count = assignments.iter().filter(|&&x| x == assignments[0]).count();
-    let b_count = assignments.len() - a_count;
-    ((a_count, b_count), edges.len())
+    // The largest groups as bars.
+    fn draw(&self) -> Vec<String> {
+        const SHOWN: usize = 10;
+        const WIDTH: usize = 60;
+
+        let mut sizes: AHashMap<usize, usize> = AHashMap::new();
+        for id in self.assignments.iter().flatten() {
+            *sizes.entry(*id).or_default() += 1;
+        }
+        let mut sizes: Vec<usize> = sizes.into_values().collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+        let total = self.assignments.len().max(1);
+        let mut lines: Vec<String> = sizes
+            .iter()
+            .take(SHOWN)
+            .map(|&n| format!("{:>5} {}", n, "█".repeat((n * WIDTH / total).max(1))))
+            .collect();
+        if sizes.len() > SHOWN {
+            lines.push(format!("{} more groups", sizes.len() - SHOWN));
+        }
+        let alone = self.assignments.iter().filter(|x| x.is_none()).count();
+        if alone > 0 {
+            lines.push(format!("{} nodes not merged yet", alone));
+        }
+        lines
+    }
 }
 
-struct Graph {
+#[derive(Debug)]
+pub struct Graph {
     edges: Vec<(usize, usize)>,
     num_nodes: usize,
 }
 
 mod parser {
-    use super::*;
     use crate::parser::prelude::*;
-    use crate::utils::IdAssigner;
 
-    pub fn parse(input: &str) -> IResult<&str, Graph> {
+    pub fn parse(input: &str) -> IResult<&str, Vec<(&str, Vec<&str>)>> {
         let node_id = alpha1;
         let line = separated_pair(node_id, tag(": "), separated_list1(space1, node_id));
-
-        let graph = many1(ws_line(line)).map(|lines| {
-            let mut id_assigner = IdAssigner::default();
-            let mut edges = Vec::new();
-
-            for (a, bs) in lines {
-                for b in bs {
-                    edges.push((
-                        id_assigner.lookup_or_assign(a),
-                        id_assigner.lookup_or_assign(b),
-                    ));
-                }
-            }
-
-            Graph {
-                edges,
-                num_nodes: id_assigner.next_id,
-            }
-        });
-
-        ws_all_consuming(graph)(input)
+        ws_all_consuming(many1(ws_line(line)))(input)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::params;
 
     const EXAMPLE_INPUT: &str = "
         jqt: rhn xhk nvd
@@ -118,11 +363,57 @@ mod tests {
 
     #[test]
     fn problem1_test() {
-        assert_eq!(problem1(EXAMPLE_INPUT).unwrap(), "54")
+        assert_eq!(Solver::problem1(EXAMPLE_INPUT).unwrap(), "54")
     }
 
     #[test]
     fn problem2_test() {
-        //assert_eq!(problem2(EXAMPLE_INPUT).unwrap(), "")
+        assert_eq!(Solver::problem2(EXAMPLE_INPUT).unwrap(), Answer::None)
+    }
+
+    #[test]
+    fn bad_input_test() {
+        let err = Solver::parse("abc: def\nghi: jkl ghi").unwrap_err();
+        assert_eq!(err.to_string(), "component ghi is wired to itself");
+
+        let err = Solver::parse("abc: def\nghi: jkl mno").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the components fall apart into 2 groups without cutting any wires"
+        );
+
+        // Every two of five components are wired together
+        let input = "a: b c d e\nb: c d e\nc: d e\nd: e";
+        let err = Solver::problem1(input).unwrap_err();
+        assert_eq!(err.to_string(), "no cut of at most 3 wires found in 2000 trials");
+
+        let overrides = params::Overrides::parse(Solver::PARAMS, &["max-trials=0".to_owned()]).unwrap();
+        params::with(&overrides, || {
+            let graph = Solver::parse(EXAMPLE_INPUT).unwrap();
+            let err = Solver::part1(&graph).unwrap_err();
+            assert_eq!(err.to_string(), "at least 1 trial is needed to find a cut");
+            assert!(Solver::animate(&graph, 1).is_err());
+        });
+    }
+
+    #[test]
+    fn animate_test() {
+        let graph = Solver::parse(EXAMPLE_INPUT).unwrap();
+        assert!(Solver::animate(&graph, 2).unwrap().is_none());
+
+        let frames: Vec<Frame> = Solver::animate(&graph, 1).unwrap().unwrap().collect();
+        let last = frames.last().unwrap();
+        assert!(last.caption.contains("cut 3 edges"));
+        assert!(last.caption.ends_with("= 54"));
+        assert_eq!(last.lines.len(), 2);
+        assert_eq!(frames[0].caption, "trial 1: 14 groups, 32 edges between them");
+    }
+
+    #[test]
+    fn generate_test() {
+        for seed in 0..24 {
+            let (planted, input) = generate_wiring(&mut InputGenerator::new(seed, 30));
+            assert_eq!(Solver::problem1(&input).unwrap(), Answer::from(planted));
+        }
     }
 }
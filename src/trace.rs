@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+// Named counters and spans recorded by a solution to explain where its time
+// goes. They are only recorded inside `collect`, so elsewhere, like while
+// benchmarking, recording costs no more than a thread local lookup. Work done
+// on other threads is not recorded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trace {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub counters: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub spans: BTreeMap<String, SpanStats>,
+}
+
+// Every time a span of the same name was entered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanStats {
+    pub calls: u64,
+    pub total_ns: u64,
+}
+
+impl Trace {
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty() && self.spans.is_empty()
+    }
+
+    pub fn merge(&mut self, other: &Trace) {
+        for (name, n) in &other.counters {
+            *self.counters.entry(name.clone()).or_default() += n;
+        }
+        for (name, s) in &other.spans {
+            let total = self.spans.entry(name.clone()).or_default();
+            total.calls += s.calls;
+            total.total_ns += s.total_ns;
+        }
+    }
+}
+
+// One line per counter and span.
+impl Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, n) in &self.counters {
+            writeln!(f, "{}: {}", name, n)?;
+        }
+        for (name, s) in &self.spans {
+            let total = Duration::from_nanos(s.total_ns);
+            writeln!(f, "{}: {} calls, {:.2?}", name, s.calls, total)?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Trace>> = const { RefCell::new(None) };
+}
+
+fn with_current(f: impl FnOnce(&mut Trace)) {
+    CURRENT.with(|c| {
+        if let Some(trace) = c.borrow_mut().as_mut() {
+            f(trace);
+        }
+    });
+}
+
+// Runs `f` and returns what it recorded.
+pub fn collect<T>(f: impl FnOnce() -> T) -> (T, Trace) {
+    let outer = CURRENT.with(|c| c.replace(Some(Trace::default())));
+    let ret = f();
+    let trace = CURRENT.with(|c| c.replace(outer)).unwrap_or_default();
+    (ret, trace)
+}
+
+// Adds `n` to a counter. Count in a local variable inside hot loops and
+// record the total once.
+pub fn count(name: &str, n: u64) {
+    with_current(|t| match t.counters.get_mut(name) {
+        Some(c) => *c += n,
+        None => {
+            t.counters.insert(name.to_owned(), n);
+        }
+    });
+}
+
+// Times the code until the returned guard is dropped.
+pub fn span(name: &'static str) -> Span {
+    Span {
+        name,
+        start: Instant::now(),
+    }
+}
+
+pub struct Span {
+    name: &'static str,
+    start: Instant,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        with_current(|t| {
+            let s = t.spans.entry(self.name.to_owned()).or_default();
+            s.calls += 1;
+            s.total_ns += elapsed.as_nanos() as u64;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_test() {
+        // Nothing is recorded outside of `collect`
+        count("ignored", 1);
+
+        let ((), trace) = collect(|| {
+            count("pops", 2);
+            count("pops", 3);
+            for _ in 0..2 {
+                let _span = span("trial");
+            }
+
+            let ((), inner) = collect(|| count("inner", 1));
+            assert_eq!(inner.counters["inner"], 1);
+        });
+
+        assert_eq!(trace.counters.len(), 1);
+        assert_eq!(trace.counters["pops"], 5);
+        assert_eq!(trace.spans["trial"].calls, 2);
+
+        let mut merged = trace.clone();
+        merged.merge(&trace);
+        assert_eq!(merged.counters["pops"], 10);
+        assert_eq!(merged.spans["trial"].calls, 4);
+    }
+
+    #[test]
+    fn serde_test() {
+        assert_eq!(serde_json::to_string(&Trace::default()).unwrap(), "{}");
+
+        let ((), trace) = collect(|| count("cycles", 7));
+        let json = serde_json::to_string(&trace).unwrap();
+        assert_eq!(json, r#"{"counters":{"cycles":7}}"#);
+        assert_eq!(serde_json::from_str::<Trace>(&json).unwrap(), trace);
+    }
+}
@@ -73,6 +73,10 @@ pub fn gcd(mut n: u64, mut m: u64) -> u64 {
     }
 }
 
-pub fn lcm(n: u64, m: u64) -> u64 {
-    n * m / gcd(n, m)
+// Least common multiple, or None if it does not fit.
+pub fn lcm(n: u64, m: u64) -> Option<u64> {
+    match gcd(n, m) {
+        0 => Some(0),
+        d => (n / d).checked_mul(m),
+    }
 }
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use adventofcode2023::answer::Answer;
+
+// What a file looked like when it was last polled. Missing files have no
+// stamp.
+type Stamp = Option<(SystemTime, u64)>;
+
+// Polls files for changes by comparing their modification time and size.
+// Besides single files, every file directly inside a directory can be
+// watched. Hidden files, like editor swap files, are ignored.
+pub struct Watcher {
+    files: Vec<PathBuf>,
+    dirs: Vec<PathBuf>,
+    stamps: BTreeMap<PathBuf, Stamp>,
+}
+
+impl Watcher {
+    pub fn new(files: Vec<PathBuf>, dirs: Vec<PathBuf>) -> Self {
+        Self {
+            files,
+            dirs,
+            stamps: BTreeMap::new(),
+        }
+    }
+
+    // Returns the files that changed, appeared or disappeared since the last
+    // poll in sorted order. The first poll returns every watched file.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut current: BTreeMap<PathBuf, Stamp> =
+            self.files.iter().map(|f| (f.clone(), stamp(f))).collect();
+        for dir in &self.dirs {
+            for path in list_files(dir) {
+                let s = stamp(&path);
+                current.insert(path, s);
+            }
+        }
+
+        let mut changed: Vec<PathBuf> = current
+            .iter()
+            .filter(|(path, s)| self.stamps.get(*path) != Some(s))
+            .map(|(path, _)| path.clone())
+            .collect();
+        // Files that vanished from a watched directory
+        changed.extend(
+            self.stamps
+                .keys()
+                .filter(|path| !current.contains_key(*path))
+                .cloned(),
+        );
+        changed.sort_unstable();
+
+        self.stamps = current;
+        changed
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+// Lists the visible files in a directory. A missing directory is empty.
+fn list_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| e.path())
+        .collect()
+}
+
+// Describes how the outcome of a part changed since the previous run, if
+// there was one. Failures are stored as their error message.
+pub fn describe_change(
+    previous: Option<&Result<Answer, String>>,
+    current: &Result<Answer, String>,
+) -> Option<String> {
+    match (previous?, current) {
+        (Ok(a), Ok(b)) if a == b => Some("unchanged".to_owned()),
+        (Ok(a), _) => Some(format!("was {}", a)),
+        (Err(_), Ok(_)) => Some("previously failed".to_owned()),
+        (Err(a), Err(b)) if a == b => Some("unchanged".to_owned()),
+        (Err(_), Err(_)) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poll_test() {
+        let dir = std::env::temp_dir().join(format!("aoc2023-watch-{}", std::process::id()));
+        let examples = dir.join("examples");
+        std::fs::create_dir_all(&examples).unwrap();
+        let input = dir.join("day06.txt");
+        std::fs::write(&input, "Time: 7\n").unwrap();
+        std::fs::write(examples.join("a.txt"), "").unwrap();
+        std::fs::write(examples.join(".a.txt.swp"), "").unwrap();
+
+        let mut watcher = Watcher::new(vec![input.clone()], vec![examples.clone()]);
+        assert_eq!(watcher.poll(), [input.clone(), examples.join("a.txt")]);
+        assert!(watcher.poll().is_empty());
+
+        // Sizes change as well, in case the modification times are too coarse
+        std::fs::write(&input, "Time: 7 15\n").unwrap();
+        std::fs::write(examples.join("b.txt"), "").unwrap();
+        assert_eq!(watcher.poll(), [input.clone(), examples.join("b.txt")]);
+
+        std::fs::remove_file(examples.join("a.txt")).unwrap();
+        std::fs::remove_file(&input).unwrap();
+        assert_eq!(watcher.poll(), [input, examples.join("a.txt")]);
+        assert!(watcher.poll().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn describe_change_test() {
+        let ok = |x: u64| Ok(Answer::from(x));
+        let err = |e: &str| Err(e.to_owned());
+
+        assert_eq!(describe_change(None, &ok(1)), None);
+        assert_eq!(describe_change(Some(&ok(1)), &ok(1)).unwrap(), "unchanged");
+        assert_eq!(describe_change(Some(&ok(1)), &ok(2)).unwrap(), "was 1");
+        assert_eq!(describe_change(Some(&ok(1)), &err("x")).unwrap(), "was 1");
+        assert_eq!(
+            describe_change(Some(&err("x")), &ok(2)).unwrap(),
+            "previously failed"
+        );
+        assert_eq!(describe_change(Some(&err("x")), &err("y")), None);
+    }
+}